@@ -25,7 +25,121 @@ fn query(_args: cli::CommonArgs, _query: cli::Query) -> ExitCode {
 
     error!("No data processing backend is available")
 }
-fn concat(_args: cli::CommonArgs, _query: cli::Concat) -> ExitCode {
+fn sql(_args: cli::CommonArgs, _query: cli::Sql) -> ExitCode {
+    #[cfg(feature = "polars")]
+    if _args.backend.is_polars() {
+        return polars_sql(_args, _query);
+    }
+    #[cfg(feature = "datafusion")]
+    if _args.backend.is_datafusion() {
+        return datafusion_sql(_args, _query);
+    }
+
+    error!("No data processing backend is available")
+}
+fn schema(_args: cli::CommonArgs, _query: cli::Schema) -> ExitCode {
+    #[cfg(feature = "polars")]
+    if _args.backend.is_polars() {
+        return polars_schema(_args, _query);
+    }
+    #[cfg(feature = "datafusion")]
+    if _args.backend.is_datafusion() {
+        return datafusion_schema(_args, _query);
+    }
+
+    error!("No data processing backend is available")
+}
+fn describe(_args: cli::CommonArgs, _query: cli::Describe) -> ExitCode {
+    if _query.metadata_only && !_args.filter.is_empty() {
+        error!("--metadata_only cannot be combined with --filter as footer statistics describe whole files");
+    }
+
+    #[cfg(feature = "polars")]
+    if _args.backend.is_polars() {
+        return polars_describe(_args, _query);
+    }
+    #[cfg(feature = "datafusion")]
+    if _args.backend.is_datafusion() {
+        return datafusion_describe(_args, _query);
+    }
+
+    error!("No data processing backend is available")
+}
+fn diff(_args: cli::CommonArgs, _query: cli::Diff) -> ExitCode {
+    if _query.key.is_empty() {
+        error!("diff requires at least one --key");
+    }
+
+    #[cfg(feature = "polars")]
+    if _args.backend.is_polars() {
+        return polars_diff(_args, _query);
+    }
+    #[cfg(feature = "datafusion")]
+    if _args.backend.is_datafusion() {
+        return datafusion_diff(_args, _query);
+    }
+
+    error!("No data processing backend is available")
+}
+fn count(_args: cli::CommonArgs, _query: cli::Count) -> ExitCode {
+    #[cfg(feature = "polars")]
+    if _args.backend.is_polars() {
+        return polars_count(_args, _query);
+    }
+    #[cfg(feature = "datafusion")]
+    if _args.backend.is_datafusion() {
+        return datafusion_count(_args, _query);
+    }
+
+    error!("No data processing backend is available")
+}
+
+fn inspect(_args: cli::CommonArgs, _query: cli::Inspect) -> ExitCode {
+    #[cfg(feature = "polars")]
+    if _args.backend.is_polars() {
+        return polars_inspect(_query);
+    }
+    #[cfg(feature = "datafusion")]
+    if _args.backend.is_datafusion() {
+        return datafusion_inspect(_query);
+    }
+
+    error!("No data processing backend is available")
+}
+
+#[cfg(any(feature = "polars", feature = "datafusion"))]
+//Prints internals of parquet `files` (path and its footer)
+fn print_inspect(query: &cli::Inspect, files: &[(&str, &mishka::parquet::FileMetadata)]) -> ExitCode {
+    if files.is_empty() {
+        error!("{}: No parquet files found", query.path);
+    }
+
+    let mut out = std::io::stdout().lock();
+    eprintln!("# files");
+    if let Err(error) = mishka::parquet::write_files(query.output_format, files, &mut out) {
+        error!("Unable to print files: {error}");
+    }
+    eprintln!("# columns");
+    if let Err(error) = mishka::parquet::write_column_summary(query.output_format, files, &mut out) {
+        error!("Unable to print columns: {error}");
+    }
+    if query.row_groups {
+        eprintln!("# row_groups");
+        if let Err(error) = mishka::parquet::write_column_chunks(query.output_format, files, &mut out) {
+            error!("Unable to print row groups: {error}");
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+fn concat(mut _args: cli::CommonArgs, _query: cli::Concat) -> ExitCode {
+    if _query.limit.is_some() {
+        _args.limit = _query.limit;
+        if let Err(error) = _args.validate_slice() {
+            error!("{error}");
+        }
+    }
+
     #[cfg(feature = "polars")]
     if _args.backend.is_polars() {
         return polars_concat(_args, _query);
@@ -38,29 +152,318 @@ fn concat(_args: cli::CommonArgs, _query: cli::Concat) -> ExitCode {
     error!("No data processing backend is available")
 }
 
+#[cfg(feature = "polars")]
+//Selects format of input `path`, detecting it from content if path has no hint
+fn polars_input_format(format: &mishka::ExpectFormat, path: &str) -> Result<Option<mishka::DetectedFormat>, mishka::polars::PolarsError> {
+    match format.select_or_infer(path) {
+        Some(format) => Ok(Some(mishka::DetectedFormat {
+            format,
+            compression: None,
+        })),
+        None => mishka::polars::detect_format(path),
+    }
+}
+
+#[cfg(feature = "polars")]
+fn polars_print(df: polars::prelude::LazyFrame, chunk_by: usize, format: mishka::format::OutputFormat) -> ExitCode {
+    let (state, callback) = mishka::format::polars::batch_function(format);
+    let df = match df.sink_batches(callback, false, core::num::NonZeroUsize::new(chunk_by)) {
+        Ok(df) => df,
+        Err(error) => error!("Unable to process data: {error}"),
+    };
+
+    match df.collect() {
+        Ok(_) => (),
+        Err(error) => error!("Unable to collect data: {error}"),
+    }
+    if let Err(error) = state.finish() {
+        error!("Unable to print data: {error}")
+    }
+    //Keep stdout for data only, so it can be piped
+    eprintln!("# Number of rows={}", state.row_count());
+
+    ExitCode::SUCCESS
+}
+
 #[cfg(feature = "polars")]
 fn polars_query(args: cli::CommonArgs, query: cli::Query) -> ExitCode {
-    let format = match args.format.select_or_infer(&query.path) {
-        Some(format) => format,
-        None => error!("Unable to infer file format. Please specify --format"),
+    let input = match polars_input_format(&args.format, &query.path) {
+        Ok(Some(input)) => input,
+        Ok(None) => error!("Unable to infer file format. Please specify --format"),
+        Err(error) => error!("{}: Unable to detect file format: {error}", query.path),
     };
 
-    let df = match args.into_query().create_lazy_polars(&query.path, format, &[]) {
+    let df = match args.into_query().with_compression(input.compression).create_lazy_polars(&query.path, input.format, &[]) {
         Ok(df) => df.with_streaming(true),
         Err(error) => error!("{}: {error}", query.path.as_str()),
     };
 
-    let (state, callback) = mishka::format::polars::batch_function();
-    let df = match df.sink_batches(callback, false, core::num::NonZeroUsize::new(query.chunk_by)) {
+    polars_print(df, query.chunk_by, query.output_format)
+}
+
+#[cfg(feature = "polars")]
+fn polars_sql(args: cli::CommonArgs, query: cli::Sql) -> ExitCode {
+    let input = match polars_input_format(&args.format, &query.path) {
+        Ok(Some(input)) => input,
+        Ok(None) => error!("Unable to infer file format. Please specify --format"),
+        Err(error) => error!("{}: Unable to detect file format: {error}", query.path),
+    };
+
+    let df = match args.into_query().with_compression(input.compression).create_lazy_polars(&query.path, input.format, &[]) {
+        Ok(df) => df,
+        Err(error) => error!("{}: {error}", query.path.as_str()),
+    };
+
+    let df = match mishka::polars::sql(df, &query.table, &query.statement) {
+        Ok(df) => df.with_streaming(true),
+        Err(error) => error!("Unable to execute SQL: {error}"),
+    };
+
+    polars_print(df, query.chunk_by, query.output_format)
+}
+
+#[cfg(feature = "polars")]
+fn polars_schema(args: cli::CommonArgs, query: cli::Schema) -> ExitCode {
+    let input = match polars_input_format(&args.format, &query.path) {
+        Ok(Some(input)) => input,
+        Ok(None) => error!("Unable to infer file format. Please specify --format"),
+        Err(error) => error!("{}: Unable to detect file format: {error}", query.path),
+    };
+
+    let delta_version = args.delta_version;
+    let mut df = match args.into_query().with_compression(input.compression).create_lazy_polars(&query.path, input.format, &[]) {
         Ok(df) => df,
+        Err(error) => error!("{}: {error}", query.path.as_str()),
+    };
+    let schema = match df.collect_schema() {
+        Ok(schema) => schema,
+        Err(error) => error!("{}: Unable to resolve schema: {error}", query.path),
+    };
+    let partitions = match mishka::polars::partition_columns(&query.path, input.format, delta_version) {
+        Ok(partitions) => partitions,
+        Err(error) => error!("{}: Unable to list partitions: {error}", query.path),
+    };
+
+    let columns = mishka::format::polars::columns(&schema, &partitions);
+    match mishka::format::write_columns(query.output_format, &columns, &mut std::io::stdout().lock()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => error!("Unable to print schema: {error}"),
+    }
+}
+
+#[cfg(feature = "polars")]
+fn polars_describe(args: cli::CommonArgs, query: cli::Describe) -> ExitCode {
+    let input = match polars_input_format(&args.format, &query.path) {
+        Ok(Some(input)) => input,
+        Ok(None) => error!("Unable to infer file format. Please specify --format"),
+        Err(error) => error!("{}: Unable to detect file format: {error}", query.path),
+    };
+
+    let stats = if query.metadata_only {
+        if !matches!(input.format, mishka::FileFormat::Parquet) {
+            error!("{}: --metadata_only requires parquet input", query.path);
+        }
+        let footers = match mishka::polars::read_parquet_footers(&query.path) {
+            Ok(footers) => footers.into_iter().map(|(_, footer)| footer).collect::<Vec<_>>(),
+            Err(error) => error!("{}: Unable to read parquet footers: {error}", query.path),
+        };
+        mishka::parquet::column_stats(&footers, &args.select)
+    } else {
+        let df = match args.into_query().with_compression(input.compression).create_lazy_polars(&query.path, input.format, &[]) {
+            Ok(df) => df,
+            Err(error) => error!("{}: {error}", query.path.as_str()),
+        };
+        match mishka::polars::describe(df) {
+            Ok(stats) => stats,
+            Err(error) => error!("Unable to compute statistics: {error}"),
+        }
+    };
+
+    match mishka::format::write_stats(query.output_format, &stats, &mut std::io::stdout().lock()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => error!("Unable to print statistics: {error}"),
+    }
+}
+
+#[cfg(feature = "polars")]
+//Writes `df` into single file
+fn polars_write_file(df: polars::prelude::LazyFrame, format: mishka::FileFormat, output: &str, csv: &mishka::CsvOptions) -> Result<(), polars::prelude::PolarsError> {
+    use polars::prelude::PolarsError;
+
+    let format = match format {
+        mishka::FileFormat::Csv => polars::prelude::FileWriteFormat::Csv(mishka::polars::csv_writer_options(csv)),
+        mishka::FileFormat::Parquet => polars::prelude::FileWriteFormat::Parquet(std::sync::Arc::new(mishka::polars::parquet_write_options(&mishka::ParquetWriteConfig::new())?)),
+        mishka::FileFormat::NdJson => polars::prelude::FileWriteFormat::NDJson(Default::default()),
+        mishka::FileFormat::Ipc => polars::prelude::FileWriteFormat::Ipc(Default::default()),
+        mishka::FileFormat::Json => return Err(PolarsError::InvalidOperation("JSON array output is not supported. Please use ndjson format".into())),
+        mishka::FileFormat::IpcStream => return Err(PolarsError::InvalidOperation("Arrow IPC stream output is not supported. Please use arrow format".into())),
+        mishka::FileFormat::Avro => return Err(PolarsError::InvalidOperation("Avro output is not supported".into())),
+        mishka::FileFormat::Delta => return Err(PolarsError::InvalidOperation("Delta output is not supported".into())),
+    };
+    let destination = polars::prelude::SinkDestination::File {
+        target: polars::prelude::SinkTarget::Path(polars::prelude::PlRefPath::new(output)),
+    };
+    let sink_options = polars::prelude::UnifiedSinkArgs {
+        sync_on_close: polars::prelude::sync_on_close::SyncOnCloseType::Data,
+        mkdir: true,
+        ..Default::default()
+    };
+
+    df.sink(destination, format, sink_options)?.collect()?;
+    Ok(())
+}
+
+#[cfg(feature = "polars")]
+fn polars_diff(args: cli::CommonArgs, query: cli::Diff) -> ExitCode {
+    //Output uses the same CSV dialect as input
+    let csv = args.csv.clone();
+    let mut frames = Vec::with_capacity(2);
+    for (path, args) in [(&query.left, args.clone()), (&query.right, args)] {
+        let input = match polars_input_format(&args.format, path) {
+            Ok(Some(input)) => input,
+            Ok(None) => error!("{path}: Unable to infer file format. Please specify --format"),
+            Err(error) => error!("{path}: Unable to detect file format: {error}"),
+        };
+        match args.into_query().with_compression(input.compression).create_lazy_polars(path, input.format, &[]) {
+            Ok(df) => frames.push(df),
+            Err(error) => error!("{path}: {error}"),
+        }
+    }
+    let (left, right) = match (frames.pop(), frames.pop()) {
+        (Some(right), Some(left)) => (left, right),
+        _ => error!("Unable to scan data"),
+    };
+
+    let diff = match mishka::polars::diff(left, right, &query.key) {
+        Ok(diff) => diff,
+        Err(error) => error!("Unable to compare data: {error}"),
+    };
+    if !diff.skipped.is_empty() {
+        eprintln!("# Columns present on one side only are not compared: {}", diff.skipped.join(","));
+    }
+
+    if let Some(output) = query.output.as_deref() {
+        let sink_format = query.format.select().unwrap_or(mishka::FileFormat::Parquet);
+        let results = [
+            (mishka::format::DiffKind::OnlyLeft, diff.only_left.clone()),
+            (mishka::format::DiffKind::OnlyRight, diff.only_right.clone()),
+            (mishka::format::DiffKind::Changed, diff.changes()),
+        ];
+        for (kind, df) in results {
+            let path = format!("{}/{}.{}", output.trim_end_matches('/'), kind.as_str(), sink_format.extension());
+            if let Err(error) = polars_write_file(df.with_streaming(true), sink_format, &path, &csv) {
+                error!("{path}: {error}")
+            }
+        }
+    }
+
+    if query.summary {
+        let counts = match diff.summary() {
+            Ok(counts) => counts,
+            Err(error) => error!("Unable to summarize differences: {error}"),
+        };
+        if let Err(error) = mishka::format::write_diff_summary(query.output_format, &counts, &mut std::io::stdout().lock()) {
+            error!("Unable to print summary: {error}")
+        }
+    } else if query.output.is_none() {
+        let changes = diff.changes();
+        let results = [
+            (mishka::format::DiffKind::OnlyLeft, diff.only_left),
+            (mishka::format::DiffKind::OnlyRight, diff.only_right),
+            (mishka::format::DiffKind::Changed, changes),
+        ];
+        for (kind, df) in results {
+            eprintln!("# {}", kind.as_str());
+            let code = polars_print(df.with_streaming(true), query.chunk_by, query.output_format);
+            if code != ExitCode::SUCCESS {
+                return code;
+            }
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+#[cfg(feature = "polars")]
+fn polars_count(args: cli::CommonArgs, query: cli::Count) -> ExitCode {
+    let input = match polars_input_format(&args.format, &query.path) {
+        Ok(Some(input)) => input,
+        Ok(None) => error!("Unable to infer file format. Please specify --format"),
+        Err(error) => error!("{}: Unable to detect file format: {error}", query.path),
+    };
+
+    let use_footers = matches!(input.format, mishka::FileFormat::Parquet) && !args.changes_row_count();
+    let mut df = match args.into_query().with_compression(input.compression).create_lazy_polars(&query.path, input.format, &[]) {
+        Ok(df) => df,
+        Err(error) => error!("{}: {error}", query.path.as_str()),
+    };
+
+    let mut counts = None;
+    if use_footers {
+        //Footers are only a shortcut, so data is scanned if they cannot be read (e.g. path has wildcard)
+        //Partition values are typed by scan schema, so that groups are the same either way
+        let types = match mishka::polars::literal_types(&mut df, &query.group_by) {
+            Ok(types) => types,
+            Err(error) => error!("{}: {error}", query.path.as_str()),
+        };
+        match (types, mishka::polars::read_parquet_footers(&query.path)) {
+            (Some(types), Ok(footers)) => {
+                let files = footers.iter().map(|(path, footer)| (path.as_str(), footer));
+                counts = mishka::parquet::count_rows(files, &query.group_by, &types);
+            },
+            (None, _) => (),
+            (_, Err(error)) => eprintln!("# Unable to read parquet footers, scanning data instead: {error}"),
+        }
+    }
+
+    let counts = match counts {
+        Some(counts) => counts,
+        None => match mishka::polars::count(df, &query.group_by) {
+            Ok(counts) => counts,
+            Err(error) => error!("Unable to count rows: {error}"),
+        },
+    };
+
+    match mishka::format::write_counts(query.output_format, &query.group_by, &counts, &mut std::io::stdout().lock()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => error!("Unable to print counts: {error}"),
+    }
+}
+
+#[cfg(feature = "polars")]
+fn polars_inspect(query: cli::Inspect) -> ExitCode {
+    let footers = match mishka::polars::read_parquet_footers(&query.path) {
+        Ok(footers) => footers,
+        Err(error) => error!("{}: Unable to read parquet footers: {error}", query.path),
+    };
+    let files = footers.iter().map(|(path, footer)| (path.as_str(), footer)).collect::<Vec<_>>();
+    print_inspect(&query, &files)
+}
+
+#[cfg(feature = "datafusion")]
+//Selects format of input `path`, detecting it from content if path has no hint
+async fn datafusion_input_format(format: &mishka::ExpectFormat, path: &str) -> Result<Option<mishka::DetectedFormat>, datafusion::error::DataFusionError> {
+    match format.select_or_infer(path) {
+        Some(format) => Ok(Some(mishka::DetectedFormat {
+            format,
+            compression: None,
+        })),
+        None => mishka::datafusion::detect_format(path).await,
+    }
+}
+
+#[cfg(feature = "datafusion")]
+async fn datafusion_print(df: datafusion::dataframe::DataFrame, format: mishka::format::OutputFormat) -> ExitCode {
+    let stream = match df.execute_stream_partitioned().await {
+        Ok(stream) => stream,
         Err(error) => error!("Unable to process data: {error}"),
     };
 
-    match df.collect() {
-        Ok(_) => println!("# Number of rows={}", state.row_count()),
+    match mishka::format::datafusion::format_partitioned_data(stream, format).await {
+        //Keep stdout for data only, so it can be piped
+        Ok(count) => eprintln!("# Number of rows={count}"),
         Err(error) => error!("Unable to collect data: {error}"),
     }
-
     ExitCode::SUCCESS
 }
 
@@ -71,9 +474,37 @@ fn datafusion_query(args: cli::CommonArgs, query: cli::Query) -> ExitCode {
         Err(error) => error!("Cannot initialize event loop: {error}"),
     };
 
-    let format = match args.format.select_or_infer(&query.path) {
-        Some(format) => format,
-        None => error!("Unable to infer file format. Please specify --format"),
+    let input = match rt.block_on(datafusion_input_format(&args.format, &query.path)) {
+        Ok(Some(input)) => input,
+        Ok(None) => error!("Unable to infer file format. Please specify --format"),
+        Err(error) => error!("{}: Unable to detect file format: {error}", query.path),
+    };
+
+    let mut cfg = mishka::datafusion::SessionConfig::new();
+    if let Ok(chunk_by) = datafusion::config::ConfigNonZeroUsize::try_new(query.chunk_by) {
+        cfg.options_mut().execution.batch_size = chunk_by;
+    }
+    rt.block_on(async move {
+        let df = match args.into_query().with_compression(input.compression).create_lazy_datafusion(cfg, &query.path, input.format, &[]).await {
+            Ok(df) => df,
+            Err(error) => error!("{}: {error}", query.path)
+        };
+
+        datafusion_print(df, query.output_format).await
+    })
+}
+
+#[cfg(feature = "datafusion")]
+fn datafusion_sql(args: cli::CommonArgs, query: cli::Sql) -> ExitCode {
+    let rt = match tokio::runtime::Builder::new_current_thread().enable_time().enable_io().build() {
+        Ok(rt) => rt,
+        Err(error) => error!("Cannot initialize event loop: {error}"),
+    };
+
+    let input = match rt.block_on(datafusion_input_format(&args.format, &query.path)) {
+        Ok(Some(input)) => input,
+        Ok(None) => error!("Unable to infer file format. Please specify --format"),
+        Err(error) => error!("{}: Unable to detect file format: {error}", query.path),
     };
 
     let mut cfg = mishka::datafusion::SessionConfig::new();
@@ -81,48 +512,295 @@ fn datafusion_query(args: cli::CommonArgs, query: cli::Query) -> ExitCode {
         cfg.options_mut().execution.batch_size = chunk_by;
     }
     rt.block_on(async move {
-        let df = match args.into_query().create_lazy_datafusion(cfg, &query.path, format, &[]).await {
+        let df = match args.into_query().with_compression(input.compression).create_lazy_datafusion(cfg, &query.path, input.format, &[]).await {
             Ok(df) => df,
             Err(error) => error!("{}: {error}", query.path)
         };
 
-        let stream = match df.execute_stream_partitioned().await {
-            Ok(stream) => stream,
-            Err(error) => error!("Unable to process data: {error}"),
+        let df = match mishka::datafusion::sql(df, &query.table, &query.statement).await {
+            Ok(df) => df,
+            Err(error) => error!("Unable to execute SQL: {error}"),
         };
 
-        match mishka::format::datafusion::format_partitioned_data(stream).await {
-            Ok(count) => println!("# Number of rows={count}"),
-            Err(error) => error!("Unable to collect data: {error}"),
+        datafusion_print(df, query.output_format).await
+    })
+}
+
+#[cfg(feature = "datafusion")]
+fn datafusion_schema(args: cli::CommonArgs, query: cli::Schema) -> ExitCode {
+    let rt = match tokio::runtime::Builder::new_current_thread().enable_time().enable_io().build() {
+        Ok(rt) => rt,
+        Err(error) => error!("Cannot initialize event loop: {error}"),
+    };
+
+    let input = match rt.block_on(datafusion_input_format(&args.format, &query.path)) {
+        Ok(Some(input)) => input,
+        Ok(None) => error!("Unable to infer file format. Please specify --format"),
+        Err(error) => error!("{}: Unable to detect file format: {error}", query.path),
+    };
+
+    let cfg = mishka::datafusion::SessionConfig::new();
+    let (df, partitions) = match rt.block_on(args.into_query().with_compression(input.compression).create_lazy_datafusion_with_partitions(cfg, &query.path, input.format, &[])) {
+        Ok(result) => result,
+        Err(error) => error!("{}: {error}", query.path),
+    };
+
+    let columns = mishka::format::datafusion::columns(df.schema().as_arrow(), &partitions);
+    match mishka::format::write_columns(query.output_format, &columns, &mut std::io::stdout().lock()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => error!("Unable to print schema: {error}"),
+    }
+}
+
+#[cfg(feature = "datafusion")]
+fn datafusion_describe(args: cli::CommonArgs, query: cli::Describe) -> ExitCode {
+    let rt = match tokio::runtime::Builder::new_current_thread().enable_time().enable_io().build() {
+        Ok(rt) => rt,
+        Err(error) => error!("Cannot initialize event loop: {error}"),
+    };
+
+    let input = match rt.block_on(datafusion_input_format(&args.format, &query.path)) {
+        Ok(Some(input)) => input,
+        Ok(None) => error!("Unable to infer file format. Please specify --format"),
+        Err(error) => error!("{}: Unable to detect file format: {error}", query.path),
+    };
+
+    let stats = if query.metadata_only {
+        if !matches!(input.format, mishka::FileFormat::Parquet) {
+            error!("{}: --metadata_only requires parquet input", query.path);
         }
+        let footers = match rt.block_on(mishka::datafusion::read_parquet_footers(&query.path)) {
+            Ok(footers) => footers.into_iter().map(|(_, footer)| footer).collect::<Vec<_>>(),
+            Err(error) => error!("{}: Unable to read parquet footers: {error}", query.path),
+        };
+        mishka::parquet::column_stats(&footers, &args.select)
+    } else {
+        let path = query.path.as_str();
+        let result = rt.block_on(async move {
+            let df = args.into_query().with_compression(input.compression).create_lazy_datafusion(mishka::datafusion::SessionConfig::new(), path, input.format, &[]).await?;
+            mishka::datafusion::describe(df).await
+        });
+        match result {
+            Ok(stats) => stats,
+            Err(error) => error!("{}: Unable to compute statistics: {error}", query.path),
+        }
+    };
+
+    match mishka::format::write_stats(query.output_format, &stats, &mut std::io::stdout().lock()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => error!("Unable to print statistics: {error}"),
+    }
+}
+
+#[cfg(feature = "datafusion")]
+//Writes `df` into single file
+async fn datafusion_write_file(df: datafusion::dataframe::DataFrame, format: mishka::FileFormat, output: &str, csv: &mishka::CsvOptions) -> Result<(), datafusion::error::DataFusionError> {
+    use datafusion::error::DataFusionError;
+
+    let df_opts = mishka::datafusion::DataFrameWriteOptions::new().with_single_file_output(true);
+    match format {
+        mishka::FileFormat::Csv => df.write_csv(output, df_opts, Some(mishka::datafusion::csv_write_options(csv))).await.map(drop),
+        mishka::FileFormat::Parquet => df.write_parquet(output, df_opts, Some(mishka::datafusion::parquet_write_options(&mishka::ParquetWriteConfig::new()))).await.map(drop),
+        mishka::FileFormat::NdJson => df.write_json(output, df_opts, None).await.map(drop),
+        mishka::FileFormat::Ipc => mishka::datafusion::write_arrow(df, output, Vec::new()).await,
+        mishka::FileFormat::Json => Err(DataFusionError::NotImplemented("JSON array output is not supported by datafusion. Please use ndjson format".to_owned())),
+        mishka::FileFormat::IpcStream => Err(DataFusionError::NotImplemented("Arrow IPC stream output is not supported by datafusion. Please use arrow format".to_owned())),
+        mishka::FileFormat::Avro => Err(DataFusionError::NotImplemented("Avro output is not supported".to_owned())),
+        mishka::FileFormat::Delta => Err(DataFusionError::NotImplemented("Delta output is not supported".to_owned())),
+    }
+}
+
+#[cfg(feature = "datafusion")]
+fn datafusion_diff(args: cli::CommonArgs, query: cli::Diff) -> ExitCode {
+    let rt = match tokio::runtime::Builder::new_current_thread().enable_time().enable_io().build() {
+        Ok(rt) => rt,
+        Err(error) => error!("Cannot initialize event loop: {error}"),
+    };
+
+    //Output uses the same CSV dialect as input
+    let csv = args.csv.clone();
+    rt.block_on(async move {
+        let mut frames = Vec::with_capacity(2);
+        for (path, args) in [(&query.left, args.clone()), (&query.right, args)] {
+            let input = match datafusion_input_format(&args.format, path).await {
+                Ok(Some(input)) => input,
+                Ok(None) => error!("{path}: Unable to infer file format. Please specify --format"),
+                Err(error) => error!("{path}: Unable to detect file format: {error}"),
+            };
+            let mut cfg = mishka::datafusion::SessionConfig::new();
+            if let Ok(chunk_by) = datafusion::config::ConfigNonZeroUsize::try_new(query.chunk_by) {
+                cfg.options_mut().execution.batch_size = chunk_by;
+            }
+            match args.into_query().with_compression(input.compression).create_lazy_datafusion(cfg, path, input.format, &[]).await {
+                Ok(df) => frames.push(df),
+                Err(error) => error!("{path}: {error}"),
+            }
+        }
+        let (left, right) = match (frames.pop(), frames.pop()) {
+            (Some(right), Some(left)) => (left, right),
+            _ => error!("Unable to scan data"),
+        };
+
+        let diff = match mishka::datafusion::diff(left, right, &query.right, &query.key).await {
+            Ok(diff) => diff,
+            Err(error) => error!("Unable to compare data: {error}"),
+        };
+        if !diff.skipped.is_empty() {
+            eprintln!("# Columns present on one side only are not compared: {}", diff.skipped.join(","));
+        }
+
+        if let Some(output) = query.output.as_deref() {
+            let sink_format = query.format.select().unwrap_or(mishka::FileFormat::Parquet);
+            let changes = match diff.changes().await {
+                Ok(changes) => changes,
+                Err(error) => error!("Unable to prepare changes: {error}"),
+            };
+            let results = [
+                (mishka::format::DiffKind::OnlyLeft, diff.only_left.clone()),
+                (mishka::format::DiffKind::OnlyRight, diff.only_right.clone()),
+                (mishka::format::DiffKind::Changed, changes),
+            ];
+            for (kind, df) in results {
+                let path = format!("{}/{}.{}", output.trim_end_matches('/'), kind.as_str(), sink_format.extension());
+                if let Err(error) = datafusion_write_file(df, sink_format, &path, &csv).await {
+                    error!("{path}: {error}")
+                }
+            }
+        }
+
+        if query.summary {
+            let counts = match diff.summary().await {
+                Ok(counts) => counts,
+                Err(error) => error!("Unable to summarize differences: {error}"),
+            };
+            if let Err(error) = mishka::format::write_diff_summary(query.output_format, &counts, &mut std::io::stdout().lock()) {
+                error!("Unable to print summary: {error}")
+            }
+        } else if query.output.is_none() {
+            let changes = match diff.changes().await {
+                Ok(changes) => changes,
+                Err(error) => error!("Unable to prepare changes: {error}"),
+            };
+            let results = [
+                (mishka::format::DiffKind::OnlyLeft, diff.only_left),
+                (mishka::format::DiffKind::OnlyRight, diff.only_right),
+                (mishka::format::DiffKind::Changed, changes),
+            ];
+            for (kind, df) in results {
+                eprintln!("# {}", kind.as_str());
+                let code = datafusion_print(df, query.output_format).await;
+                if code != ExitCode::SUCCESS {
+                    return code;
+                }
+            }
+        }
+
         ExitCode::SUCCESS
     })
 }
 
+#[cfg(feature = "datafusion")]
+fn datafusion_count(args: cli::CommonArgs, query: cli::Count) -> ExitCode {
+    let rt = match tokio::runtime::Builder::new_current_thread().enable_time().enable_io().build() {
+        Ok(rt) => rt,
+        Err(error) => error!("Cannot initialize event loop: {error}"),
+    };
+
+    let input = match rt.block_on(datafusion_input_format(&args.format, &query.path)) {
+        Ok(Some(input)) => input,
+        Ok(None) => error!("Unable to infer file format. Please specify --format"),
+        Err(error) => error!("{}: Unable to detect file format: {error}", query.path),
+    };
+
+    let use_footers = matches!(input.format, mishka::FileFormat::Parquet) && !args.changes_row_count();
+    let path = query.path.as_str();
+    let df = match rt.block_on(args.into_query().with_compression(input.compression).create_lazy_datafusion(mishka::datafusion::SessionConfig::new(), path, input.format, &[])) {
+        Ok(df) => df,
+        Err(error) => error!("{path}: {error}"),
+    };
+
+    let mut counts = None;
+    if use_footers {
+        //Footers are only a shortcut, so data is scanned if they cannot be read
+        //Partition values are typed by scan schema, so that groups are the same either way
+        match (mishka::datafusion::literal_types(&df, &query.group_by), rt.block_on(mishka::datafusion::read_parquet_footers(path))) {
+            (Some(types), Ok(footers)) => {
+                let files = footers.iter().map(|(path, footer)| (path.as_str(), footer));
+                counts = mishka::parquet::count_rows(files, &query.group_by, &types);
+            },
+            (None, _) => (),
+            (_, Err(error)) => eprintln!("# Unable to read parquet footers, scanning data instead: {error}"),
+        }
+    }
+
+    let counts = match counts {
+        Some(counts) => counts,
+        None => match rt.block_on(mishka::datafusion::count(df, &query.group_by)) {
+            Ok(counts) => counts,
+            Err(error) => error!("{path}: Unable to count rows: {error}"),
+        },
+    };
+
+    match mishka::format::write_counts(query.output_format, &query.group_by, &counts, &mut std::io::stdout().lock()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => error!("Unable to print counts: {error}"),
+    }
+}
+
+#[cfg(feature = "datafusion")]
+fn datafusion_inspect(query: cli::Inspect) -> ExitCode {
+    let rt = match tokio::runtime::Builder::new_current_thread().enable_time().enable_io().build() {
+        Ok(rt) => rt,
+        Err(error) => error!("Cannot initialize event loop: {error}"),
+    };
+
+    let footers = match rt.block_on(mishka::datafusion::read_parquet_footers(&query.path)) {
+        Ok(footers) => footers,
+        Err(error) => error!("{}: Unable to read parquet footers: {error}", query.path),
+    };
+    let files = footers.iter().map(|(path, footer)| (path.as_str(), footer)).collect::<Vec<_>>();
+    print_inspect(&query, &files)
+}
+
 #[cfg(feature = "polars")]
 fn polars_concat(args: cli::CommonArgs, query: cli::Concat) -> ExitCode {
     use core::fmt::Write;
     use polars::prelude::file_provider;
 
-    let format = match args.format.select_or_infer(&query.path) {
-        Some(format) => format,
-        None => error!("Unable to infer file format. Please specify --format"),
+    //Output uses the same CSV dialect as input
+    let csv = args.csv.clone();
+    let parquet_config = query.parquet_config();
+    let input = match polars_input_format(&args.format, &query.path) {
+        Ok(Some(input)) => input,
+        Ok(None) => error!("Unable to infer file format. Please specify --format"),
+        Err(error) => error!("{}: Unable to detect file format: {error}", query.path),
     };
     let sink_format = match query.format.select_or_infer(&query.output) {
         Some(format) => format,
         None => error!("Unable to infer output format. Please specify --format"),
     };
+    let compression = query.compression.or_else(|| mishka::Compression::split_path(&query.output).1);
+    if compression.is_some() && !sink_format.is_compressible() {
+        error!("{}: Compression is supported only for CSV and JSON output", query.output);
+    }
+    if let Some(compression) = compression {
+        error!("{}: Compression '{}' of output is not supported by polars. Use --backend datafusion", query.output, compression.extension());
+    }
 
     let df_partition_by = if query.read_path_partitions {
         query.partition_by.as_slice()
     } else {
         &[]
     };
-    let df = match args.into_query().with_keep_partition(query.keep_partitions).create_lazy_polars(&query.path, format, df_partition_by) {
+    let df = match args.into_query().with_keep_partition(query.keep_partitions).with_compression(input.compression).create_lazy_polars(&query.path, input.format, df_partition_by) {
         Ok(df) => df.with_streaming(true),
         Err(error) => error!("{}: {error}", query.path.as_str()),
     };
 
+    if query.partition_by.is_empty() && matches!(sink_format, mishka::FileFormat::Json | mishka::FileFormat::IpcStream) {
+        return polars_write_in_memory(df, sink_format, &query.output);
+    }
+
     let target = polars::prelude::PlRefPath::new(query.output.as_str());
 
     let destination = if query.partition_by.is_empty() {
@@ -176,20 +854,22 @@ fn polars_concat(args: cli::CommonArgs, query: cli::Concat) -> ExitCode {
 
     let format = match sink_format {
         mishka::FileFormat::Csv => {
-            let options = polars::prelude::CsvWriterOptions {
-                include_header: true,
-                ..Default::default()
-            };
-            polars::prelude::FileWriteFormat::Csv(options)
+            polars::prelude::FileWriteFormat::Csv(mishka::polars::csv_writer_options(&csv))
         }
         mishka::FileFormat::Parquet => {
-            let options = polars::prelude::ParquetWriteOptions {
-                compression: polars::prelude::ParquetCompression::Snappy,
-                ..Default::default()
+            let options = match mishka::polars::parquet_write_options(&parquet_config) {
+                Ok(options) => options,
+                Err(error) => error!("{}: {error}", query.output.as_str()),
             };
 
             polars::prelude::FileWriteFormat::Parquet(std::sync::Arc::new(options))
         }
+        mishka::FileFormat::NdJson => polars::prelude::FileWriteFormat::NDJson(Default::default()),
+        mishka::FileFormat::Ipc => polars::prelude::FileWriteFormat::Ipc(Default::default()),
+        mishka::FileFormat::Json => error!("JSON array output cannot be partitioned. Please use ndjson format"),
+        mishka::FileFormat::IpcStream => error!("Arrow IPC stream output cannot be partitioned. Please use arrow format"),
+        mishka::FileFormat::Avro => error!("Avro output is not supported"),
+        mishka::FileFormat::Delta => error!("Delta output is not supported"),
     };
 
     match df.sink(destination, format, sink_options) {
@@ -202,6 +882,31 @@ fn polars_concat(args: cli::CommonArgs, query: cli::Concat) -> ExitCode {
     ExitCode::SUCCESS
 }
 
+#[cfg(feature = "polars")]
+fn polars_write_in_memory(df: polars::prelude::LazyFrame, format: mishka::FileFormat, output: &str) -> ExitCode {
+    use polars::prelude::{IpcStreamWriter, JsonFormat, JsonWriter, SerWriter};
+
+    //Formats without streaming sink require whole data to be collected
+    let mut df = match df.collect() {
+        Ok(df) => df,
+        Err(error) => error!("Unable to collect data: {error}"),
+    };
+    let file = match std::fs::File::create(output) {
+        Ok(file) => file,
+        Err(error) => error!("{output}: Unable to create file: {error}"),
+    };
+    let result = match format {
+        mishka::FileFormat::Json => JsonWriter::new(file).with_json_format(JsonFormat::Json).finish(&mut df),
+        mishka::FileFormat::IpcStream => IpcStreamWriter::new(file).finish(&mut df),
+        format => error!("{output}: Unexpected in-memory output format '{}'", format.extension()),
+    };
+    if let Err(error) = result {
+        error!("{output}: Unable to write: {error}")
+    }
+
+    ExitCode::SUCCESS
+}
+
 #[cfg(feature = "datafusion")]
 fn datafusion_concat(args: cli::CommonArgs, query: cli::Concat) -> ExitCode {
     use core::fmt::Write;
@@ -211,14 +916,21 @@ fn datafusion_concat(args: cli::CommonArgs, query: cli::Concat) -> ExitCode {
         Err(error) => error!("Cannot initialize event loop: {error}"),
     };
 
-    let format = match args.format.select_or_infer(&query.path) {
-        Some(format) => format,
-        None => error!("Unable to infer file format. Please specify --format"),
+    //Output uses the same CSV dialect as input
+    let csv = args.csv.clone();
+    let input = match rt.block_on(datafusion_input_format(&args.format, &query.path)) {
+        Ok(Some(input)) => input,
+        Ok(None) => error!("Unable to infer file format. Please specify --format"),
+        Err(error) => error!("{}: Unable to detect file format: {error}", query.path),
     };
     let sink_format = match query.format.select_or_infer(&query.output) {
         Some(format) => format,
         None => error!("Unable to infer output format. Please specify --format"),
     };
+    let compression = query.compression.or_else(|| mishka::Compression::split_path(&query.output).1);
+    if compression.is_some() && !sink_format.is_compressible() {
+        error!("{}: Compression is supported only for CSV and JSON output", query.output);
+    }
 
     let mut cfg = mishka::datafusion::SessionConfig::new();
     let timestamp = mishka::utils::unit_now().as_secs();
@@ -240,7 +952,7 @@ fn datafusion_concat(args: cli::CommonArgs, query: cli::Concat) -> ExitCode {
             &[]
         };
 
-        let df = match args.into_query().with_keep_partition(query.keep_partitions).create_lazy_datafusion(cfg, &query.path, format, df_partition_by).await {
+        let df = match args.into_query().with_keep_partition(query.keep_partitions).with_compression(input.compression).create_lazy_datafusion(cfg, &query.path, input.format, df_partition_by).await {
             Ok(result) => result,
             Err(error) => error!("{}: {error}", query.path)
         };
@@ -248,14 +960,14 @@ fn datafusion_concat(args: cli::CommonArgs, query: cli::Concat) -> ExitCode {
         let df_opts = if query.partition_by.is_empty() {
             mishka::datafusion::DataFrameWriteOptions::new().with_single_file_output(true)
         } else {
-            mishka::datafusion::DataFrameWriteOptions::new().with_partition_by(query.partition_by)
+            mishka::datafusion::DataFrameWriteOptions::new().with_partition_by(query.partition_by.clone())
         };
 
         match sink_format {
             mishka::FileFormat::Csv => {
                 let csv_options = datafusion::config::CsvOptions {
-                    has_header: Some(true),
-                    ..Default::default()
+                    compression: mishka::datafusion::compression_type_variant(compression),
+                    ..mishka::datafusion::csv_write_options(&csv)
                 };
 
                 if let Err(error) = df.write_csv(&query.output, df_opts, Some(csv_options)).await {
@@ -263,23 +975,31 @@ fn datafusion_concat(args: cli::CommonArgs, query: cli::Concat) -> ExitCode {
                 }
             },
             mishka::FileFormat::Parquet => {
-                let parquet_options = datafusion::config::TableParquetOptions {
-                    global: datafusion::config::ParquetOptions {
-                        compression: Some("snappy".to_owned()),
-                        coerce_int96: None,
-                        //Minimize overhead of datafusion's arrow format
-                        statistics_enabled: Some("none".to_owned()),
-                        skip_arrow_metadata: true,
-                        created_by: String::new(),
-                        ..Default::default()
-                    },
+                let parquet_options = mishka::datafusion::parquet_write_options(&query.parquet_config());
+
+                if let Err(error) = df.write_parquet(&query.output, df_opts, Some(parquet_options)).await {
+                    error!("{}: {error}", query.output)
+                }
+            },
+            mishka::FileFormat::NdJson => {
+                let json_options = datafusion::config::JsonOptions {
+                    compression: mishka::datafusion::compression_type_variant(compression),
                     ..Default::default()
                 };
 
-                if let Err(error) = df.write_parquet(&query.output, df_opts, Some(parquet_options)).await {
+                if let Err(error) = df.write_json(&query.output, df_opts, Some(json_options)).await {
                     error!("{}: {error}", query.output)
                 }
-            }
+            },
+            mishka::FileFormat::Ipc => {
+                if let Err(error) = mishka::datafusion::write_arrow(df, &query.output, query.partition_by).await {
+                    error!("{}: {error}", query.output)
+                }
+            },
+            mishka::FileFormat::Json => error!("{}: JSON array output is not supported by datafusion. Please use ndjson format", query.output),
+            mishka::FileFormat::IpcStream => error!("{}: Arrow IPC stream output is not supported by datafusion. Please use arrow format", query.output),
+            mishka::FileFormat::Avro => error!("{}: Avro output is not supported", query.output),
+            mishka::FileFormat::Delta => error!("{}: Delta output is not supported", query.output),
         }
         ExitCode::SUCCESS
     })
@@ -294,9 +1014,33 @@ fn main() -> ExitCode {
                                  .init();
     }
 
-    let (args, command) = cli::args().split_parts();
+    let (mut args, command) = match cli::args().split_parts() {
+        Ok(parts) => parts,
+        Err(error) => error!("{error}"),
+    };
+    if let Err(error) = args.load_schema() {
+        error!("Unable to load schema: {error}");
+    }
+    if let Err(error) = args.validate_slice() {
+        error!("{error}");
+    }
+    if args.sample_fraction.is_some() && args.sample_n.is_some() {
+        error!("--sample_fraction cannot be combined with --sample_n");
+    }
+    if args.sample_fraction.is_some_and(|fraction| !(0.0..=1.0).contains(&fraction)) {
+        error!("--sample_fraction must be between 0 and 1");
+    }
+    if !args.sample_by.is_empty() && args.sample_n.is_none() {
+        error!("--sample_by requires --sample_n");
+    }
     match command {
         cli::Command::Query(params) => query(args, params),
         cli::Command::Concat(params) => concat(args, params),
+        cli::Command::Sql(params) => sql(args, params),
+        cli::Command::Schema(params) => schema(args, params),
+        cli::Command::Describe(params) | cli::Command::Stats(params) => describe(args, params),
+        cli::Command::Diff(params) => diff(args, params),
+        cli::Command::Count(params) => count(args, params),
+        cli::Command::Inspect(params) => inspect(args, params),
     }
 }
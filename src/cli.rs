@@ -1,120 +1,9 @@
 //! Command line arguments
 use arg::Args;
 
-use crate::{ExpectFormat, Int96Timestamp};
-
-#[derive(Debug)]
-///Operand types
-pub enum Operand {
-    ///Literal is always should be treated as constant
-    Literal(String),
-    ///Identifier assumes existing column name
-    Identifier(String),
-}
-
-impl Operand {
-    ///Parses from string identifying any quoted value as literal
-    pub fn parse(text: &str) -> Self {
-        let ident = text.trim_matches('"').trim_matches('\'');
-
-        if ident.len() == text.len() {
-            if ident.chars().all(|ch| ch.is_numeric()) {
-            Self::Literal(text.to_owned())
-            } else {
-                Self::Identifier(text.to_owned())
-            }
-        } else {
-            Self::Literal(ident.to_owned())
-        }
-    }
-}
-
-#[derive(Debug)]
-///Possible operators
-pub enum Operator {
-    ///<
-    Less,
-    ///<=
-    LessEq,
-    ///==
-    Eq,
-    /// !=
-    NotEq,
-    ///>=
-    GreaterEq,
-    ///>
-    Greater,
-}
-
-impl Operator {
-    ///Parses operator
-    pub fn parse(text: &str) -> Option<Self> {
-        match text {
-            "=" | "==" => Some(Self::Eq),
-            "!=" => Some(Self::NotEq),
-            ">" => Some(Self::Greater),
-            ">=" => Some(Self::GreaterEq),
-            "<" => Some(Self::Less),
-            "<=" => Some(Self::LessEq),
-            _ => None,
-        }
-    }
-}
-
-#[derive(Debug)]
-///Simple SQL like expression
-pub struct Expression {
-    ///Left side
-    pub left: Operand,
-    ///Operator
-    pub operator: Operator,
-    ///Right side
-    pub right: Operand,
-}
-
-impl core::str::FromStr for Expression {
-    type Err = ();
-    fn from_str(text: &str) -> Result<Self, Self::Err> {
-        let mut left = None;
-        let mut operator = None;
-        let mut right = None;
-
-        for part in text.split_whitespace() {
-            if left.is_none() {
-                left = Some(Operand::parse(part));
-            } else if operator.is_none() {
-                match Operator::parse(part) {
-                    Some(new_operator) => {
-                        operator = Some(new_operator);
-                    },
-                    None => return Err(())
-                }
-            } else if right.is_none() {
-                right = Some(Operand::parse(part));
-            } else {
-                return Err(())
-            }
-        }
-        let left = match left {
-            Some(left) => left,
-            None => return Err(()),
-        };
-        let operator = match operator {
-            Some(operator) => operator,
-            None => return Err(()),
-        };
-        let right = match right {
-            Some(right) => right,
-            None => return Err(()),
-        };
-
-        Ok(Self {
-            left,
-            operator,
-            right,
-        })
-    }
-}
+use crate::format::OutputFormat;
+use crate::{ColumnSchema, Compression, CsvOptions, DeltaVersion, ExpectFormat, Int96Timestamp, ParquetCodec, ParquetStatistics, ParquetWriteConfig};
+pub use crate::expr::{Aggregate, Aggregation, ComputedColumn, Expression, Operand, Operator};
 
 #[derive(Copy, Clone, Debug)]
 ///Backend to use
@@ -173,6 +62,204 @@ impl core::str::FromStr for Int96Timestamp {
     }
 }
 
+//Parses optional compression `level`, which must be within `range`
+fn parse_level<T: core::str::FromStr + PartialOrd>(level: Option<&str>, range: core::ops::RangeInclusive<T>, error: &'static str) -> Result<Option<T>, &'static str> {
+    match level.map(str::parse) {
+        None => Ok(None),
+        Some(Ok(level)) if range.contains(&level) => Ok(Some(level)),
+        Some(_) => Err(error),
+    }
+}
+
+impl core::str::FromStr for ParquetCodec {
+    type Err = &'static str;
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        const ERROR: &str = "Allowed values: uncompressed, snappy, gzip[:level], lz4, zstd[:level], brotli[:level]";
+
+        let (codec, level) = match text.split_once(':') {
+            Some((codec, level)) => (codec, Some(level)),
+            None => (text, None),
+        };
+
+        if codec.eq_ignore_ascii_case("uncompressed") || codec.eq_ignore_ascii_case("none") {
+            level.is_none().then_some(Self::Uncompressed).ok_or(ERROR)
+        } else if codec.eq_ignore_ascii_case("snappy") {
+            level.is_none().then_some(Self::Snappy).ok_or(ERROR)
+        } else if codec.eq_ignore_ascii_case("lz4") {
+            level.is_none().then_some(Self::Lz4).ok_or(ERROR)
+        } else if codec.eq_ignore_ascii_case("gzip") {
+            parse_level(level, 0..=9, "Gzip level must be 0-9").map(Self::Gzip)
+        } else if codec.eq_ignore_ascii_case("zstd") {
+            parse_level(level, 1..=22, "Zstd level must be 1-22").map(Self::Zstd)
+        } else if codec.eq_ignore_ascii_case("brotli") {
+            parse_level(level, 0..=11, "Brotli level must be 0-11").map(Self::Brotli)
+        } else {
+            Err(ERROR)
+        }
+    }
+}
+
+impl core::str::FromStr for ParquetStatistics {
+    type Err = &'static str;
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        if text.eq_ignore_ascii_case("none") {
+            Ok(Self::None)
+        } else if text.eq_ignore_ascii_case("chunk") {
+            Ok(Self::Chunk)
+        } else if text.eq_ignore_ascii_case("page") {
+            Ok(Self::Page)
+        } else {
+            Err("Allowed values: none, chunk, page")
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+///Single ASCII character argument
+///
+///Accepts `\t` or `tab` for tabulation
+pub struct Char(pub u8);
+
+impl core::str::FromStr for Char {
+    type Err = &'static str;
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        if text == "\\t" || text.eq_ignore_ascii_case("tab") {
+            return Ok(Self(b'\t'));
+        }
+
+        match text.as_bytes() {
+            [ch] if ch.is_ascii() => Ok(Self(*ch)),
+            _ => Err("Expected single ASCII character"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+///Sort key in format `column[:asc|desc][:nulls_first|nulls_last]`
+pub struct Sort {
+    ///Column to sort by
+    pub column: String,
+    ///Sort direction, if specified explicitly
+    pub desc: Option<bool>,
+    ///Indicates whether nulls are placed before values. Defaults to nulls last
+    pub nulls_first: bool,
+}
+
+impl core::str::FromStr for DeltaVersion {
+    type Err = String;
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        use crate::literal::{LiteralType, TimeUnit, Value};
+
+        if let Ok(version) = text.parse() {
+            return Ok(Self::Version(version));
+        }
+
+        let typ = LiteralType::Timestamp {
+            unit: TimeUnit::Millisecond,
+            timezone: None,
+        };
+        match Value::parse(text, &typ) {
+            Ok(Value::Timestamp { value, .. }) => Ok(Self::Timestamp(value)),
+            Ok(_) => Err("Expected version number or timestamp".to_owned()),
+            Err(error) => Err(format!("Expected version number or timestamp. {error}")),
+        }
+    }
+}
+
+impl core::str::FromStr for ColumnSchema {
+    type Err = String;
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let (name, dtype) = match text.split_once(':') {
+            Some((name, dtype)) if !name.is_empty() => (name, dtype),
+            _ => return Err(format!("'{text}': Expected format `column:type[:not_null]`")),
+        };
+        let (dtype, nullable) = match dtype.strip_suffix(":not_null") {
+            Some(dtype) => (dtype, false),
+            None => (dtype.strip_suffix(":nullable").unwrap_or(dtype), true),
+        };
+
+        Ok(Self {
+            name: name.to_owned(),
+            dtype: dtype.parse().map_err(|error| format!("'{text}': {error}"))?,
+            nullable,
+        })
+    }
+}
+
+//Parses column's entry of schema file: either type name or object with `type` and optional `nullable`
+fn parse_schema_column(name: String, entry: &serde_json::Value) -> Result<ColumnSchema, String> {
+    let (dtype, nullable) = match entry {
+        serde_json::Value::String(dtype) => (dtype.as_str(), true),
+        serde_json::Value::Object(entry) => match entry.get("type").and_then(serde_json::Value::as_str) {
+            Some(dtype) => (dtype, entry.get("nullable").and_then(serde_json::Value::as_bool).unwrap_or(true)),
+            None => return Err(format!("Column '{name}' has no `type`")),
+        },
+        _ => return Err(format!("Column '{name}' must be type name or object with `type`")),
+    };
+
+    match dtype.parse() {
+        Ok(dtype) => Ok(ColumnSchema {
+            name,
+            dtype,
+            nullable,
+        }),
+        Err(error) => Err(format!("Column '{name}': {error}")),
+    }
+}
+
+///Parses JSON schema
+///
+///Schema is either object mapping column name to its type or array of objects with `name`, `type` and optional `nullable`.
+///Type of column is specified the same way as in `--dtype`
+pub fn parse_schema(text: &str) -> Result<Vec<ColumnSchema>, String> {
+    let schema = serde_json::from_str::<serde_json::Value>(text).map_err(|error| format!("Invalid JSON: {error}"))?;
+    match schema {
+        serde_json::Value::Object(columns) => columns.into_iter().map(|(name, entry)| parse_schema_column(name, &entry)).collect(),
+        serde_json::Value::Array(columns) => columns.iter().map(|entry| match entry.get("name").and_then(serde_json::Value::as_str) {
+            Some(name) => parse_schema_column(name.to_owned(), entry),
+            None => Err("Column has no `name`".to_owned()),
+        }).collect(),
+        _ => Err("Schema must be JSON object or array".to_owned()),
+    }
+}
+
+impl core::str::FromStr for Sort {
+    type Err = &'static str;
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut column = text;
+        let mut desc = None;
+        let mut nulls_first = None;
+
+        //Modifiers are parsed from the end, so column name itself may contain `:`
+        //Null ordering is expected to be last, after direction
+        while let Some((rest, modifier)) = column.rsplit_once(':') {
+            if modifier.eq_ignore_ascii_case("asc") && desc.is_none() {
+                desc = Some(false);
+            } else if modifier.eq_ignore_ascii_case("desc") && desc.is_none() {
+                desc = Some(true);
+            } else if modifier.eq_ignore_ascii_case("nulls_first") && nulls_first.is_none() && desc.is_none() {
+                nulls_first = Some(true);
+            } else if modifier.eq_ignore_ascii_case("nulls_last") && nulls_first.is_none() && desc.is_none() {
+                nulls_first = Some(false);
+            } else if ["asc", "desc", "nulls_first", "nulls_last"].iter().any(|known| modifier.eq_ignore_ascii_case(known)) {
+                return Err("Sort modifier is out of order. Expected format: column[:asc|desc][:nulls_first|nulls_last]");
+            } else {
+                break;
+            }
+            column = rest;
+        }
+
+        if column.is_empty() {
+            return Err("Column name is missing. Expected format: column[:asc|desc][:nulls_first|nulls_last]");
+        }
+
+        Ok(Self {
+            column: column.to_owned(),
+            desc,
+            nulls_first: nulls_first.unwrap_or(false),
+        })
+    }
+}
 
 #[derive(Args, Debug)]
 ///Query data
@@ -180,6 +267,9 @@ pub struct Query {
     #[arg(long, default_value = "1000")]
     ///Limit number of elements to process at most. Default size 1000
     pub chunk_by: usize,
+    #[arg(long, default_value = "OutputFormat::Csv")]
+    ///Output format: csv, table, json (lines), markdown. Defaults to csv
+    pub output_format: OutputFormat,
     #[arg(required)]
     ///Path(s) to a file or directory (may be URI or include wildcard)
     pub path: String,
@@ -200,9 +290,33 @@ pub struct Concat {
     #[arg(long, default_value = "ExpectFormat::Infer")]
     ///Expected file format. Defaults to inferring from path
     pub format: ExpectFormat,
+    #[arg(long)]
+    ///Compression of CSV or JSON output (gzip, zstd, bzip2, xz). Datafusion only. Defaults to inferring from output path
+    pub compression: Option<Compression>,
+    #[arg(long)]
+    ///Limits number of rows to write. Overrides common `--limit`
+    pub limit: Option<usize>,
     #[arg(long, default_value)]
     ///Optional common prefix for output files. Applied to partitioned output only.
     pub prefix: String,
+    #[arg(long, default_value = "ParquetCodec::Snappy")]
+    ///Parquet compression codec with optional level (e.g. `zstd:9`). Defaults to snappy
+    pub parquet_codec: ParquetCodec,
+    #[arg(long)]
+    ///Maximum number of rows in parquet row group
+    pub row_group_size: Option<usize>,
+    #[arg(long)]
+    ///Maximum size of parquet data page in bytes
+    pub data_page_size: Option<usize>,
+    #[arg(long)]
+    ///Specifies to disable parquet dictionary encoding
+    pub no_dictionary: bool,
+    #[arg(long)]
+    ///Level of parquet statistics: none, chunk, page
+    pub statistics: Option<ParquetStatistics>,
+    #[arg(long)]
+    ///Columns to write parquet bloom filters for
+    pub bloom_filter: Vec<String>,
     #[arg(required)]
     ///Path(s) to a file or directory (may be URI or include wildcard)
     pub path: String,
@@ -211,6 +325,122 @@ pub struct Concat {
     pub output: String,
 }
 
+impl Concat {
+    ///Creates parquet writer options from arguments
+    pub fn parquet_config(&self) -> ParquetWriteConfig {
+        ParquetWriteConfig {
+            codec: self.parquet_codec,
+            row_group_size: self.row_group_size,
+            data_page_size: self.data_page_size,
+            dictionary: !self.no_dictionary,
+            statistics: self.statistics,
+            bloom_filter_columns: self.bloom_filter.clone(),
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+///Runs SQL statement against data
+pub struct Sql {
+    #[arg(long, default_value = "1000")]
+    ///Limit number of elements to process at most. Default size 1000
+    pub chunk_by: usize,
+    #[arg(long, default_value = "\"data\".to_owned()")]
+    ///Name of the table to use in SQL statement. Defaults to `data`
+    pub table: String,
+    #[arg(long, default_value = "OutputFormat::Csv")]
+    ///Output format: csv, table, json (lines), markdown. Defaults to csv
+    pub output_format: OutputFormat,
+    #[arg(required)]
+    ///Path(s) to a file or directory (may be URI or include wildcard)
+    pub path: String,
+    #[arg(required)]
+    ///SQL statement to execute
+    pub statement: String,
+}
+
+#[derive(Args, Debug)]
+///Prints schema of data
+pub struct Schema {
+    #[arg(long, default_value = "OutputFormat::Table")]
+    ///Output format: csv, table, json (lines), markdown. Defaults to table
+    pub output_format: OutputFormat,
+    #[arg(required)]
+    ///Path(s) to a file or directory (may be URI or include wildcard)
+    pub path: String,
+}
+
+#[derive(Args, Debug)]
+///Prints statistics of every column
+pub struct Describe {
+    #[arg(long)]
+    ///Specifies to use parquet footer statistics only, without reading data
+    pub metadata_only: bool,
+    #[arg(long, default_value = "OutputFormat::Table")]
+    ///Output format: csv, table, json (lines), markdown. Defaults to table
+    pub output_format: OutputFormat,
+    #[arg(required)]
+    ///Path(s) to a file or directory (may be URI or include wildcard)
+    pub path: String,
+}
+
+#[derive(Args, Debug)]
+///Compares two datasets by key
+pub struct Diff {
+    #[arg(long)]
+    ///Key columns identifying rows in both datasets
+    pub key: Vec<String>,
+    #[arg(long)]
+    ///Specifies to print number of rows per kind of difference only
+    pub summary: bool,
+    #[arg(long)]
+    ///Directory to write `only_left`, `only_right` and `changed` files into. By default results are printed
+    pub output: Option<String>,
+    #[arg(long, default_value = "ExpectFormat::Infer")]
+    ///Format of output files. Output directory has no extension to infer it from, so parquet is used by default
+    pub format: ExpectFormat,
+    #[arg(long, default_value = "1000")]
+    ///Limit number of elements to process at most. Default size 1000
+    pub chunk_by: usize,
+    #[arg(long, default_value = "OutputFormat::Csv")]
+    ///Output format: csv, table, json (lines), markdown. Defaults to csv
+    pub output_format: OutputFormat,
+    #[arg(required)]
+    ///Path(s) to left file or directory (may be URI or include wildcard)
+    pub left: String,
+    #[arg(required)]
+    ///Path(s) to right file or directory (may be URI or include wildcard)
+    pub right: String,
+}
+
+#[derive(Args, Debug)]
+///Counts rows
+pub struct Count {
+    #[arg(long)]
+    ///Partition columns to count rows per value of
+    pub group_by: Vec<String>,
+    #[arg(long, default_value = "OutputFormat::Table")]
+    ///Output format: csv, table, json (lines), markdown. Defaults to table
+    pub output_format: OutputFormat,
+    #[arg(required)]
+    ///Path(s) to a file or directory (may be URI or include wildcard)
+    pub path: String,
+}
+
+#[derive(Args, Debug)]
+///Prints internals of parquet files
+pub struct Inspect {
+    #[arg(long)]
+    ///Specifies to print every column chunk of every row group, in addition to per column summary
+    pub row_groups: bool,
+    #[arg(long, default_value = "OutputFormat::Table")]
+    ///Output format: csv, table, json (lines), markdown. Defaults to table
+    pub output_format: OutputFormat,
+    #[arg(required)]
+    ///Path(s) to a parquet file or directory (may be URI or include wildcard)
+    pub path: String,
+}
+
 #[derive(Args, Debug)]
 ///Possible commands
 pub enum Command {
@@ -218,17 +448,32 @@ pub enum Command {
     Query(Query),
     ///concat data
     Concat(Concat),
+    ///run SQL against data
+    Sql(Sql),
+    ///print schema of data
+    Schema(Schema),
+    ///print statistics of every column
+    Describe(Describe),
+    ///alias to describe
+    Stats(Describe),
+    ///compare two datasets by key
+    Diff(Diff),
+    ///count rows
+    Count(Count),
+    ///print internals of parquet files
+    Inspect(Inspect),
 }
 
+#[derive(Clone)]
 ///Common parameters of CLI
 pub struct CommonArgs {
     ///Backend to use
     pub backend: Backend,
     ///List of column names to select
     pub select: Vec<String>,
-    ///List of column names to sort in order
-    pub sort: Vec<String>,
-    ///Specifies descending order for sort. Defaults to ascending.
+    ///List of columns to sort in order
+    pub sort: Vec<Sort>,
+    ///Specifies descending order for columns without explicit direction. Defaults to ascending.
     pub sort_desc: bool,
     ///Specify to select unique
     pub unique: bool,
@@ -236,19 +481,80 @@ pub struct CommonArgs {
     pub unique_by: Vec<String>,
     ///Specify to count duplicate records under column `dup_count`
     pub count_duplicates: bool,
+    ///Specify computed columns in format `name=expression`
+    pub with_column: Vec<ComputedColumn>,
     ///Specify filtering expressions to use when selecting data.
     pub filter: Vec<Expression>,
+    ///Specify columns to group by
+    pub group_by: Vec<String>,
+    ///Specify aggregations in format `name=function(column)`
+    pub agg: Vec<Aggregate>,
+    ///Specify filtering expressions to apply after aggregation (e.g. on `dup_count`)
+    pub having: Vec<Expression>,
+    ///Specifies fraction of rows to sample
+    pub sample_fraction: Option<f64>,
+    ///Specifies number of rows to sample (per group if `sample_by` is specified)
+    pub sample_n: Option<usize>,
+    ///Specifies seed for sampling. Random by default
+    pub seed: Option<u64>,
+    ///Specifies columns to stratify sample by
+    pub sample_by: Vec<String>,
+    ///Limits number of rows to output
+    pub limit: Option<usize>,
+    ///Number of rows to skip before output
+    pub offset: usize,
+    ///Specifies to output last N rows only
+    pub tail: Option<usize>,
     ///Specify to use stable operations
     pub stable: bool,
     ///Expected file format. Defaults to inferring from path
     pub format: ExpectFormat,
+    ///CSV dialect for input and output
+    pub csv: CsvOptions,
     ///Specifies time unit for int96. Defaults to nanosecond
     pub coerce_int96: Int96Timestamp,
     ///Specifies whether to keep partitioned columns
-    pub keep_partition: bool
+    pub keep_partition: bool,
+    ///Path to JSON schema overriding inferred types
+    pub schema: Option<String>,
+    ///Types of columns overriding inferred schema
+    pub dtype: Vec<ColumnSchema>,
+    ///Version of Delta table to read
+    pub delta_version: DeltaVersion,
 }
 
 impl CommonArgs {
+    ///Returns whether options may change number of rows (e.g. filters or limits)
+    pub fn changes_row_count(&self) -> bool {
+        !self.filter.is_empty() || self.unique || self.count_duplicates || !self.group_by.is_empty() || !self.agg.is_empty() || !self.having.is_empty()
+            || self.sample_fraction.is_some() || self.sample_n.is_some() || self.limit.is_some() || self.offset != 0 || self.tail.is_some()
+    }
+
+    ///Validates that options selecting rows to output do not conflict
+    pub fn validate_slice(&self) -> Result<(), &'static str> {
+        if self.tail.is_some() && (self.limit.is_some() || self.offset != 0) {
+            return Err("--tail cannot be combined with --limit or --offset");
+        }
+        Ok(())
+    }
+
+    ///Loads schema file, if specified, merging it with `dtype`
+    ///
+    ///Columns specified by `dtype` take precedence over schema file
+    pub fn load_schema(&mut self) -> Result<(), String> {
+        let path = match self.schema.take() {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        let text = std::fs::read_to_string(&path).map_err(|error| format!("{path}: {error}"))?;
+        let mut schema = parse_schema(&text).map_err(|error| format!("{path}: {error}"))?;
+        schema.retain(|column| !self.dtype.iter().any(|dtype| dtype.name == column.name));
+        schema.append(&mut self.dtype);
+        self.dtype = schema;
+        Ok(())
+    }
+
     ///Creates query parameters from arguments
     pub fn into_query(
         self,
@@ -257,21 +563,55 @@ impl CommonArgs {
         impl ExactSizeIterator<Item = crate::SortBy>,
         impl ExactSizeIterator<Item = String>,
         impl ExactSizeIterator<Item = Expression>,
+        impl ExactSizeIterator<Item = ComputedColumn>,
+        impl ExactSizeIterator<Item = String>,
+        impl ExactSizeIterator<Item = Aggregate>,
+        impl ExactSizeIterator<Item = String>,
     > {
+        let sample_size = match (self.sample_n, self.sample_fraction) {
+            (Some(count), _) => Some(crate::SampleSize::Count(count)),
+            (None, Some(fraction)) => Some(crate::SampleSize::Fraction(fraction)),
+            (None, None) => None,
+        };
+        let is_group_by = !self.group_by.is_empty() || !self.agg.is_empty();
         crate::Query {
             column: self.select.into_iter(),
-            sort_by: self.sort.into_iter().map(move |column| crate::SortBy {
-                column,
-                desc: self.sort_desc,
+            sort_by: self.sort.into_iter().map(move |sort| crate::SortBy {
+                column: sort.column,
+                desc: sort.desc.unwrap_or(self.sort_desc),
+                nulls_first: sort.nulls_first,
             }),
+            stable_sort: self.stable,
             unique: self.unique.then(move || crate::Unique {
                 columns: self.unique_by.into_iter(),
                 is_stable: self.stable,
             }),
+            csv: self.csv,
             coerce_int96: self.coerce_int96,
+            schema: self.dtype,
+            delta_version: self.delta_version,
             keep_partition: self.keep_partition,
             count_duplicates: self.count_duplicates,
+            compression: None,
+            with_column: self.with_column.into_iter(),
             filter: self.filter.into_iter(),
+            sample: sample_size.map(move |size| crate::Sample {
+                size,
+                seed: self.seed.unwrap_or_else(|| crate::utils::unit_now().as_nanos() as u64),
+                by: self.sample_by.into_iter(),
+            }),
+            group_by: is_group_by.then(move || crate::GroupBy {
+                columns: self.group_by.into_iter(),
+                aggregates: self.agg.into_iter(),
+            }),
+            slice: match self.tail {
+                Some(tail) => Some(crate::Slice::Tail(tail)),
+                None => (self.offset != 0 || self.limit.is_some()).then_some(crate::Slice::Head {
+                    offset: self.offset,
+                    limit: self.limit,
+                }),
+            },
+            having: self.having.into_iter(),
         }
     }
 }
@@ -287,10 +627,10 @@ pub struct Cli {
     ///List of column names to select
     pub select: Vec<String>,
     #[arg(long)]
-    ///List of column names to sort in order
-    pub sort: Vec<String>,
+    ///List of columns to sort in order. Format: `column[:asc|desc][:nulls_first|nulls_last]`
+    pub sort: Vec<Sort>,
     #[arg(long)]
-    ///Specifies descending order for sort. Defaults to ascending.
+    ///Specifies descending order for columns without explicit direction. Defaults to ascending.
     pub sort_desc: bool,
     #[arg(long)]
     ///Specify to select unique
@@ -302,26 +642,97 @@ pub struct Cli {
     ///Specify to count duplicate records under column `dup_count`
     pub count_duplicates: bool,
     #[arg(long)]
+    ///Specify computed columns in format `name=expression`
+    pub with_column: Vec<ComputedColumn>,
+    #[arg(long)]
     ///Specify filtering expressions to use when selecting data.
     pub filter: Vec<Expression>,
     #[arg(long)]
+    ///Specify columns to group by
+    pub group_by: Vec<String>,
+    #[arg(long)]
+    ///Specify aggregations in format `name=function(column)`.
+    ///
+    ///Supported functions: count, count_distinct, sum, min, max, mean, first, last
+    pub agg: Vec<Aggregate>,
+    #[arg(long)]
+    ///Specify filtering expressions to apply after aggregation (e.g. on `dup_count`)
+    pub having: Vec<Expression>,
+    #[arg(long)]
+    ///Specifies fraction of rows to sample (between 0 and 1)
+    pub sample_fraction: Option<f64>,
+    #[arg(long)]
+    ///Specifies number of rows to sample (per group if `sample_by` is specified)
+    pub sample_n: Option<usize>,
+    #[arg(long)]
+    ///Specifies seed for sampling. Random by default
+    pub seed: Option<u64>,
+    #[arg(long)]
+    ///Specifies columns to stratify sample by
+    pub sample_by: Vec<String>,
+    #[arg(long)]
+    ///Limits number of rows to output
+    pub limit: Option<usize>,
+    #[arg(long)]
+    ///Alias to `--limit`. Cannot be combined with `--limit`
+    pub head: Option<usize>,
+    #[arg(long, default_value)]
+    ///Number of rows to skip before output. Defaults to 0
+    pub offset: usize,
+    #[arg(long)]
+    ///Specifies to output last N rows only. Cannot be combined with `--limit` or `--offset`
+    pub tail: Option<usize>,
+    #[arg(long)]
     ///Specify to use stable operations
     pub stable: bool,
     #[arg(long, default_value = "ExpectFormat::Infer")]
     ///Expected file format. Defaults to inferring from path
     pub format: ExpectFormat,
+    #[arg(long)]
+    ///CSV field delimiter. Defaults to `,`
+    pub delimiter: Option<Char>,
+    #[arg(long)]
+    ///CSV quote character. Defaults to `"`
+    pub quote: Option<Char>,
+    #[arg(long)]
+    ///CSV escape character. By default quotes are escaped by doubling them
+    pub escape: Option<Char>,
+    #[arg(long)]
+    ///Specifies that CSV has no header
+    pub no_header: bool,
+    #[arg(long)]
+    ///String representing null value in CSV. By default empty field is null
+    pub null_value: Option<String>,
+    #[arg(long)]
+    ///Prefix of CSV comment lines to skip
+    pub comment: Option<Char>,
+    #[arg(long, default_value)]
+    ///Number of CSV lines to skip at the beginning of file
+    pub skip_rows: usize,
+    #[arg(long)]
+    ///Specifies to truncate CSV lines with more fields than in header instead of failing
+    pub truncate_ragged_lines: bool,
     #[arg(long, default_value = "Int96Timestamp::new()")]
     ///Specifies time unit for int96. Defaults to nanosecond
     pub coerce_int96: Int96Timestamp,
+    #[arg(long)]
+    ///Path to JSON schema overriding inferred column types
+    pub schema: Option<String>,
+    #[arg(long)]
+    ///Type of column overriding inferred type. Format: `column:type[:not_null]`
+    pub dtype: Vec<ColumnSchema>,
+    #[arg(long, default_value = "DeltaVersion::Latest")]
+    ///Version of Delta table to read: version number or timestamp. Defaults to latest
+    pub delta_version: DeltaVersion,
     #[arg(sub)]
-    ///Command to run. Possible values: query, concat
+    ///Command to run. Possible values: query, concat, sql, schema, describe (stats), diff, count, inspect
     pub command: Command,
 }
 
 impl Cli {
     #[inline(always)]
     ///Splits arguments into common and command's specifics
-    pub fn split_parts(self) -> (CommonArgs, Command) {
+    pub fn split_parts(self) -> Result<(CommonArgs, Command), &'static str> {
         let Self {
             select,
             sort,
@@ -329,10 +740,33 @@ impl Cli {
             mut unique,
             unique_by,
             count_duplicates,
+            with_column,
             filter,
+            group_by,
+            agg,
+            having,
+            sample_fraction,
+            sample_n,
+            seed,
+            sample_by,
+            limit,
+            head,
+            offset,
+            tail,
             stable,
             format,
+            delimiter,
+            quote,
+            escape,
+            no_header,
+            null_value,
+            comment,
+            skip_rows,
+            truncate_ragged_lines,
             coerce_int96,
+            schema,
+            dtype,
+            delta_version,
             command,
             backend
         } = self;
@@ -345,14 +779,41 @@ impl Cli {
             unique,
             unique_by,
             count_duplicates,
+            with_column,
             filter,
+            group_by,
+            agg,
+            having,
+            sample_fraction,
+            sample_n,
+            seed,
+            sample_by,
+            limit: match (limit, head) {
+                (Some(_), Some(_)) => return Err("--head cannot be combined with --limit"),
+                (limit, head) => limit.or(head),
+            },
+            offset,
+            tail,
             stable,
             format,
+            csv: CsvOptions {
+                delimiter: delimiter.map_or(b',', |delimiter| delimiter.0),
+                quote: quote.map_or(b'"', |quote| quote.0),
+                escape: escape.map(|escape| escape.0),
+                has_header: !no_header,
+                null_value,
+                comment: comment.map(|comment| comment.0),
+                skip_rows,
+                truncate_ragged_lines,
+            },
             coerce_int96,
             backend,
             keep_partition: true,
+            schema,
+            dtype,
+            delta_version,
         };
-        (common, command)
+        Ok((common, command))
     }
 }
 
@@ -361,3 +822,108 @@ impl Cli {
 pub fn args() -> Cli {
     arg::parse_args()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_sort() {
+        let sort = "price".parse::<Sort>().expect("to parse");
+        assert_eq!(sort.column, "price");
+        assert_eq!(sort.desc, None);
+        assert!(!sort.nulls_first);
+
+        let sort = "price:DESC:nulls_first".parse::<Sort>().expect("to parse");
+        assert_eq!(sort.column, "price");
+        assert_eq!(sort.desc, Some(true));
+        assert!(sort.nulls_first);
+
+        let sort = "price:nulls_last".parse::<Sort>().expect("to parse");
+        assert_eq!(sort.desc, None);
+        assert!(!sort.nulls_first);
+
+        //Column name may contain `:`
+        let sort = "a:b:asc".parse::<Sort>().expect("to parse");
+        assert_eq!(sort.column, "a:b");
+        assert_eq!(sort.desc, Some(false));
+
+        //Modifiers in wrong order or repeated
+        assert!("price:nulls_first:desc".parse::<Sort>().is_err());
+        assert!("price:asc:desc".parse::<Sort>().is_err());
+        assert!("price:nulls_last:nulls_first".parse::<Sort>().is_err());
+
+        assert!(":desc".parse::<Sort>().is_err());
+        assert!("".parse::<Sort>().is_err());
+    }
+
+    #[test]
+    fn should_parse_column_schema() {
+        let column = "id:int32:not_null".parse::<ColumnSchema>().expect("to parse");
+        assert_eq!(column.name, "id");
+        assert!(matches!(column.dtype, crate::literal::LiteralType::Int(32)));
+        assert!(!column.nullable);
+
+        let column = "price:decimal(10, 2):nullable".parse::<ColumnSchema>().expect("to parse");
+        assert!(matches!(column.dtype, crate::literal::LiteralType::Decimal { precision: 10, scale: 2 }));
+        assert!(column.nullable);
+
+        let column = "ts:timestamp(ms, +01:00)".parse::<ColumnSchema>().expect("to parse");
+        assert_eq!(column.name, "ts");
+        assert!(matches!(column.dtype, crate::literal::LiteralType::Timestamp { timezone: Some(ref timezone), .. } if timezone == "+01:00"));
+        assert!(column.nullable);
+
+        let column = "ratio:float32".parse::<ColumnSchema>().expect("to parse");
+        assert!(matches!(column.dtype, crate::literal::LiteralType::Float(32)));
+        let column = "ratio:double".parse::<ColumnSchema>().expect("to parse");
+        assert!(matches!(column.dtype, crate::literal::LiteralType::Float(64)));
+
+        assert!("id".parse::<ColumnSchema>().is_err());
+        assert!(":int32".parse::<ColumnSchema>().is_err());
+        assert!("id:integer".parse::<ColumnSchema>().is_err());
+    }
+
+    #[test]
+    fn should_parse_schema_file() {
+        let schema = parse_schema(r#"{"id": "int64", "name": {"type": "string", "nullable": false}}"#).expect("to parse");
+        assert_eq!(schema.len(), 2);
+        assert!(schema.iter().any(|column| column.name == "id" && column.nullable));
+        assert!(schema.iter().any(|column| column.name == "name" && !column.nullable));
+
+        let schema = parse_schema(r#"[{"name": "id", "type": "uint8"}]"#).expect("to parse");
+        assert!(matches!(schema.as_slice(), [ColumnSchema { dtype: crate::literal::LiteralType::UInt(8), nullable: true, .. }]));
+
+        assert!(parse_schema(r#"{"id": 1}"#).is_err());
+        assert!(parse_schema(r#"[{"type": "int64"}]"#).is_err());
+        assert!(parse_schema("\"int64\"").is_err());
+    }
+
+    #[test]
+    fn should_parse_parquet_codec() {
+        assert_eq!("snappy".parse(), Ok(ParquetCodec::Snappy));
+        assert_eq!("NONE".parse(), Ok(ParquetCodec::Uncompressed));
+        assert_eq!("zstd".parse(), Ok(ParquetCodec::Zstd(None)));
+        assert_eq!("zstd:9".parse(), Ok(ParquetCodec::Zstd(Some(9))));
+        assert_eq!("gzip:6".parse(), Ok(ParquetCodec::Gzip(Some(6))));
+        assert_eq!("brotli".parse(), Ok(ParquetCodec::Brotli(None)));
+
+        assert!("snappy:1".parse::<ParquetCodec>().is_err());
+        assert!("zstd:fast".parse::<ParquetCodec>().is_err());
+        assert!("zstd:0".parse::<ParquetCodec>().is_err());
+        assert!("zstd:23".parse::<ParquetCodec>().is_err());
+        assert!("gzip:10".parse::<ParquetCodec>().is_err());
+        assert_eq!("brotli:11".parse(), Ok(ParquetCodec::Brotli(Some(11))));
+        assert!("brotli:12".parse::<ParquetCodec>().is_err());
+        assert!("lzo".parse::<ParquetCodec>().is_err());
+    }
+
+    #[test]
+    fn should_parse_delta_version() {
+        assert!(matches!("42".parse(), Ok(DeltaVersion::Version(42))));
+        assert!(matches!("1970-01-01T00:00:01Z".parse(), Ok(DeltaVersion::Timestamp(1_000))));
+        assert!(matches!("1970-01-02".parse(), Ok(DeltaVersion::Timestamp(86_400_000))));
+
+        assert!("-1".parse::<DeltaVersion>().is_err());
+        assert!("latest".parse::<DeltaVersion>().is_err());
+    }
+}
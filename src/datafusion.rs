@@ -3,7 +3,8 @@
 use std::path::Path;
 use std::sync::Arc;
 
-use super::{cli, FileFormat, Query, SortBy, DUPLICATE_COLUMN};
+use super::{delta, expr, format, literal, parquet, ColumnSchema, Compression, CsvOptions, DeltaVersion, DetectedFormat, FileFormat, ParquetCodec, ParquetStatistics, ParquetWriteConfig, Query, SampleSize, Slice, SortBy, DIFF_CHANGED_SUFFIX, DIFF_PRESENT_COLUMN, DIFF_RIGHT_SUFFIX, DUPLICATE_COLUMN, SAMPLE_KEY_COLUMN};
+use literal::{LiteralType, Value};
 
 pub use datafusion::dataframe::DataFrameWriteOptions;
 pub use datafusion::execution::context::{SessionContext, SessionConfig};
@@ -15,24 +16,185 @@ use datafusion::error::DataFusionError;
 use datafusion::logical_expr::{col, lit};
 use datafusion::logical_expr::{Expr, SortExpr};
 use datafusion::common::arrow::datatypes::DataType;
+use datafusion::common::{DFSchema, ScalarValue};
 use futures_util::StreamExt;
+use object_store::{GetOptions, GetRange, ObjectStore};
 
-fn apply_select_sort_on_non_distinct_query(mut df: DataFrame, select: Vec<Expr>, sort: impl ExactSizeIterator<Item = SortBy>) -> Result<DataFrame, DataFusionError> {
+//Name of temporary column used to preserve original order of rows
+const ROW_NUMBER_COLUMN: &str = "__mishka_row_number";
+
+fn sort_expr(sort: SortBy) -> SortExpr {
+    col(sort.column).sort(!sort.desc, sort.nulls_first)
+}
+
+fn apply_sort(df: DataFrame, sort: impl ExactSizeIterator<Item = SortBy>, is_stable: bool) -> Result<DataFrame, DataFusionError> {
+    if sort.len() == 0 {
+        return Ok(df);
+    }
+
+    let mut sort = sort.map(sort_expr).collect::<Vec<_>>();
+    if is_stable {
+        //Datafusion sort is not stable, so use position of row as last key
+        let df = df.with_column(ROW_NUMBER_COLUMN, datafusion::functions_window::expr_fn::row_number())?;
+        sort.push(col(ROW_NUMBER_COLUMN).sort(true, false));
+        df.sort(sort)?.drop_columns(&[ROW_NUMBER_COLUMN])
+    } else {
+        df.sort(sort)
+    }
+}
+
+fn csv_format(path: &str, options: &CsvOptions) -> Result<datafusion::datasource::file_format::csv::CsvFormat, DataFusionError> {
+    if options.skip_rows > 0 {
+        return Err(DataFusionError::NotImplemented(format!("{path}: Skipping CSV rows is not supported by datafusion")));
+    }
+
+    let format = datafusion::datasource::file_format::csv::CsvFormat::default().with_has_header(options.has_header)
+                                                                             .with_delimiter(options.delimiter)
+                                                                             .with_quote(options.quote)
+                                                                             .with_escape(options.escape)
+                                                                             .with_comment(options.comment)
+                                                                             .with_null_regex(options.null_value.as_deref().map(|null| format!("^{}$", expr::escape_regex(null))))
+                                                                             .with_truncated_rows(options.truncate_ragged_lines);
+    Ok(format)
+}
+
+///Creates CSV writer options
+pub fn csv_write_options(options: &CsvOptions) -> datafusion::config::CsvOptions {
+    datafusion::config::CsvOptions {
+        has_header: Some(options.has_header),
+        delimiter: options.delimiter,
+        quote: options.quote,
+        escape: options.escape,
+        null_value: options.null_value.clone(),
+        ..Default::default()
+    }
+}
+
+///Creates parquet writer options
+pub fn parquet_write_options(config: &ParquetWriteConfig) -> datafusion::config::TableParquetOptions {
+    use datafusion::config::{ParquetColumnOptions, ParquetOptions, TableParquetOptions};
+
+    let compression = match config.codec {
+        ParquetCodec::Uncompressed => "uncompressed".to_owned(),
+        ParquetCodec::Snappy => "snappy".to_owned(),
+        ParquetCodec::Gzip(level) => format!("gzip({})", level.unwrap_or(6)),
+        ParquetCodec::Lz4 => "lz4_raw".to_owned(),
+        ParquetCodec::Zstd(level) => format!("zstd({})", level.unwrap_or(3)),
+        ParquetCodec::Brotli(level) => format!("brotli({})", level.unwrap_or(1)),
+    };
+    let statistics_enabled = match config.statistics {
+        //Minimize overhead of datafusion's arrow format
+        None | Some(ParquetStatistics::None) => "none",
+        Some(ParquetStatistics::Chunk) => "chunk",
+        Some(ParquetStatistics::Page) => "page",
+    };
+
+    let mut global = ParquetOptions {
+        compression: Some(compression),
+        coerce_int96: None,
+        statistics_enabled: Some(statistics_enabled.to_owned()),
+        dictionary_enabled: Some(config.dictionary),
+        skip_arrow_metadata: true,
+        created_by: String::new(),
+        ..Default::default()
+    };
+    if let Some(row_group_size) = config.row_group_size {
+        global.max_row_group_size = row_group_size;
+    }
+    if let Some(data_page_size) = config.data_page_size {
+        global.data_pagesize_limit = data_page_size;
+    }
+
+    let mut options = TableParquetOptions {
+        global,
+        ..Default::default()
+    };
+    for column in config.bloom_filter_columns.iter() {
+        let column_options = ParquetColumnOptions {
+            bloom_filter_enabled: Some(true),
+            ..Default::default()
+        };
+        options.column_specific_options.insert(column.clone(), column_options);
+    }
+    options
+}
+
+fn file_compression_type(compression: Option<Compression>) -> datafusion::datasource::file_format::file_compression_type::FileCompressionType {
+    use datafusion::datasource::file_format::file_compression_type::FileCompressionType;
+
+    match compression {
+        Some(Compression::Gzip) => FileCompressionType::GZIP,
+        Some(Compression::Zstd) => FileCompressionType::ZSTD,
+        Some(Compression::Bzip2) => FileCompressionType::BZIP2,
+        Some(Compression::Xz) => FileCompressionType::XZ,
+        None => FileCompressionType::UNCOMPRESSED,
+    }
+}
+
+///Returns datafusion's compression type for writing files
+pub fn compression_type_variant(compression: Option<Compression>) -> datafusion::common::parsers::CompressionTypeVariant {
+    use datafusion::common::parsers::CompressionTypeVariant;
+
+    match compression {
+        Some(Compression::Gzip) => CompressionTypeVariant::GZIP,
+        Some(Compression::Zstd) => CompressionTypeVariant::ZSTD,
+        Some(Compression::Bzip2) => CompressionTypeVariant::BZIP2,
+        Some(Compression::Xz) => CompressionTypeVariant::XZ,
+        None => CompressionTypeVariant::UNCOMPRESSED,
+    }
+}
+
+fn apply_tail(df: DataFrame, tail: usize) -> Result<DataFrame, DataFusionError> {
+    //Read rows in reverse order to take last N, then restore original order
+    let df = df.with_column(ROW_NUMBER_COLUMN, datafusion::functions_window::expr_fn::row_number())?;
+    df.sort(vec![col(ROW_NUMBER_COLUMN).sort(false, false)])?
+      .limit(0, Some(tail))?
+      .sort(vec![col(ROW_NUMBER_COLUMN).sort(true, false)])?
+      .drop_columns(&[ROW_NUMBER_COLUMN])
+}
+
+fn apply_sample(df: DataFrame, sample: crate::Sample<impl ExactSizeIterator<Item = String>>) -> Result<DataFrame, DataFusionError> {
+    use datafusion::functions_window::expr_fn::row_number;
+    use datafusion::logical_expr::{cast, ExprFunctionExt};
+
+    //Row number starts with 1 while polars' row index starts with 0
+    let df = df.with_column(SAMPLE_KEY_COLUMN, cast(row_number(), DataType::Int64) - lit(1i64))?;
+    let df = df.with_column(SAMPLE_KEY_COLUMN, super::sample_key(col(SAMPLE_KEY_COLUMN), sample.seed, |value| lit(value)))?;
+    let df = match sample.size {
+        SampleSize::Fraction(fraction) => df.filter(col(SAMPLE_KEY_COLUMN).lt(lit(super::sample_threshold(fraction))))?,
+        //Taking N rows with smallest random keys is equivalent to reservoir sampling and runs as top-k
+        SampleSize::Count(count) => if sample.by.len() == 0 {
+            df.sort(vec![col(SAMPLE_KEY_COLUMN).sort(true, false)])?.limit(0, Some(count))?
+        } else {
+            //Datafusion has no bounded top-k per group, so window ranks all rows within each group
+            let rank = row_number().partition_by(sample.by.map(col).collect())
+                                   .order_by(vec![col(SAMPLE_KEY_COLUMN).sort(true, false)])
+                                   .build()?;
+            df.with_column(ROW_NUMBER_COLUMN, rank)?
+              .filter(col(ROW_NUMBER_COLUMN).lt_eq(lit(count as u64)))?
+              .drop_columns(&[ROW_NUMBER_COLUMN])?
+        },
+    };
+    df.drop_columns(&[SAMPLE_KEY_COLUMN])
+}
+
+fn apply_select_sort_on_non_distinct_query(mut df: DataFrame, select: Vec<Expr>, sort: impl ExactSizeIterator<Item = SortBy>, is_stable: bool) -> Result<DataFrame, DataFusionError> {
     if select.len() > 0 {
         df = df.select(select)?
     };
 
-    if sort.len() != 0 {
-        //Stable sort?
-        df.sort_by(sort.map(|sort| col(sort.column)).collect())
-    } else {
-        Ok(df)
-    }
+    apply_sort(df, sort, is_stable)
 }
 
-impl<CI: ExactSizeIterator<Item = String>, SBI: ExactSizeIterator<Item = SortBy>, UCI: ExactSizeIterator<Item = String>, WHERE: ExactSizeIterator<Item = cli::Expression>> Query<CI, SBI, UCI, WHERE> {
+impl<CI: ExactSizeIterator<Item = String>, SBI: ExactSizeIterator<Item = SortBy>, UCI: ExactSizeIterator<Item = String>, WHERE: ExactSizeIterator<Item = expr::Expression>, WC: ExactSizeIterator<Item = expr::ComputedColumn>, GCI: ExactSizeIterator<Item = String>, AI: ExactSizeIterator<Item = expr::Aggregate>, SCI: ExactSizeIterator<Item = String>> Query<CI, SBI, UCI, WHERE, WC, GCI, AI, SCI> {
+    #[inline]
     ///Scans `path` expecting specified `format`
-    pub async fn create_lazy_datafusion(self, mut ctx: SessionConfig, path: &str, format: FileFormat, partition_by: &[String]) -> Result<DataFrame, DataFusionError> {
+    pub async fn create_lazy_datafusion(self, ctx: SessionConfig, path: &str, format: FileFormat, partition_by: &[String]) -> Result<DataFrame, DataFusionError> {
+        self.create_lazy_datafusion_with_partitions(ctx, path, format, partition_by).await.map(|(df, _)| df)
+    }
+
+    ///Scans `path` expecting specified `format`, returning names of partition columns alongside data frame
+    pub async fn create_lazy_datafusion_with_partitions(self, mut ctx: SessionConfig, path: &str, format: FileFormat, partition_by: &[String]) -> Result<(DataFrame, Vec<String>), DataFusionError> {
         use datafusion::datasource::file_format;
 
         let mut user_partitions = partition_by.iter().map(String::as_str).collect::<Vec<_>>();
@@ -42,7 +204,10 @@ impl<CI: ExactSizeIterator<Item = String>, SBI: ExactSizeIterator<Item = SortBy>
         let mut table_partition_cols = Vec::new();
         let mut table_path = String::new();
         let os_path = Path::new(path).to_owned();
-        let is_file = os_path.extension().is_some();
+        let env = create_runtime(path).await?;
+        let original_path = datafusion::datasource::listing::ListingTableUrl::parse(path)?;
+        //Object without extension may still be a single file
+        let is_file = os_path.extension().is_some() || (!path.ends_with('/') && is_object(&env, &original_path).await);
         for component in os_path.iter().flat_map(|component| component.to_str()) {
             if let Some((key, value)) = component.split_once('=') {
                 table_partition_cols.push((key.to_owned(), DataType::Utf8View));
@@ -65,8 +230,8 @@ impl<CI: ExactSizeIterator<Item = String>, SBI: ExactSizeIterator<Item = SortBy>
         }
 
         if !table_partition_cols.is_empty() {
-            println!(">Infer path partitions={:?}", table_partition_cols);
-            println!(">Table path={table_path}");
+            eprintln!(">Infer path partitions={:?}", table_partition_cols);
+            eprintln!(">Table path={table_path}");
         }
 
         //Assume user passes partitions in the same order as they should be in target
@@ -80,72 +245,150 @@ impl<CI: ExactSizeIterator<Item = String>, SBI: ExactSizeIterator<Item = SortBy>
             options.execution.keep_partition_by_columns = self.keep_partition;
             options.execution.listing_table_ignore_subdirectory = false;
             options.execution.listing_table_factory_infer_partitions = true;
+            //Fraction is taken from every row independently, so it does not depend on order of rows
+            let is_sample_count = self.sample.as_ref().is_some_and(|sample| matches!(sample.size, SampleSize::Count(_)));
+            if self.stable_sort || is_sample_count || matches!(self.slice, Some(Slice::Tail(_))) {
+                //Scan files within single partition to have deterministic order of rows
+                options.execution.target_partitions = 1;
+            }
             if !self.coerce_int96.is_default() {
                 options.execution.parquet.coerce_int96 = Some(self.coerce_int96.as_unit_name().to_owned());
             }
         }
 
-        let env = create_runtime(&table_path).await?;
         let ctx = SessionContext::new_with_config_rt(ctx, env);
 
-        let original_path = datafusion::datasource::listing::ListingTableUrl::parse(path)?;
-        let listing_path = datafusion::datasource::listing::ListingTableUrl::parse(&table_path)?;
-        let mut listing_options = datafusion::datasource::listing::ListingOptions::new(match format {
-            FileFormat::Csv => Arc::new(file_format::csv::CsvFormat::default().with_has_header(true)),
-            FileFormat::Parquet => Arc::new(file_format::parquet::ParquetFormat::new()),
-        });
+        let (mut df, schema, table_partition_cols) = if let FileFormat::Delta = format {
+            scan_delta(&ctx, path, self.delta_version, &self.schema).await?
+        } else {
+            let listing_path = datafusion::datasource::listing::ListingTableUrl::parse(&table_path)?;
+            let compression = match self.compression.or_else(|| Compression::split_path(path).1) {
+                Some(compression) if !format.is_compressible() => return Err(DataFusionError::NotImplemented(format!("{path}: Compression '{}' is not supported for {} format", compression.extension(), format.extension()))),
+                compression => file_compression_type(compression),
+            };
+            let mut listing_options = datafusion::datasource::listing::ListingOptions::new(match format {
+                FileFormat::Csv => Arc::new(csv_format(path, &self.csv)?.with_file_compression_type(compression)),
+                //Delta is scanned separately, but consists of parquet files anyway
+                FileFormat::Parquet | FileFormat::Delta => Arc::new(file_format::parquet::ParquetFormat::new()),
+                FileFormat::NdJson => Arc::new(file_format::json::JsonFormat::default().with_file_compression_type(compression)),
+                FileFormat::Ipc => Arc::new(file_format::arrow::ArrowFormat),
+                FileFormat::IpcStream => return Err(DataFusionError::NotImplemented(format!("{path}: Arrow IPC stream is not supported by datafusion, use Arrow IPC file"))),
+                FileFormat::Avro => Arc::new(file_format::avro::AvroFormat),
+                FileFormat::Json => return Err(DataFusionError::NotImplemented(format!("{path}: JSON array is not supported by datafusion, use newline delimited JSON"))),
+            });
+            if is_file {
+                //Use exact extension of the file as it may differ from format's default (e.g. `.jsonl`)
+                let extension = match os_path.file_name().and_then(|name| name.to_str()).and_then(|name| name.split_once('.')) {
+                    Some((_, extension)) => format!(".{extension}"),
+                    None => String::new(),
+                };
+                listing_options = listing_options.with_file_extension(extension);
+            } else if compression.is_compressed() {
+                //Files within directory carry compression suffix (e.g. `.csv.gz`)
+                let extension = listing_options.format.get_ext_with_compression(&compression)?;
+                listing_options = listing_options.with_file_extension(extension);
+            }
 
-        println!(">{original_path}: Fetching available file");
-        let ctx_object_store = ctx.runtime_env().object_store(&original_path)?;
-        let first_file = match original_path.list_all_files(&ctx.state(), &*ctx_object_store, &listing_options.file_extension).await?.next().await {
-            Some(first_file) => first_file?,
-            None => return Err(DataFusionError::Internal(format!("{path}: No files available to infer schema"))),
-        };
-        println!(">{}: Inferring schema", first_file.location);
-        let old_table_partition_cols_len = table_partition_cols.len();
-        for part in first_file.location.parts() {
-            if let Some((new_key, _)) = part.as_ref().split_once('=') {
-                if table_partition_cols.iter().position(|(key, _typ)| key == new_key).is_none() {
-                    table_partition_cols.push((new_key.to_owned(), DataType::Utf8View));
+            eprintln!(">{original_path}: Fetching available file");
+            let ctx_object_store = ctx.runtime_env().object_store(&original_path)?;
+            let first_file = match original_path.list_all_files(&ctx.state(), &*ctx_object_store, &listing_options.file_extension).await?.next().await {
+                Some(first_file) => first_file?,
+                None => return Err(DataFusionError::Internal(format!("{path}: No files available to infer schema"))),
+            };
+            eprintln!(">{}: Inferring schema", first_file.location);
+            let old_table_partition_cols_len = table_partition_cols.len();
+            for part in first_file.location.parts() {
+                if let Some((new_key, _)) = part.as_ref().split_once('=') {
+                    if table_partition_cols.iter().position(|(key, _typ)| key == new_key).is_none() {
+                        table_partition_cols.push((new_key.to_owned(), DataType::Utf8View));
+                    }
+                }
+            }
+
+            if old_table_partition_cols_len != table_partition_cols.len() {
+                eprintln!(">Infer schema partitions={:?}", table_partition_cols);
+            }
+
+            for (key, dtype) in table_partition_cols.iter_mut() {
+                if let Some(column) = self.schema.iter().find(|column| column.name == *key) {
+                    *dtype = arrow_type(&column.dtype);
                 }
             }
-        }
 
-        if old_table_partition_cols_len != table_partition_cols.len() {
-            println!(">Infer schema partitions={:?}", table_partition_cols);
+            listing_options = listing_options.with_table_partition_cols(table_partition_cols.clone());
+            let schema = listing_options.format.infer_schema(&ctx.state(), &ctx_object_store, &[first_file]).await?;
+            let schema = apply_schema(schema, &self.schema, &table_partition_cols)?;
+
+            let config = datafusion::datasource::listing::ListingTableConfig::new(listing_path).with_listing_options(listing_options).with_schema(schema.clone());
+            let listing = datafusion::datasource::listing::ListingTable::try_new(config)?;
+
+            let table_name = table_path.trim_end_matches('/').rsplit('/').next().unwrap();
+            if partition_filters.is_empty() {
+                eprintln!(">Read table '{table_name}'");
+            } else {
+                eprintln!(">Read table '{table_name}' with filters {filter}", filter=crate::format::datafusion::FiltersFmt(&partition_filters));
+            }
+            let df_plan = datafusion::logical_expr::LogicalPlanBuilder::scan_with_filters(table_name, Arc::new(DefaultTableSource::new(Arc::new(listing))), None, partition_filters)?.build()?;
+            let df = datafusion::dataframe::DataFrame::new(ctx.state(), df_plan);
+            (df, schema, table_partition_cols)
+        };
+        let partitions = table_partition_cols.iter().map(|(column, _)| column.clone()).collect();
+
+        let mut computed_columns = Vec::with_capacity(self.with_column.len());
+        for computed in self.with_column {
+            df = df.with_column(&computed.name, computed.expr.into_datafusion_expr())?;
+            computed_columns.push(computed.name);
         }
 
-        listing_options = listing_options.with_table_partition_cols(table_partition_cols.clone());
-        let schema = listing_options.format.infer_schema(&ctx.state(), &ctx_object_store, &[first_file]).await?;
+        //Filter before aggregation to allow pushing predicates into scan
+        for filter in self.filter {
+            let filter = filter.into_datafusion_expr(df.schema())?;
+            df = df.filter(filter)?;
+        }
 
-        let config = datafusion::datasource::listing::ListingTableConfig::new(listing_path).with_listing_options(listing_options).with_schema(schema.clone());
-        let listing = datafusion::datasource::listing::ListingTable::try_new(config)?;
+        if let Some(sample) = self.sample {
+            df = apply_sample(df, sample)?;
+        }
 
-        let table_name = table_path.trim_end_matches('/').rsplit('/').next().unwrap();
-        if partition_filters.is_empty() {
-            println!(">Read table '{table_name}'");
-        } else {
-            println!(">Read table '{table_name}' with filters {filter}", filter=crate::format::datafusion::FiltersFmt(&partition_filters));
+        let is_aggregated = self.group_by.is_some();
+        if let Some(group_by) = self.group_by {
+            let columns = group_by.columns.map(col).collect();
+            let aggregates = group_by.aggregates.map(expr::Aggregate::into_datafusion_expr).collect();
+            df = df.aggregate(columns, aggregates)?;
         }
-        let df_plan = datafusion::logical_expr::LogicalPlanBuilder::scan_with_filters(table_name, Arc::new(DefaultTableSource::new(Arc::new(listing))), None, partition_filters)?.build()?;
-        let mut df = datafusion::dataframe::DataFrame::new(ctx.state(), df_plan);
+
         let select_columns = self.column.map(col).collect();
         df = if let Some(unique) = self.unique {
-            if unique.columns.len() == 0 {
-                let distinct_on = unique.columns.map(|column| col(column)).collect();
-                let sort = if self.sort_by.len() > 0 {
-                    Some(self.sort_by.map(|it| SortExpr {
-                        expr: col(it.column),
-                        asc: !it.desc,
-                        nulls_first: false
-                    }).collect())
+            if unique.columns.len() != 0 {
+                let distinct_on = unique.columns.map(col).collect::<Vec<_>>();
+                let sort_by = self.sort_by.map(sort_expr).collect::<Vec<_>>();
+                let mut select_columns = if select_columns.is_empty() {
+                    df.schema().columns().into_iter().map(Expr::Column).collect()
                 } else {
-                    None
+                    select_columns
                 };
-                df.distinct_on(distinct_on, select_columns, sort)?
+                //Datafusion requires `DISTINCT ON` expressions to lead sort, which picks row within each group
+                let mut sort = distinct_on.iter().map(|expr| expr.clone().sort(true, false)).chain(sort_by.iter().cloned()).collect::<Vec<_>>();
+                if unique.is_stable {
+                    //Keep first row within each group and preserve order of rows, same as apply_sort
+                    df = df.with_column(ROW_NUMBER_COLUMN, datafusion::functions_window::expr_fn::row_number())?;
+                    sort.push(col(ROW_NUMBER_COLUMN).sort(true, false));
+                    select_columns.push(col(ROW_NUMBER_COLUMN));
+                    let sort_by = sort_by.into_iter().chain([col(ROW_NUMBER_COLUMN).sort(true, false)]).collect();
+                    df.distinct_on(distinct_on, select_columns, Some(sort))?
+                      .sort(sort_by)?
+                      .drop_columns(&[ROW_NUMBER_COLUMN])?
+                } else {
+                    df = df.distinct_on(distinct_on, select_columns, Some(sort))?;
+                    if sort_by.is_empty() {
+                        df
+                    } else {
+                        df.sort(sort_by)?
+                    }
+                }
             } else {
                 df = df.distinct()?;
-                apply_select_sort_on_non_distinct_query(df, select_columns, self.sort_by)?
+                apply_select_sort_on_non_distinct_query(df, select_columns, self.sort_by, self.stable_sort)?
             }
         } else {
             if self.count_duplicates {
@@ -153,46 +396,338 @@ impl<CI: ExactSizeIterator<Item = String>, SBI: ExactSizeIterator<Item = SortBy>
                 let df = if select_columns.len() > 0 {
                     df.aggregate(select_columns.clone(), aggr_expr)?
                 } else {
-                    let group_expr = if self.keep_partition {
-                        table_partition_cols.into_iter().map(|(field, _)| col(field)).chain(schema.fields().iter().map(|field| col(field.name()))).collect()
+                    let computed_columns = computed_columns.into_iter().map(col);
+                    let group_expr = if is_aggregated {
+                        df.schema().columns().into_iter().map(Expr::Column).collect()
+                    } else if self.keep_partition {
+                        table_partition_cols.into_iter().map(|(field, _)| col(field)).chain(schema.fields().iter().map(|field| col(field.name()))).chain(computed_columns).collect()
                     } else {
-                        schema.fields().iter().map(|field| col(field.name())).collect()
+                        schema.fields().iter().map(|field| col(field.name())).chain(computed_columns).collect()
                     };
                     df.aggregate(group_expr, aggr_expr)?
                 };
 
-                apply_select_sort_on_non_distinct_query(df, select_columns, self.sort_by)?
+                apply_select_sort_on_non_distinct_query(df, select_columns, self.sort_by, self.stable_sort)?
             } else {
-                apply_select_sort_on_non_distinct_query(df, select_columns, self.sort_by)?
+                apply_select_sort_on_non_distinct_query(df, select_columns, self.sort_by, self.stable_sort)?
             }
         };
 
-        for filter in self.filter {
-            let left = match filter.left {
-                cli::Operand::Literal(literal) => lit(literal),
-                cli::Operand::Identifier(ident) => col(ident),
-            };
+        for having in self.having {
+            let having = having.into_datafusion_expr(df.schema())?;
+            df = df.filter(having)?;
+        }
 
-            let right = match filter.right {
-                cli::Operand::Literal(literal) => lit(literal),
-                cli::Operand::Identifier(ident) => col(ident),
-            };
+        df = match self.slice {
+            Some(Slice::Head { offset, limit }) => df.limit(offset, limit)?,
+            Some(Slice::Tail(tail)) => apply_tail(df, tail)?,
+            None => df,
+        };
 
-            let filter = match filter.operator {
-                cli::Operator::Less => left.lt(right),
-                cli::Operator::LessEq => left.lt_eq(right),
-                cli::Operator::Eq => left.eq(right),
-                cli::Operator::NotEq => left.not_eq(right),
-                cli::Operator::GreaterEq => left.gt_eq(right),
-                cli::Operator::Greater => left.gt(right),
-            };
-            df = df.filter(filter)?;
+        Ok((df, partitions))
+    }
+}
+
+///Returns arrow type corresponding to literal type
+pub fn arrow_type(dtype: &LiteralType) -> DataType {
+    use datafusion::common::arrow::datatypes::TimeUnit;
+
+    match dtype {
+        LiteralType::Boolean => DataType::Boolean,
+        LiteralType::Int(8) => DataType::Int8,
+        LiteralType::Int(16) => DataType::Int16,
+        LiteralType::Int(32) => DataType::Int32,
+        LiteralType::Int(_) => DataType::Int64,
+        LiteralType::UInt(8) => DataType::UInt8,
+        LiteralType::UInt(16) => DataType::UInt16,
+        LiteralType::UInt(32) => DataType::UInt32,
+        LiteralType::UInt(_) => DataType::UInt64,
+        LiteralType::Float(16) => DataType::Float16,
+        LiteralType::Float(32) => DataType::Float32,
+        LiteralType::Float(_) => DataType::Float64,
+        LiteralType::Decimal { precision, scale } => DataType::Decimal128(*precision, *scale),
+        LiteralType::Date => DataType::Date32,
+        LiteralType::Timestamp { unit, timezone } => DataType::Timestamp(match unit {
+            literal::TimeUnit::Second => TimeUnit::Second,
+            literal::TimeUnit::Millisecond => TimeUnit::Millisecond,
+            literal::TimeUnit::Microsecond => TimeUnit::Microsecond,
+            literal::TimeUnit::Nanosecond => TimeUnit::Nanosecond,
+        }, timezone.as_deref().map(Into::into)),
+        LiteralType::String => DataType::Utf8,
+    }
+}
+
+//Overrides types of columns within inferred `schema`
+//
+//Columns missing in inferred schema are rejected unless they are partitions, same as with polars.
+//Readers validate data against resulting schema, failing on values that cannot be parsed or on nulls in not nullable columns
+fn apply_schema(schema: datafusion::common::arrow::datatypes::SchemaRef, columns: &[ColumnSchema], partitions: &[(String, DataType)]) -> Result<datafusion::common::arrow::datatypes::SchemaRef, DataFusionError> {
+    use datafusion::common::arrow::datatypes::{Field, Schema};
+
+    if columns.is_empty() {
+        return Ok(schema);
+    }
+
+    let mut fields = schema.fields().iter().map(|field| field.as_ref().clone()).collect::<Vec<_>>();
+    for column in columns {
+        let field = Field::new(column.name.as_str(), arrow_type(&column.dtype), column.nullable);
+        match fields.iter_mut().find(|field| *field.name() == column.name) {
+            Some(existing) => *existing = field,
+            None if partitions.iter().any(|(key, _)| *key == column.name) => (),
+            None => return Err(DataFusionError::Plan(format!("Column '{}' is not found in data", column.name))),
+        }
+    }
+    Ok(Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone())))
+}
+
+///Returns literal types of `columns` within `df`, if literal can be cast to every one of them
+pub fn literal_types(df: &DataFrame, columns: &[String]) -> Option<Vec<LiteralType>> {
+    columns.iter().map(|column| df.schema().field_with_unqualified_name(column).ok().and_then(|field| literal_type(field.data_type()))).collect()
+}
+
+///Returns literal type corresponding to arrow `dtype`, if literal can be cast to it
+pub fn literal_type(dtype: &DataType) -> Option<LiteralType> {
+    use datafusion::common::arrow::datatypes::TimeUnit;
+
+    match dtype {
+        DataType::Boolean => Some(LiteralType::Boolean),
+        DataType::Int8 => Some(LiteralType::Int(8)),
+        DataType::Int16 => Some(LiteralType::Int(16)),
+        DataType::Int32 => Some(LiteralType::Int(32)),
+        DataType::Int64 => Some(LiteralType::Int(64)),
+        DataType::UInt8 => Some(LiteralType::UInt(8)),
+        DataType::UInt16 => Some(LiteralType::UInt(16)),
+        DataType::UInt32 => Some(LiteralType::UInt(32)),
+        DataType::UInt64 => Some(LiteralType::UInt(64)),
+        DataType::Float16 => Some(LiteralType::Float(16)),
+        DataType::Float32 => Some(LiteralType::Float(32)),
+        DataType::Float64 => Some(LiteralType::Float(64)),
+        DataType::Decimal128(precision, scale) => Some(LiteralType::Decimal {
+            precision: *precision,
+            scale: *scale,
+        }),
+        //Literal is parsed as 128 bit decimal and then cast to the column's type, so limit its precision
+        DataType::Decimal256(precision, scale) => Some(LiteralType::Decimal {
+            precision: (*precision).min(38),
+            scale: *scale,
+        }),
+        DataType::Date32 | DataType::Date64 => Some(LiteralType::Date),
+        DataType::Timestamp(unit, timezone) => Some(LiteralType::Timestamp {
+            unit: match unit {
+                TimeUnit::Second => literal::TimeUnit::Second,
+                TimeUnit::Millisecond => literal::TimeUnit::Millisecond,
+                TimeUnit::Microsecond => literal::TimeUnit::Microsecond,
+                TimeUnit::Nanosecond => literal::TimeUnit::Nanosecond,
+            },
+            timezone: timezone.as_ref().map(|timezone| timezone.to_string()),
+        }),
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => Some(LiteralType::String),
+        _ => None,
+    }
+}
+
+fn scalar_value(value: Value) -> ScalarValue {
+    match value {
+        Value::Boolean(value) => ScalarValue::Boolean(Some(value)),
+        Value::Int(value) => ScalarValue::Int64(Some(value)),
+        Value::UInt(value) => ScalarValue::UInt64(Some(value)),
+        Value::Float(value) => ScalarValue::Float64(Some(value)),
+        Value::Decimal { value, precision, scale } => ScalarValue::Decimal128(Some(value), precision, scale),
+        Value::Date(value) => ScalarValue::Date32(Some(value)),
+        Value::Timestamp { value, unit, timezone } => {
+            let timezone = timezone.map(Into::into);
+            match unit {
+                literal::TimeUnit::Second => ScalarValue::TimestampSecond(Some(value), timezone),
+                literal::TimeUnit::Millisecond => ScalarValue::TimestampMillisecond(Some(value), timezone),
+                literal::TimeUnit::Microsecond => ScalarValue::TimestampMicrosecond(Some(value), timezone),
+                literal::TimeUnit::Nanosecond => ScalarValue::TimestampNanosecond(Some(value), timezone),
+            }
+        },
+        Value::String(value) => ScalarValue::Utf8(Some(value)),
+    }
+}
+
+//Casts literal to the type of `column`, if it exists within `schema`
+fn typed_literal(schema: &DFSchema, column: Option<&str>, literal: &str) -> Result<Option<Expr>, DataFusionError> {
+    let (column, dtype) = match column.and_then(|column| schema.field_with_unqualified_name(column).ok().map(|field| (column, field.data_type()))) {
+        Some(result) => result,
+        None => return Ok(None),
+    };
+    let typ = match literal_type(dtype) {
+        Some(typ) => typ,
+        None => return Ok(None),
+    };
+
+    match Value::parse(literal, &typ) {
+        Ok(value) => Ok(Some(lit(scalar_value(value).cast_to(dtype)?))),
+        Err(error) => Err(DataFusionError::Plan(format!("{column}: {error}"))),
+    }
+}
+
+fn datafusion_function(function: expr::Function, args: Vec<expr::Operand>) -> Expr {
+    use datafusion::functions::expr_fn;
+
+    let args = args.into_iter().map(expr::Operand::into_datafusion_expr).collect::<Vec<_>>();
+    let arg = || args.first().cloned().unwrap_or_else(|| lit(ScalarValue::Null));
+    match function {
+        expr::Function::Upper => expr_fn::upper(arg()),
+        expr::Function::Lower => expr_fn::lower(arg()),
+        expr::Function::Length => expr_fn::character_length(arg()),
+        expr::Function::Trim => expr_fn::btrim(vec![arg()]),
+        expr::Function::Abs => expr_fn::abs(arg()),
+        expr::Function::Coalesce => expr_fn::coalesce(args),
+        expr::Function::DateTrunc(unit) => expr_fn::date_trunc(lit(unit.as_sql_name()), arg()),
+        expr::Function::Year => expr_fn::date_part(lit("year"), arg()),
+        expr::Function::Month => expr_fn::date_part(lit("month"), arg()),
+        expr::Function::Day => expr_fn::date_part(lit("day"), arg()),
+        expr::Function::Hour => expr_fn::date_part(lit("hour"), arg()),
+    }
+}
+
+impl expr::Operand {
+    ///Converts into datafusion expression
+    pub fn into_datafusion_expr(self) -> Expr {
+        match self {
+            expr::Operand::Literal(literal) => lit(literal),
+            expr::Operand::Number(number) => lit(scalar_value(Value::from_number(&number))),
+            expr::Operand::Identifier(ident) => col(ident),
+            expr::Operand::Arithmetic { left, operator, right } => {
+                let left = left.into_datafusion_expr();
+                let right = right.into_datafusion_expr();
+                match operator {
+                    expr::ArithmeticOperator::Add => left + right,
+                    expr::ArithmeticOperator::Subtract => left - right,
+                    expr::ArithmeticOperator::Multiply => left * right,
+                    expr::ArithmeticOperator::Divide => left / right,
+                    expr::ArithmeticOperator::Modulo => left % right,
+                }
+            },
+            expr::Operand::Function { function, args } => datafusion_function(function, args),
+        }
+    }
+
+    ///Converts into datafusion expression, casting literal to the type of `column` within `schema`
+    pub fn into_datafusion_typed_expr(self, schema: &DFSchema, column: Option<&str>) -> Result<Expr, DataFusionError> {
+        match self {
+            expr::Operand::Literal(ref literal) | expr::Operand::Number(ref literal) => match typed_literal(schema, column, literal)? {
+                Some(expr) => Ok(expr),
+                None => Ok(self.into_datafusion_expr()),
+            },
+            operand => Ok(operand.into_datafusion_expr()),
         }
+    }
+}
+
+impl expr::Aggregate {
+    ///Converts into datafusion aggregation expression
+    pub fn into_datafusion_expr(self) -> Expr {
+        use datafusion::functions_aggregate::{expr_fn, first_last};
+
+        let argument = match self.argument {
+            Some(argument) => argument.into_datafusion_expr(),
+            None => return expr_fn::count(lit("*")).alias(self.name),
+        };
+        let expr = match self.function {
+            expr::Aggregation::Count => expr_fn::count(argument),
+            expr::Aggregation::CountDistinct => expr_fn::count_distinct(argument),
+            expr::Aggregation::Sum => expr_fn::sum(argument),
+            expr::Aggregation::Min => expr_fn::min(argument),
+            expr::Aggregation::Max => expr_fn::max(argument),
+            expr::Aggregation::Mean => expr_fn::avg(argument),
+            expr::Aggregation::First => first_last::first_value_udaf().call(vec![argument]),
+            expr::Aggregation::Last => first_last::last_value_udaf().call(vec![argument]),
+        };
+        expr.alias(self.name)
+    }
+}
+
+impl expr::Expression {
+    ///Converts into datafusion expression
+    ///
+    ///Literals are cast to the type of column from `schema` they are compared against
+    pub fn into_datafusion_expr(self, schema: &DFSchema) -> Result<Expr, DataFusionError> {
+        let expr = match self {
+            expr::Expression::Compare { left, operator, right } => {
+                let left_column = left.as_identifier().map(str::to_owned);
+                let right_column = right.as_identifier().map(str::to_owned);
+                let left = left.into_datafusion_typed_expr(schema, right_column.as_deref())?;
+                let right = right.into_datafusion_typed_expr(schema, left_column.as_deref())?;
+                match operator {
+                    expr::Operator::Less => left.lt(right),
+                    expr::Operator::LessEq => left.lt_eq(right),
+                    expr::Operator::Eq => left.eq(right),
+                    expr::Operator::NotEq => left.not_eq(right),
+                    expr::Operator::GreaterEq => left.gt_eq(right),
+                    expr::Operator::Greater => left.gt(right),
+                }
+            },
+            expr::Expression::And(left, right) => left.into_datafusion_expr(schema)?.and(right.into_datafusion_expr(schema)?),
+            expr::Expression::Or(left, right) => left.into_datafusion_expr(schema)?.or(right.into_datafusion_expr(schema)?),
+            expr::Expression::Not(expression) => datafusion::logical_expr::not(expression.into_datafusion_expr(schema)?),
+            expr::Expression::In { operand, list, negated } => {
+                let mut values = Vec::with_capacity(list.len());
+                for literal in list {
+                    match typed_literal(schema, operand.as_identifier(), &literal)? {
+                        Some(value) => values.push(value),
+                        None => values.push(lit(literal)),
+                    }
+                }
+                operand.into_datafusion_expr().in_list(values, negated)
+            },
+            expr::Expression::Between { operand, low, high, negated } => {
+                let column = operand.as_identifier().map(str::to_owned);
+                let low = low.into_datafusion_typed_expr(schema, column.as_deref())?;
+                let high = high.into_datafusion_typed_expr(schema, column.as_deref())?;
+                if negated {
+                    operand.into_datafusion_expr().not_between(low, high)
+                } else {
+                    operand.into_datafusion_expr().between(low, high)
+                }
+            },
+            expr::Expression::IsNull { operand, negated: false } => operand.into_datafusion_expr().is_null(),
+            expr::Expression::IsNull { operand, negated: true } => operand.into_datafusion_expr().is_not_null(),
+            expr::Expression::Like { operand, pattern, case_insensitive: false, negated: false } => operand.into_datafusion_expr().like(lit(pattern)),
+            expr::Expression::Like { operand, pattern, case_insensitive: false, negated: true } => operand.into_datafusion_expr().not_like(lit(pattern)),
+            expr::Expression::Like { operand, pattern, case_insensitive: true, negated: false } => operand.into_datafusion_expr().ilike(lit(pattern)),
+            expr::Expression::Like { operand, pattern, case_insensitive: true, negated: true } => operand.into_datafusion_expr().not_ilike(lit(pattern)),
+            expr::Expression::Regex { operand, pattern, negated } => {
+                let operator = if negated {
+                    datafusion::logical_expr::Operator::RegexNotMatch
+                } else {
+                    datafusion::logical_expr::Operator::RegexMatch
+                };
+                datafusion::logical_expr::binary_expr(operand.into_datafusion_expr(), operator, lit(pattern))
+            },
+        };
 
-        Ok(df)
+        Ok(expr)
     }
 }
 
+///Executes SQL `statement` over `df` registered as table `name`
+///
+///Table inherits all settings of `df` session, including partition filters inferred from path
+pub async fn sql(df: DataFrame, name: &str, statement: &str) -> Result<DataFrame, DataFusionError> {
+    let (state, plan) = df.into_parts();
+    let ctx = SessionContext::new_with_state(state);
+    let table = DataFrame::new(ctx.state(), plan).into_view();
+    ctx.register_table(name, table)?;
+    ctx.sql(statement).await
+}
+
+///Writes `df` as Arrow IPC file(s) into `path`, partitioning by `partition_by` columns
+///
+///Datafusion has no `DataFrame` method for Arrow output so it is written using `COPY` plan
+pub async fn write_arrow(df: DataFrame, path: &str, partition_by: Vec<String>) -> Result<(), DataFusionError> {
+    use datafusion::datasource::file_format::{arrow::ArrowFormatFactory, format_as_file_type};
+
+    let (state, plan) = df.into_parts();
+    let file_type = format_as_file_type(Arc::new(ArrowFormatFactory::new()));
+    let plan = datafusion::logical_expr::LogicalPlanBuilder::copy_to(plan, path.to_owned(), file_type, Default::default(), partition_by)?.build()?;
+    DataFrame::new(state, plan).collect().await?;
+    Ok(())
+}
+
 #[cfg(any(feature = "aws", feature = "gcp"))]
 struct BucketNameMissing;
 
@@ -213,6 +748,673 @@ impl core::fmt::Display for BucketNameMissing {
 #[cfg(any(feature = "aws", feature = "gcp"))]
 impl std::error::Error for BucketNameMissing {}
 
+//Checks whether `url` points to existing object rather than prefix
+async fn is_object(env: &RuntimeEnv, url: &datafusion::datasource::listing::ListingTableUrl) -> bool {
+    let options = GetOptions {
+        head: true,
+        ..GetOptions::default()
+    };
+    match env.object_store(url) {
+        Ok(store) => store.get_opts(url.prefix(), options).await.is_ok(),
+        Err(_) => false,
+    }
+}
+
+///Detects format of data within `path` (file, directory or URI prefix) by probing first listed data file
+///
+///Returns `None` if format cannot be detected
+pub async fn detect_format(path: &str) -> Result<Option<DetectedFormat>, DataFusionError> {
+    let env = create_runtime(path).await?;
+    let ctx = SessionContext::new_with_config_rt(SessionConfig::new(), env);
+    let state = ctx.state();
+    let url = datafusion::datasource::listing::ListingTableUrl::parse(path)?;
+    let store = ctx.runtime_env().object_store(&url)?;
+
+    if let Some(Ok(_)) = store.list(Some(&url.prefix().child(delta::LOG_DIR))).next().await {
+        return Ok(Some(DetectedFormat {
+            format: FileFormat::Delta,
+            compression: None,
+        }));
+    }
+
+    eprintln!(">{url}: Detecting format");
+    let mut files = url.list_all_files(&state, &*store, "").await?;
+    while let Some(file) = files.next().await {
+        let file = file?;
+        let is_hidden = match file.location.prefix_match(url.prefix()) {
+            Some(mut parts) => parts.any(|part| DetectedFormat::is_hidden(part.as_ref())),
+            None => false,
+        };
+        if is_hidden || file.size == 0 {
+            continue;
+        }
+
+        let options = GetOptions {
+            range: Some(GetRange::Bounded(0..file.size.min(DetectedFormat::MAGIC_LEN as u64))),
+            ..GetOptions::default()
+        };
+        let head = store.get_opts(&file.location, options).await?.bytes().await?;
+        eprintln!(">{}: Probed for format", file.location);
+        return Ok(DetectedFormat::detect(file.location.filename().unwrap_or_default(), &head));
+    }
+
+    Ok(None)
+}
+
+//Maximum number of parquet footers fetched at once
+const FOOTER_CONCURRENCY: usize = 32;
+
+///Reads footers of parquet files within `path` (file, directory or URI prefix)
+///
+///Footers are fetched concurrently, returning location of every file alongside its footer in order of listing
+pub async fn read_parquet_footers(path: &str) -> Result<Vec<(String, parquet::FileMetadata)>, DataFusionError> {
+    let env = create_runtime(path).await?;
+    let ctx = SessionContext::new_with_config_rt(SessionConfig::new(), env);
+    let state = ctx.state();
+    let url = datafusion::datasource::listing::ListingTableUrl::parse(path)?;
+    let store = ctx.runtime_env().object_store(&url)?;
+
+    let mut objects = Vec::new();
+    let mut files = url.list_all_files(&state, &*store, "").await?;
+    while let Some(file) = files.next().await {
+        let file = file?;
+        let is_hidden = match file.location.prefix_match(url.prefix()) {
+            Some(mut parts) => parts.any(|part| DetectedFormat::is_hidden(part.as_ref())),
+            None => false,
+        };
+        //Single file is accepted regardless of its extension
+        if is_hidden || (url.is_collection() && file.location.extension() != Some("parquet")) {
+            continue;
+        }
+        objects.push(file);
+    }
+    drop(files);
+
+    let footers = futures_util::stream::iter(objects).map(|file| read_parquet_footer(&*store, file)).buffered(FOOTER_CONCURRENCY).collect::<Vec<_>>().await;
+    footers.into_iter().collect()
+}
+
+async fn read_parquet_footer(store: &dyn ObjectStore, file: object_store::ObjectMeta) -> Result<(String, parquet::FileMetadata), DataFusionError> {
+    let location = file.location.to_string();
+    let error = |error: parquet::ParquetError| DataFusionError::Execution(format!("{location}: {error}"));
+    let tail_len = parquet::TAIL_LEN as u64;
+    if file.size < tail_len {
+        return Err(DataFusionError::Execution(format!("{location}: File is too small to be parquet")));
+    }
+
+    let options = GetOptions {
+        range: Some(GetRange::Bounded(file.size - tail_len..file.size)),
+        ..GetOptions::default()
+    };
+    let tail = store.get_opts(&file.location, options).await?.bytes().await?;
+    let len = parquet::footer_len(&tail).map_err(error)? as u64;
+    if len + tail_len > file.size {
+        return Err(DataFusionError::Execution(format!("{location}: Footer length {len} exceeds file size")));
+    }
+
+    let options = GetOptions {
+        range: Some(GetRange::Bounded(file.size - tail_len - len..file.size - tail_len)),
+        ..GetOptions::default()
+    };
+    let footer = store.get_opts(&file.location, options).await?.bytes().await?;
+    let footer = parquet::FileMetadata::parse(&footer).map_err(error)?;
+    Ok((location, footer))
+}
+
+///Computes statistics of every column in `df`
+///
+///Data is aggregated in a single streaming pass. Distinct count is approximate
+pub async fn describe(df: DataFrame) -> Result<Vec<format::ColumnStats>, DataFusionError> {
+    use datafusion::functions_aggregate::expr_fn;
+
+    let fields = df.schema().fields().clone();
+    let mut aggregates = vec![expr_fn::count(lit("*")).alias("rows")];
+    for (idx, field) in fields.iter().enumerate() {
+        let column = col(field.name());
+        let data_type = field.data_type();
+        aggregates.push(expr_fn::count(column.clone()).alias(format!("{idx}:count")));
+        //Nested values have no order
+        if !data_type.is_nested() {
+            aggregates.push(expr_fn::min(column.clone()).alias(format!("{idx}:min")));
+            aggregates.push(expr_fn::max(column.clone()).alias(format!("{idx}:max")));
+            //Sketch supports integers and text only, so other types are counted by their text
+            let distinct = match data_type {
+                DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => column.clone(),
+                data_type if data_type.is_integer() => column.clone(),
+                _ => datafusion::logical_expr::cast(column.clone(), DataType::Utf8),
+            };
+            aggregates.push(expr_fn::approx_distinct(distinct).alias(format!("{idx}:distinct")));
+        }
+        //Includes decimals, same as within polars
+        if data_type.is_numeric() {
+            aggregates.push(expr_fn::avg(datafusion::logical_expr::cast(column, DataType::Float64)).alias(format!("{idx}:mean")));
+        }
+    }
+
+    let batches = df.aggregate(Vec::new(), aggregates)?.collect().await?;
+    let batch = match batches.first() {
+        Some(batch) if batch.num_rows() > 0 => batch,
+        _ => return Err(DataFusionError::Internal("Aggregation produced no rows".to_owned())),
+    };
+    let value = |name: &str| batch.column_by_name(name).and_then(|column| format::datafusion::cell(column.as_ref(), 0));
+    let rows = format::count(value("rows"));
+    Ok(fields.iter().enumerate().map(|(idx, field)| format::ColumnStats {
+        name: field.name().clone(),
+        rows,
+        nulls: rows.zip(format::count(value(&format!("{idx}:count")))).map(|(rows, count)| rows.saturating_sub(count)),
+        min: value(&format!("{idx}:min")),
+        max: value(&format!("{idx}:max")),
+        mean: match value(&format!("{idx}:mean")) {
+            Some(Value::Float(mean)) => Some(mean),
+            _ => None,
+        },
+        distinct: format::count(value(&format!("{idx}:distinct"))),
+    }).collect())
+}
+
+///Counts rows of `df` grouped by `group_by` columns in a single streaming pass
+///
+///Groups are sorted by their values
+pub async fn count(df: DataFrame, group_by: &[String]) -> Result<Vec<(Vec<format::Cell>, u64)>, DataFusionError> {
+    use datafusion::functions_aggregate::expr_fn;
+
+    let group_exprs = group_by.iter().map(|column| col(column)).collect::<Vec<_>>();
+    //Nulls go first as within polars and footer counts
+    let sort = group_by.iter().map(|column| col(column).sort(true, true)).collect::<Vec<_>>();
+    let mut df = df.aggregate(group_exprs, vec![expr_fn::count(lit("*"))])?;
+    if !sort.is_empty() {
+        df = df.sort(sort)?;
+    }
+
+    let mut counts = Vec::new();
+    for batch in df.collect().await? {
+        for idx in 0..batch.num_rows() {
+            let mut group = batch.columns().iter().map(|column| format::datafusion::cell(column.as_ref(), idx)).collect::<Vec<_>>();
+            //Count is the last column
+            let count = group.pop().and_then(format::count).unwrap_or(0);
+            counts.push((group, count));
+        }
+    }
+    Ok(counts)
+}
+
+///Result of comparing two data frames by key
+pub struct Diff {
+    ///Key columns
+    pub keys: Vec<String>,
+    ///Columns compared in both data frames
+    pub columns: Vec<String>,
+    ///Columns present in only one of data frames, which are not compared
+    pub skipped: Vec<String>,
+    ///Rows present in left data frame only
+    pub only_left: DataFrame,
+    ///Rows present in right data frame only
+    pub only_right: DataFrame,
+    //Changed rows: key columns followed by left value, right value and change mark of every compared column
+    changed: DataFrame,
+    //Number of rows of every kind followed by number of changes of every compared column
+    counts: DataFrame,
+}
+
+///Compares `left` and `right` by `keys`, where `right` is scanned from `right_path`
+///
+///Rows must be unique by `keys`, otherwise error reports number of duplicate keys. Nulls are compared as equal values, except within keys
+pub async fn diff(left: DataFrame, right: DataFrame, right_path: &str, keys: &[String]) -> Result<Diff, DataFusionError> {
+    use datafusion::functions_aggregate::expr_fn;
+    use datafusion::logical_expr::{binary_expr, cast, JoinType, Operator};
+
+    //Plan is executed by left side, so it needs access to storage of right side
+    let right_url = datafusion::datasource::listing::ListingTableUrl::parse(right_path)?;
+    let right_store = right.task_ctx().runtime_env().object_store(&right_url)?;
+    left.task_ctx().runtime_env().register_object_store(right_url.object_store().as_ref(), right_store);
+
+    let left_fields = left.schema().fields().clone();
+    let right_fields = right.schema().fields().clone();
+    let has_field = |fields: &datafusion::common::arrow::datatypes::Fields, name: &str| fields.iter().any(|field| field.name() == name);
+    for key in keys {
+        if !has_field(&left_fields, key) || !has_field(&right_fields, key) {
+            return Err(DataFusionError::Execution(format!("Key '{key}' must be present in both data")));
+        }
+    }
+
+    let mut columns = Vec::new();
+    let mut skipped = Vec::new();
+    for field in left_fields.iter().filter(|field| !keys.contains(field.name())) {
+        match has_field(&right_fields, field.name()) {
+            true => columns.push(field.name().clone()),
+            false => skipped.push(field.name().clone()),
+        }
+    }
+    skipped.extend(right_fields.iter().filter(|field| !has_field(&left_fields, field.name())).map(|field| field.name().clone()));
+    if columns.is_empty() {
+        return Err(DataFusionError::Execution("No columns to compare besides keys".to_owned()));
+    }
+
+    //Duplicate key would match every row with the same key on the other side
+    let has_key = keys.iter().map(|key| col(key).is_not_null()).reduce(|left, right| left.and(right)).unwrap_or(lit(true));
+    for (side, df) in [("Left", &left), ("Right", &right)] {
+        let duplicates = df.clone().filter(has_key.clone())?
+                                   .aggregate(keys.iter().map(|key| col(key)).collect(), vec![expr_fn::count(lit(1)).alias(DUPLICATE_COLUMN)])?
+                                   .filter(col(DUPLICATE_COLUMN).gt(lit(1)))?
+                                   .count().await?;
+        if duplicates > 0 {
+            return Err(DataFusionError::Execution(format!("{side} data has {duplicates} duplicate keys")));
+        }
+    }
+
+    //Rename right columns to avoid ambiguity within join
+    let right_name = |name: &str| format!("{name}{DIFF_RIGHT_SUFFIX}");
+    let renamed = right.select(right_fields.iter().map(|field| col(field.name()).alias(right_name(field.name()))).collect::<Vec<_>>())?;
+    let left_on = keys.iter().map(String::as_str).collect::<Vec<_>>();
+    let right_on = keys.iter().map(|key| right_name(key)).collect::<Vec<_>>();
+    let right_on = right_on.iter().map(String::as_str).collect::<Vec<_>>();
+
+    let only_left = left.clone().join(renamed.clone(), JoinType::LeftAnti, &left_on, &right_on, None)?;
+    let only_right = left.clone().join(renamed.clone(), JoinType::RightAnti, &left_on, &right_on, None)?
+                                 .select(right_fields.iter().map(|field| col(right_name(field.name())).alias(field.name())).collect::<Vec<_>>())?;
+
+    let is_changed = |column: &String| binary_expr(col(column), Operator::IsDistinctFrom, col(right_name(column)));
+    let is_row_changed = columns.iter().map(is_changed).reduce(|left, right| left.or(right)).unwrap_or(lit(false));
+    let mut select = keys.iter().map(|key| col(key)).collect::<Vec<_>>();
+    for column in columns.iter() {
+        select.push(col(column));
+        select.push(col(right_name(column)));
+        select.push(is_changed(column).alias(format!("{column}{DIFF_CHANGED_SUFFIX}")));
+    }
+    let changed = left.clone().join(renamed.clone(), JoinType::Inner, &left_on, &right_on, None)?.filter(is_row_changed.clone())?.select(select)?;
+
+    //Every kind of rows is counted within single full join
+    let rows = |expr: Expr, name: &str| expr_fn::sum(cast(expr, DataType::UInt64)).alias(name);
+    let left_present = col(DIFF_PRESENT_COLUMN);
+    let right_present = col(right_name(DIFF_PRESENT_COLUMN));
+    let both_present = left_present.clone().is_not_null().and(right_present.clone().is_not_null());
+    let mut counts = vec![
+        rows(right_present.is_null(), format::DiffKind::OnlyLeft.as_str()),
+        rows(left_present.is_null(), format::DiffKind::OnlyRight.as_str()),
+        rows(both_present.clone().and(is_row_changed), format::DiffKind::Changed.as_str()),
+    ];
+    counts.extend(columns.iter().map(|column| rows(both_present.clone().and(is_changed(column)), &format!("{column}{DIFF_CHANGED_SUFFIX}"))));
+    let left = left.with_column(DIFF_PRESENT_COLUMN, lit(true))?;
+    let renamed = renamed.with_column(&right_name(DIFF_PRESENT_COLUMN), lit(true))?;
+    let counts = left.join(renamed, JoinType::Full, &left_on, &right_on, None)?.aggregate(Vec::new(), counts)?;
+
+    Ok(Diff {
+        keys: keys.to_vec(),
+        columns,
+        skipped,
+        only_left,
+        only_right,
+        changed,
+        counts,
+    })
+}
+
+impl Diff {
+    ///Returns changes of rows present in both: key columns followed by changed `column` with its `left` and `right` values
+    ///
+    ///Every value is formatted as text by [format::Cell], so changes are reported identically by both backends.
+    ///Changed rows are unpivoted batch by batch as they are streamed, so changes are never collected into memory
+    pub async fn changes(&self) -> Result<DataFrame, DataFusionError> {
+        use datafusion::catalog::streaming::StreamingTable;
+        use datafusion::common::arrow::datatypes::{Field, Schema};
+
+        let names = format::DiffChanges::new(&self.keys, &self.columns).names;
+        let schema = Arc::new(Schema::new(names.iter().map(|name| Field::new(name, DataType::Utf8, true)).collect::<Vec<_>>()));
+        let changes = DiffChangesStream {
+            plan: self.changed.clone().create_physical_plan().await?,
+            schema: schema.clone(),
+            keys: self.keys.clone(),
+            columns: self.columns.clone(),
+        };
+        let table = StreamingTable::try_new(schema, vec![Arc::new(changes)])?;
+        //Keep session of compared data, so that changes can be written into the same storage
+        let (state, _) = self.changed.clone().into_parts();
+        SessionContext::new_with_state(state).read_table(Arc::new(table))
+    }
+
+    ///Counts rows of every kind, including number of changes per column
+    pub async fn summary(self) -> Result<Vec<format::DiffCount>, DataFusionError> {
+        let batches = self.counts.collect().await?;
+        let batch = batches.iter().find(|batch| batch.num_rows() > 0);
+        Ok(format::diff_summary(&self.columns, |idx| {
+            batch.and_then(|batch| format::count(format::datafusion::cell(batch.column(idx).as_ref(), 0))).unwrap_or(0)
+        }))
+    }
+}
+
+//Stream of changes unpivoted from every batch of changed rows
+#[derive(Debug, Clone)]
+struct DiffChangesStream {
+    plan: Arc<dyn datafusion::physical_plan::ExecutionPlan>,
+    schema: datafusion::common::arrow::datatypes::SchemaRef,
+    keys: Vec<String>,
+    columns: Vec<String>,
+}
+
+impl DiffChangesStream {
+    fn unpivot(&self, batch: &datafusion::common::arrow::array::RecordBatch) -> Result<datafusion::common::arrow::array::RecordBatch, DataFusionError> {
+        use datafusion::common::arrow::array::{ArrayRef, RecordBatch, StringArray};
+
+        let mut changes = format::DiffChanges::new(&self.keys, &self.columns);
+        let mut row = Vec::with_capacity(batch.num_columns());
+        for idx in 0..batch.num_rows() {
+            row.clear();
+            row.extend(batch.columns().iter().map(|column| format::datafusion::cell(column.as_ref(), idx)));
+            changes.push(&row);
+        }
+        let columns = changes.take().into_iter().map(|values| Arc::new(StringArray::from(values)) as ArrayRef).collect();
+        Ok(RecordBatch::try_new(self.schema.clone(), columns)?)
+    }
+}
+
+impl datafusion::physical_plan::streaming::PartitionStream for DiffChangesStream {
+    #[inline(always)]
+    fn schema(&self) -> &datafusion::common::arrow::datatypes::SchemaRef {
+        &self.schema
+    }
+
+    fn execute(&self, ctx: Arc<datafusion::execution::TaskContext>) -> datafusion::execution::SendableRecordBatchStream {
+        use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+        use futures_util::TryStreamExt;
+
+        let changes = self.clone();
+        let batches = futures_util::stream::once(futures_util::future::ready(datafusion::physical_plan::execute_stream(self.plan.clone(), ctx))).try_flatten();
+        Box::pin(RecordBatchStreamAdapter::new(self.schema.clone(), batches.map(move |batch| batch.and_then(|batch| changes.unpivot(&batch)))))
+    }
+}
+
+fn delta_error(error: delta::DeltaError) -> DataFusionError {
+    DataFusionError::External(Box::new(error))
+}
+
+//Collects non-null strings of list `array` at `row`
+fn string_list(array: &dyn datafusion::common::arrow::array::Array, row: usize) -> Result<Vec<String>, DataFusionError> {
+    use datafusion::common::arrow::array::{Array, AsArray};
+    use datafusion::common::arrow::compute::cast;
+
+    let list = array.as_list::<i32>();
+    if list.is_null(row) {
+        return Ok(Vec::new());
+    }
+    let values = cast(&list.value(row), &DataType::Utf8)?;
+    Ok(values.as_string::<i32>().iter().flatten().map(str::to_owned).collect())
+}
+
+//Collects entries of map `array` at `row`
+fn string_map(array: &datafusion::common::arrow::array::MapArray, row: usize) -> Result<Vec<(String, Option<String>)>, DataFusionError> {
+    use datafusion::common::arrow::array::{Array, AsArray};
+    use datafusion::common::arrow::compute::cast;
+
+    if array.is_null(row) {
+        return Ok(Vec::new());
+    }
+    let entries = array.value(row);
+    let keys = cast(entries.column(0), &DataType::Utf8)?;
+    let keys = keys.as_string::<i32>();
+    let values = cast(entries.column(1), &DataType::Utf8)?;
+    let values = values.as_string::<i32>();
+    Ok((0..keys.len()).filter(|idx| keys.is_valid(*idx)).map(|idx| (keys.value(idx).to_owned(), values.is_valid(idx).then(|| values.value(idx).to_owned()))).collect())
+}
+
+//Adds data files, protocol and metadata of Delta checkpoint to `snapshot`
+async fn read_delta_checkpoint(ctx: &SessionContext, url: &str, snapshot: &mut delta::Snapshot) -> Result<(), DataFusionError> {
+    use datafusion::common::arrow::array::{Array, AsArray};
+    use datafusion::common::arrow::compute::cast;
+    use datafusion::common::arrow::datatypes::Int64Type;
+
+    let df = ctx.read_parquet(url, datafusion::prelude::ParquetReadOptions::default()).await?;
+    let columns = ["add", "protocol", "metaData"].into_iter().filter(|column| df.schema().field_with_unqualified_name(column).is_ok()).collect::<Vec<_>>();
+    //Every row holds single action, so columns of other actions are null
+    let batches = df.select_columns(&columns)?.collect().await?;
+    for batch in batches {
+        if let Some(protocol) = batch.column_by_name("protocol").map(|protocol| protocol.as_struct()) {
+            let versions = match protocol.column_by_name("minReaderVersion") {
+                Some(versions) => cast(versions, &DataType::Int64)?,
+                None => return Err(DataFusionError::Execution(format!("{url}: Checkpoint has no `protocol.minReaderVersion`"))),
+            };
+            let versions = versions.as_primitive::<Int64Type>();
+            let features = protocol.column_by_name("readerFeatures");
+            for row in (0..protocol.len()).filter(|row| protocol.is_valid(*row) && versions.is_valid(*row)) {
+                snapshot.set_protocol(delta::Protocol {
+                    min_reader_version: versions.value(row),
+                    reader_features: match features {
+                        Some(features) => string_list(features.as_ref(), row)?,
+                        None => Vec::new(),
+                    },
+                });
+            }
+        }
+
+        if let Some(metadata) = batch.column_by_name("metaData").map(|metadata| metadata.as_struct()) {
+            let (schemas, partition_columns) = match (metadata.column_by_name("schemaString"), metadata.column_by_name("partitionColumns")) {
+                (Some(schemas), Some(partition_columns)) => (cast(schemas, &DataType::Utf8)?, partition_columns),
+                _ => return Err(DataFusionError::Execution(format!("{url}: Checkpoint has no `metaData.schemaString` or `metaData.partitionColumns`"))),
+            };
+            let schemas = schemas.as_string::<i32>();
+            let configuration = metadata.column_by_name("configuration").map(|configuration| configuration.as_map());
+            for row in (0..metadata.len()).filter(|row| metadata.is_valid(*row) && schemas.is_valid(*row)) {
+                let configuration = match configuration {
+                    Some(configuration) => string_map(configuration, row)?.into_iter().flat_map(|(key, value)| value.map(|value| (key, value))).collect(),
+                    None => Vec::new(),
+                };
+                snapshot.set_metadata(delta::Metadata {
+                    schema_string: schemas.value(row).to_owned(),
+                    partition_columns: string_list(partition_columns.as_ref(), row)?,
+                    configuration,
+                });
+            }
+        }
+
+        let add = match batch.column_by_name("add") {
+            Some(add) => add.as_struct(),
+            None => continue,
+        };
+        let (paths, sizes) = match (add.column_by_name("path"), add.column_by_name("size")) {
+            (Some(paths), Some(sizes)) => (cast(paths, &DataType::Utf8)?, cast(sizes, &DataType::Int64)?),
+            _ => return Err(DataFusionError::Execution(format!("{url}: Checkpoint has no `add.path` or `add.size`"))),
+        };
+        let paths = paths.as_string::<i32>();
+        let sizes = sizes.as_primitive::<Int64Type>();
+        let partition_values = add.column_by_name("partitionValues").map(|values| values.as_map());
+
+        for row in 0..add.len() {
+            //Rows of other actions have no `add`
+            if add.is_null(row) || paths.is_null(row) {
+                continue;
+            }
+
+            let partitions = match partition_values {
+                Some(values) => string_map(values, row)?,
+                None => Vec::new(),
+            };
+            let size = sizes.is_valid(row).then(|| sizes.value(row)).unwrap_or(0) as u64;
+            snapshot.add(delta::DataFile::new(paths.value(row), size, partitions).map_err(delta_error)?);
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+//Active files of Delta table, scanned as parquet source with partition values of every file attached to it
+//
+//Files are pruned by filters on partition columns and split into file groups to scan them in parallel
+struct DeltaTable {
+    store_url: datafusion::execution::object_store::ObjectStoreUrl,
+    file_schema: datafusion::common::arrow::datatypes::SchemaRef,
+    partition_fields: Vec<datafusion::common::arrow::datatypes::FieldRef>,
+    schema: datafusion::common::arrow::datatypes::SchemaRef,
+    files: Vec<datafusion::datasource::listing::PartitionedFile>,
+}
+
+#[async_trait::async_trait]
+impl datafusion::catalog::TableProvider for DeltaTable {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn schema(&self) -> datafusion::common::arrow::datatypes::SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> datafusion::logical_expr::TableType {
+        datafusion::logical_expr::TableType::Base
+    }
+
+    fn supports_filters_pushdown(&self, filters: &[&Expr]) -> Result<Vec<datafusion::logical_expr::TableProviderFilterPushDown>, DataFusionError> {
+        //Filters only prune files by partition values, so they are applied to scanned rows anyway
+        Ok(vec![datafusion::logical_expr::TableProviderFilterPushDown::Inexact; filters.len()])
+    }
+
+    async fn scan(&self, state: &dyn datafusion::catalog::Session, projection: Option<&Vec<usize>>, filters: &[Expr], limit: Option<usize>) -> Result<Arc<dyn datafusion::physical_plan::ExecutionPlan>, DataFusionError> {
+        use datafusion::datasource::physical_plan::{FileGroup, FileScanConfigBuilder, ParquetSource};
+        use datafusion::datasource::source::DataSourceExec;
+        use datafusion::datasource::table_schema::TableSchema;
+
+        let files = self.prune_files(state, filters)?;
+        let mut file_groups = FileGroup::new(files).split_files(state.config().target_partitions());
+        if file_groups.is_empty() {
+            file_groups.push(FileGroup::new(Vec::new()));
+        }
+
+        let table_schema = TableSchema::new(self.file_schema.clone(), self.partition_fields.clone());
+        let config = FileScanConfigBuilder::new(self.store_url.clone(), Arc::new(ParquetSource::new(table_schema)))
+            .with_file_groups(file_groups)
+            .with_projection_indices(projection.cloned())?
+            .with_limit(limit)
+            .build();
+        Ok(DataSourceExec::from_data_source(config))
+    }
+}
+
+impl DeltaTable {
+    //Returns files with partition values matching `filters` that refer only to partition columns
+    fn prune_files(&self, state: &dyn datafusion::catalog::Session, filters: &[Expr]) -> Result<Vec<datafusion::datasource::listing::PartitionedFile>, DataFusionError> {
+        use datafusion::common::arrow::array::{AsArray, RecordBatch, RecordBatchOptions};
+        use datafusion::common::arrow::datatypes::Schema;
+        use datafusion::datasource::listing::helpers::expr_applicable_for_cols;
+
+        let columns = self.partition_fields.iter().map(|field| field.name().as_str()).collect::<Vec<_>>();
+        let filter = datafusion::logical_expr::utils::conjunction(filters.iter().filter(|filter| expr_applicable_for_cols(&columns, filter)).cloned());
+        let filter = match filter {
+            Some(filter) if !self.files.is_empty() => filter,
+            _ => return Ok(self.files.clone()),
+        };
+
+        //Every file is row of its partition values
+        let schema = Arc::new(Schema::new(self.partition_fields.clone()));
+        let mut values = Vec::with_capacity(self.partition_fields.len());
+        for idx in 0..self.partition_fields.len() {
+            values.push(ScalarValue::iter_to_array(self.files.iter().map(|file| file.partition_values[idx].clone()))?);
+        }
+        let options = RecordBatchOptions::new().with_row_count(Some(self.files.len()));
+        let batch = RecordBatch::try_new_with_options(schema.clone(), values, &options)?;
+
+        let filter = state.create_physical_expr(filter, &DFSchema::try_from(schema)?)?;
+        let matches = filter.evaluate(&batch)?.into_array(batch.num_rows())?;
+        let matches = matches.as_boolean_opt().ok_or_else(|| DataFusionError::Internal("Partition filter must be boolean".to_owned()))?;
+        Ok(self.files.iter().zip(matches.iter()).filter(|(_, is_match)| *is_match == Some(true)).map(|(file, _)| file.clone()).collect())
+    }
+}
+
+//Reads Delta table at `path` returning data frame, its file schema and partition columns
+//
+//Only files listed by transaction log are read within single scan, with partition values taken from the log.
+//Table schema from the log types partition columns and columns missing in data files
+async fn scan_delta(ctx: &SessionContext, path: &str, version: DeltaVersion, columns: &[ColumnSchema]) -> Result<(DataFrame, datafusion::common::arrow::datatypes::SchemaRef, Vec<(String, DataType)>), DataFusionError> {
+    use datafusion::common::arrow::datatypes::{Field, Schema};
+    use datafusion::datasource::listing::{ListingTableUrl, PartitionedFile};
+    use datafusion::datasource::file_format::parquet::ParquetFormat;
+    use datafusion::datasource::file_format::FileFormat as _;
+
+    let root = path.trim_end_matches('/');
+    let url = ListingTableUrl::parse(path)?;
+    let store = ctx.runtime_env().object_store(&url)?;
+    let log_prefix = url.prefix().child(delta::LOG_DIR);
+
+    eprintln!(">{path}: Listing Delta log");
+    let mut log = Vec::new();
+    let mut log_files = store.list(Some(&log_prefix));
+    while let Some(file) = log_files.next().await {
+        let file = file?;
+        if let Some(name) = file.location.filename() {
+            log.push(delta::LogFile {
+                name: name.to_owned(),
+                last_modified: file.last_modified.timestamp_millis(),
+            });
+        }
+    }
+
+    let segment = delta::LogSegment::new(&log, version).map_err(delta_error)?;
+    eprintln!(">{path}: Reading Delta table version={}", segment.version);
+    let mut snapshot = delta::Snapshot::default();
+    for checkpoint in segment.checkpoint.iter() {
+        read_delta_checkpoint(ctx, &format!("{root}/{}/{checkpoint}", delta::LOG_DIR), &mut snapshot).await?;
+    }
+    for commit in segment.commits.iter() {
+        let commit = store.get_opts(&log_prefix.child(commit.as_str()), GetOptions::default()).await?.bytes().await?;
+        let commit = core::str::from_utf8(&commit).map_err(|error| DataFusionError::External(Box::new(error)))?;
+        snapshot.apply_commit(commit).map_err(delta_error)?;
+    }
+    snapshot.check_support().map_err(delta_error)?;
+
+    let table_schema = snapshot.schema().map_err(delta_error)?;
+    let table_partition_cols = snapshot.partition_columns().into_iter().map(|column| {
+        let user_type = columns.iter().find(|user_column| user_column.name == column).map(|user_column| &user_column.dtype);
+        let table_type = table_schema.iter().find(|table_column| table_column.name == column).and_then(|table_column| table_column.dtype.as_ref());
+        let dtype = user_type.or(table_type).map_or(DataType::Utf8View, arrow_type);
+        (column, dtype)
+    }).collect::<Vec<_>>();
+
+    let mut files = Vec::new();
+    for file in snapshot.into_files() {
+        let file_url = if file.is_absolute() {
+            ListingTableUrl::parse(&file.path)?
+        } else {
+            ListingTableUrl::parse(format!("{root}/{}", file.path))?
+        };
+        if file_url.object_store() != url.object_store() {
+            return Err(DataFusionError::NotImplemented(format!("{path}: Data file '{}' is outside of table's storage", file.path)));
+        }
+        let mut partition_values = Vec::with_capacity(table_partition_cols.len());
+        for (column, dtype) in table_partition_cols.iter() {
+            let value = ScalarValue::Utf8(file.partition_value(column).map(str::to_owned));
+            partition_values.push(value.cast_to(dtype)?);
+        }
+        //Location is set from parsed URL, as path within log is already decoded
+        let mut partitioned = PartitionedFile::new(String::new(), file.size);
+        partitioned.object_meta.location = file_url.prefix().clone();
+        partitioned.partition_values = partition_values;
+        files.push(partitioned);
+    }
+
+    let first_file = match files.first() {
+        Some(first_file) => first_file.object_meta.clone(),
+        None => return Err(DataFusionError::Execution(format!("{path}: Delta table has no data files"))),
+    };
+    eprintln!(">{}: Inferring schema", first_file.location);
+    let schema = ParquetFormat::new().infer_schema(&ctx.state(), &store, &[first_file]).await?;
+    //Columns added to the table later are missing in older files
+    let mut fields = schema.fields().iter().map(|field| field.as_ref().clone()).collect::<Vec<_>>();
+    for column in table_schema.iter().filter(|column| !table_partition_cols.iter().any(|(partition, _)| *partition == column.name)) {
+        if let (false, Some(dtype)) = (fields.iter().any(|field| *field.name() == column.name), column.dtype.as_ref()) {
+            fields.push(Field::new(column.name.as_str(), arrow_type(dtype), true));
+        }
+    }
+    let schema = Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()));
+    let schema = apply_schema(schema, columns, &table_partition_cols)?;
+
+    let partition_fields = table_partition_cols.iter().map(|(column, dtype)| Arc::new(Field::new(column.as_str(), dtype.clone(), true))).collect::<Vec<_>>();
+    let table = DeltaTable {
+        store_url: url.object_store(),
+        file_schema: schema.clone(),
+        schema: Arc::new(Schema::new(schema.fields().iter().cloned().chain(partition_fields.iter().cloned()).collect::<Vec<_>>())),
+        partition_fields,
+        files,
+    };
+    let df = ctx.read_table(Arc::new(table))?;
+    Ok((df, schema, table_partition_cols))
+}
+
 //Creates datafusion runtime based on hints from `path`
 async fn create_runtime(_path: &str) -> Result<Arc<RuntimeEnv>, DataFusionError> {
     //TODO: Current cache manager forces full table listing on cache miss, so disable file list
@@ -237,7 +1439,7 @@ async fn create_runtime(_path: &str) -> Result<Arc<RuntimeEnv>, DataFusionError>
 
         match object_store_aws::init(Some(&object_store_aws::http::Builder::new().with_ring())).await {
             Ok(credentials) => {
-                println!(">Registering AWS storage with url={url}");
+                eprintln!(">Registering AWS storage with url={url}");
 
                 let mut s3 = object_store_aws::AmazonS3Builder::from_env().with_region(credentials.region_str()).with_bucket_name(bucket_name);
                 match credentials.http_client() {
@@ -245,7 +1447,7 @@ async fn create_runtime(_path: &str) -> Result<Arc<RuntimeEnv>, DataFusionError>
                         s3 = s3.with_http_connector(http_client);
                     },
                     Ok(None) => {
-                        println!("> AWS SDK HTTP client is not availalble");
+                        eprintln!("> AWS SDK HTTP client is not availalble");
                     },
                     Err(error) => {
                         eprintln!("# AWS SDK HTTP client is not availalble: {error}");
@@ -270,10 +1472,119 @@ async fn create_runtime(_path: &str) -> Result<Arc<RuntimeEnv>, DataFusionError>
         url.set_path("");
         url.set_query(None);
 
-        println!(">Registering GCP storage with url={url}");
+        eprintln!(">Registering GCP storage with url={url}");
         let gcp = object_store::gcp::GoogleCloudStorageBuilder::from_env().with_bucket_name(bucket_name).build().map_err(|error| DataFusionError::External(Box::new(error)))?;
         env.object_store_registry.register_store(&url, Arc::new(gcp));
     }
 
     env.build_arc()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::common::arrow::array::{ArrayRef, Int64Array, RecordBatch, StringArray};
+
+    fn frame(ctx: &SessionContext, ids: Vec<Option<i64>>, names: Vec<Option<&str>>) -> DataFrame {
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from(ids)) as ArrayRef),
+            ("name", Arc::new(StringArray::from(names)) as ArrayRef),
+        ]).expect("batch");
+        ctx.read_batch(batch).expect("frame")
+    }
+
+    //Formats every row of `df` as text, with empty string for null
+    async fn rows(df: DataFrame) -> Vec<Vec<String>> {
+        let mut rows = Vec::new();
+        for batch in df.collect().await.expect("to collect") {
+            for idx in 0..batch.num_rows() {
+                rows.push(batch.columns().iter().map(|column| format::datafusion::cell(column.as_ref(), idx).map_or(String::new(), |value| value.to_string())).collect());
+            }
+        }
+        rows
+    }
+
+    fn block_on<F: core::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread().build().expect("runtime").block_on(future)
+    }
+
+    #[test]
+    fn should_diff_by_key() {
+        block_on(async {
+            let ctx = SessionContext::new();
+            let left = frame(&ctx, vec![Some(1), Some(2), Some(3), None], vec![Some("a"), Some("b"), None, Some("x")]);
+            let left = left.with_column("left_only", lit(1i32)).expect("left");
+            let right = frame(&ctx, vec![Some(2), Some(3), Some(4), None], vec![Some("c"), None, Some("d"), Some("x")]);
+            let diff = diff(left, right, ".", &["id".to_owned()]).await.expect("to compare");
+            assert_eq!(diff.columns, ["name"]);
+            assert_eq!(diff.skipped, ["left_only"]);
+
+            //Null keys never match
+            let only_left = diff.only_left.clone().sort(vec![col("id").sort(true, true)]).expect("sort");
+            assert_eq!(rows(only_left).await, [["", "x", "1"], ["1", "a", "1"]]);
+            let only_right = diff.only_right.clone().sort(vec![col("id").sort(true, true)]).expect("sort");
+            assert_eq!(rows(only_right).await, [["", "x"], ["4", "d"]]);
+            //Nulls are equal within values
+            let changes = diff.changes().await.expect("changes");
+            assert_eq!(rows(changes).await, [["2", "name", "b", "c"]]);
+
+            let summary = diff.summary().await.expect("summary");
+            let summary = summary.iter().map(|count| (count.kind, count.column.as_deref(), count.rows)).collect::<Vec<_>>();
+            assert_eq!(summary, [
+                (format::DiffKind::OnlyLeft, None, 2),
+                (format::DiffKind::OnlyRight, None, 2),
+                (format::DiffKind::Changed, None, 1),
+                (format::DiffKind::Changed, Some("name"), 1),
+            ]);
+        });
+    }
+
+    #[test]
+    fn should_count_footers_as_scan() {
+        block_on(async {
+            let root = std::env::temp_dir().join(format!("mishka-datafusion-count-{}", std::process::id()));
+            for (year, city, rows) in [("2024", "Oslo", 3), ("9", "Oslo", 1), ("10", "Paris", 4)] {
+                let dir = root.join(format!("year={year}")).join(format!("city={city}"));
+                std::fs::create_dir_all(&dir).expect("partition dir");
+                let batch = RecordBatch::try_from_iter([("id", Arc::new(Int64Array::from_iter_values(0..rows)) as ArrayRef)]).expect("batch");
+                let file = std::fs::File::create(dir.join("part-0.parquet")).expect("parquet file");
+                let mut writer = datafusion::parquet::arrow::ArrowWriter::try_new(file, batch.schema(), None).expect("writer");
+                writer.write(&batch).expect("to write parquet");
+                writer.close().expect("to close parquet");
+            }
+            let path = format!("{}/", root.to_str().expect("utf-8 path"));
+            let group_by = ["year".to_owned(), "city".to_owned()];
+
+            let ctx = SessionContext::new();
+            let partition_cols = group_by.iter().map(|column| (column.clone(), DataType::Utf8View)).collect();
+            let df = ctx.read_parquet(path.as_str(), datafusion::prelude::ParquetReadOptions::default().table_partition_cols(partition_cols)).await.expect("scan");
+            let types = literal_types(&df, &group_by).expect("literal types");
+            let footers = read_parquet_footers(&path).await.expect("footers");
+            let footer_counts = parquet::count_rows(footers.iter().map(|(path, footer)| (path.as_str(), footer)), &group_by, &types).expect("footer counts");
+            let scan_counts = count(df, &group_by).await.expect("scan counts");
+            std::fs::remove_dir_all(&root).expect("to clean up");
+
+            let groups = |counts: &[(Vec<format::Cell>, u64)]| counts.iter().map(|(group, rows)| (group.iter().map(|cell| cell.as_ref().map_or(String::new(), |value| value.to_string())).collect::<Vec<_>>(), *rows)).collect::<Vec<_>>();
+            assert_eq!(groups(&footer_counts), groups(&scan_counts));
+            assert_eq!(groups(&footer_counts), [
+                (vec!["10".to_owned(), "Paris".to_owned()], 4),
+                (vec!["2024".to_owned(), "Oslo".to_owned()], 3),
+                (vec!["9".to_owned(), "Oslo".to_owned()], 1),
+            ]);
+        });
+    }
+
+    #[test]
+    fn should_reject_duplicate_keys() {
+        block_on(async {
+            let ctx = SessionContext::new();
+            let left = frame(&ctx, vec![Some(1), Some(1), Some(2), Some(2), None, None], vec![Some("a"), Some("b"), Some("c"), Some("d"), Some("e"), Some("f")]);
+            let right = frame(&ctx, vec![Some(1), None, None], vec![Some("a"), Some("b"), Some("c")]);
+            let error = diff(left.clone(), right.clone(), ".", &["id".to_owned()]).await.err().expect("duplicate keys");
+            assert!(error.to_string().contains("Left data has 2 duplicate keys"), "{error}");
+            //Rows with null keys are never matched, so they are not duplicates
+            let error = diff(right, left, ".", &["id".to_owned()]).await.err().expect("duplicate keys");
+            assert!(error.to_string().contains("Right data has 2 duplicate keys"), "{error}");
+        });
+    }
+}
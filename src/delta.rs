@@ -0,0 +1,548 @@
+//! Delta Lake transaction log
+//!
+//! Log replay is shared between backends, which only list and read files of the log.
+//! Checkpoints are parquet files, so they are read by backends and passed to [Snapshot::add],
+//! [Snapshot::set_protocol] and [Snapshot::set_metadata].
+
+use core::fmt;
+use std::collections::BTreeMap;
+
+use crate::DeltaVersion;
+use crate::literal::{LiteralType, TimeUnit};
+
+///Name of directory containing transaction log
+pub const LOG_DIR: &str = "_delta_log";
+//Reader features that do not change how data files are read
+const SUPPORTED_READER_FEATURES: [&str; 2] = ["timestampNtz", "vacuumProtocolCheck"];
+
+#[derive(Debug)]
+///Delta log error
+pub struct DeltaError(String);
+
+impl fmt::Display for DeltaError {
+    #[inline(always)]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for DeltaError {}
+
+///File within transaction log directory
+pub struct LogFile {
+    ///File name
+    pub name: String,
+    ///Modification time in milliseconds since UNIX epoch
+    pub last_modified: i64,
+}
+
+enum LogFileKind {
+    Commit(u64),
+    Checkpoint {
+        version: u64,
+        part: u64,
+        parts: u64,
+    },
+}
+
+impl LogFileKind {
+    fn parse(name: &str) -> Option<Self> {
+        let (version, rest) = name.split_once('.')?;
+        if version.len() != 20 {
+            return None;
+        }
+        let version = version.parse().ok()?;
+        if rest == "json" {
+            return Some(Self::Commit(version));
+        }
+
+        let checkpoint = rest.strip_prefix("checkpoint.")?.strip_suffix("parquet")?;
+        if checkpoint.is_empty() {
+            return Some(Self::Checkpoint { version, part: 1, parts: 1 });
+        }
+        //Multi-part checkpoint: `<version>.checkpoint.<part>.<parts>.parquet`
+        //V2 checkpoints (`<version>.checkpoint.<uuid>.parquet`) are not recognized
+        let (part, parts) = checkpoint.strip_suffix('.')?.split_once('.')?;
+        Some(Self::Checkpoint {
+            version,
+            part: part.parse().ok()?,
+            parts: parts.parse().ok()?,
+        })
+    }
+}
+
+///Files of transaction log required to reconstruct version of the table
+pub struct LogSegment {
+    ///Version of the table
+    pub version: u64,
+    ///Parts of the checkpoint to start from. Empty if there is no checkpoint
+    pub checkpoint: Vec<String>,
+    ///Commits after checkpoint in order of versions
+    pub commits: Vec<String>,
+}
+
+impl LogSegment {
+    ///Selects files of transaction log required to read `version`
+    pub fn new(files: &[LogFile], version: DeltaVersion) -> Result<Self, DeltaError> {
+        let mut commits = BTreeMap::new();
+        let mut checkpoints = BTreeMap::<u64, Vec<(u64, u64, &str)>>::new();
+        for file in files {
+            match LogFileKind::parse(&file.name) {
+                Some(LogFileKind::Commit(version)) => {
+                    commits.insert(version, file);
+                },
+                Some(LogFileKind::Checkpoint { version, part, parts }) => checkpoints.entry(version).or_default().push((part, parts, file.name.as_str())),
+                None => (),
+            }
+        }
+
+        let latest = match (commits.last_key_value(), checkpoints.last_key_value()) {
+            (Some((commit, _)), Some((checkpoint, _))) => *commit.max(checkpoint),
+            (Some((commit, _)), None) => *commit,
+            (None, Some((checkpoint, _))) => *checkpoint,
+            (None, None) => return Err(DeltaError("Transaction log is empty".to_owned())),
+        };
+        let version = match version {
+            DeltaVersion::Latest => latest,
+            DeltaVersion::Version(version) if version > latest => return Err(DeltaError(format!("Version {version} does not exist, latest version is {latest}"))),
+            DeltaVersion::Version(version) => version,
+            DeltaVersion::Timestamp(timestamp) => match commits.iter().rev().find(|(_, file)| file.last_modified <= timestamp) {
+                Some((version, _)) => *version,
+                None => return Err(DeltaError(format!("No version committed at or before timestamp {timestamp}ms"))),
+            },
+        };
+
+        //Use latest checkpoint with all parts present
+        //Checkpoint may be written both as single file and in parts, so check every variant
+        let checkpoint = checkpoints.range(..=version).rev().find_map(|(checkpoint_version, files)| {
+            files.iter().map(|(_, parts, _)| *parts).find_map(|parts| {
+                let mut names = files.iter().filter(|(_, count, _)| *count == parts).collect::<Vec<_>>();
+                names.sort_unstable_by_key(|(part, _, _)| *part);
+                names.dedup_by_key(|(part, _, _)| *part);
+                let is_complete = names.len() as u64 == parts && names.iter().zip(1..).all(|((part, _, _), idx)| *part == idx);
+                is_complete.then(|| (*checkpoint_version, names.iter().map(|(_, _, name)| (*name).to_owned()).collect::<Vec<_>>()))
+            })
+        });
+        let (start, checkpoint) = match checkpoint {
+            Some((checkpoint_version, checkpoint)) => (checkpoint_version + 1, checkpoint),
+            None => (0, Vec::new()),
+        };
+
+        let mut segment_commits = Vec::new();
+        for commit in start..=version {
+            match commits.get(&commit) {
+                Some(file) => segment_commits.push(file.name.clone()),
+                None => return Err(DeltaError(format!("Transaction log is missing commit {commit} required to read version {version}"))),
+            }
+        }
+
+        Ok(Self {
+            version,
+            checkpoint,
+            commits: segment_commits,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+///Data file of the table
+pub struct DataFile {
+    ///Path relative to table root or absolute URI
+    pub path: String,
+    ///Size in bytes
+    pub size: u64,
+    ///Partition values by column. `None` is null
+    pub partition_values: Vec<(String, Option<String>)>,
+}
+
+impl DataFile {
+    ///Creates new file from its URI encoded `path` as stored in transaction log
+    pub fn new(path: &str, size: u64, partition_values: Vec<(String, Option<String>)>) -> Result<Self, DeltaError> {
+        Ok(Self {
+            path: percent_decode(path).ok_or_else(|| DeltaError(format!("'{path}': Invalid URI encoded path")))?,
+            size,
+            partition_values,
+        })
+    }
+
+    #[inline]
+    ///Returns whether path is absolute rather than relative to table root
+    pub fn is_absolute(&self) -> bool {
+        self.path.contains("://") || self.path.starts_with('/')
+    }
+
+    ///Returns value of partition `column`
+    pub fn partition_value(&self, column: &str) -> Option<&str> {
+        self.partition_values.iter().find(|(key, _)| key == column).and_then(|(_, value)| value.as_deref())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+///`protocol` action, specifying requirements to readers of the table
+pub struct Protocol {
+    ///Minimum version of protocol reader must implement
+    pub min_reader_version: i64,
+    ///Features reader must support. Only used with reader version 3
+    pub reader_features: Vec<String>,
+}
+
+impl Protocol {
+    //Checks that table can be read by ignoring every feature besides listing of data files
+    fn check(&self) -> Result<(), DeltaError> {
+        match self.min_reader_version {
+            ..=1 => Ok(()),
+            2 => Err(DeltaError("Reader version 2 requires column mapping, which is not supported".to_owned())),
+            3 => match self.reader_features.iter().find(|feature| !SUPPORTED_READER_FEATURES.contains(&feature.as_str())) {
+                Some(feature) => Err(DeltaError(format!("Reader feature '{feature}' is not supported"))),
+                None => Ok(()),
+            },
+            version => Err(DeltaError(format!("Reader version {version} is not supported"))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+///`metaData` action, describing the table
+pub struct Metadata {
+    ///Schema of the table serialized as JSON
+    pub schema_string: String,
+    ///Partition columns
+    pub partition_columns: Vec<String>,
+    ///Table properties
+    pub configuration: Vec<(String, String)>,
+}
+
+impl Metadata {
+    fn from_json(metadata: &serde_json::Value) -> Result<Self, DeltaError> {
+        let schema_string = metadata.get("schemaString").and_then(serde_json::Value::as_str).ok_or_else(|| DeltaError("`metaData` action has no schemaString".to_owned()))?;
+        let partition_columns = match metadata.get("partitionColumns").and_then(serde_json::Value::as_array) {
+            Some(columns) => columns.iter().flat_map(serde_json::Value::as_str).map(str::to_owned).collect(),
+            None => Vec::new(),
+        };
+        let configuration = match metadata.get("configuration").and_then(serde_json::Value::as_object) {
+            Some(configuration) => configuration.iter().flat_map(|(key, value)| value.as_str().map(|value| (key.clone(), value.to_owned()))).collect(),
+            None => Vec::new(),
+        };
+        Ok(Self {
+            schema_string: schema_string.to_owned(),
+            partition_columns,
+            configuration,
+        })
+    }
+
+    //Checks that table properties do not change how data files are read
+    fn check(&self) -> Result<(), DeltaError> {
+        match self.configuration.iter().find(|(key, _)| key == "delta.columnMapping.mode") {
+            Some((_, mode)) if mode != "none" => Err(DeltaError(format!("Column mapping mode '{mode}' is not supported"))),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+///Column of the table schema
+pub struct Column {
+    ///Column name
+    pub name: String,
+    ///Type of the column. `None` if type has no literal equivalent (e.g. binary or nested), in which case type of data file is used
+    pub dtype: Option<LiteralType>,
+}
+
+//Converts primitive type of Delta schema into literal type
+fn column_type(dtype: &str) -> Option<LiteralType> {
+    match dtype {
+        "boolean" => Some(LiteralType::Boolean),
+        "byte" => Some(LiteralType::Int(8)),
+        "short" => Some(LiteralType::Int(16)),
+        "integer" => Some(LiteralType::Int(32)),
+        "long" => Some(LiteralType::Int(64)),
+        "float" => Some(LiteralType::Float(32)),
+        "double" => Some(LiteralType::Float(64)),
+        "string" => Some(LiteralType::String),
+        "date" => Some(LiteralType::Date),
+        "timestamp" => Some(LiteralType::Timestamp {
+            unit: TimeUnit::Microsecond,
+            timezone: Some("UTC".to_owned()),
+        }),
+        "timestamp_ntz" => Some(LiteralType::Timestamp {
+            unit: TimeUnit::Microsecond,
+            timezone: None,
+        }),
+        dtype => {
+            let (precision, scale) = dtype.strip_prefix("decimal(")?.strip_suffix(')')?.split_once(',')?;
+            Some(LiteralType::Decimal {
+                precision: precision.trim().parse().ok()?,
+                scale: scale.trim().parse().ok()?,
+            })
+        },
+    }
+}
+
+#[derive(Default)]
+///Snapshot of the table, built by replaying transaction log
+pub struct Snapshot {
+    files: BTreeMap<String, DataFile>,
+    protocol: Option<Protocol>,
+    metadata: Option<Metadata>,
+}
+
+impl Snapshot {
+    #[inline]
+    ///Adds data file
+    pub fn add(&mut self, file: DataFile) {
+        self.files.insert(file.path.clone(), file);
+    }
+
+    #[inline]
+    ///Sets protocol of the table, replacing previous one
+    pub fn set_protocol(&mut self, protocol: Protocol) {
+        self.protocol = Some(protocol);
+    }
+
+    #[inline]
+    ///Sets metadata of the table, replacing previous one
+    pub fn set_metadata(&mut self, metadata: Metadata) {
+        self.metadata = Some(metadata);
+    }
+
+    ///Applies actions of commit (newline delimited JSON)
+    pub fn apply_commit(&mut self, text: &str) -> Result<(), DeltaError> {
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let action = serde_json::from_str::<serde_json::Value>(line).map_err(|error| DeltaError(format!("Invalid action: {error}")))?;
+            if let Some(add) = action.get("add") {
+                let path = add.get("path").and_then(serde_json::Value::as_str).ok_or_else(|| DeltaError("`add` action has no path".to_owned()))?;
+                let size = add.get("size").and_then(serde_json::Value::as_u64).ok_or_else(|| DeltaError(format!("'{path}': `add` action has no size")))?;
+                let partition_values = match add.get("partitionValues").and_then(serde_json::Value::as_object) {
+                    Some(values) => values.iter().map(|(key, value)| (key.clone(), value.as_str().map(str::to_owned))).collect(),
+                    None => Vec::new(),
+                };
+                self.add(DataFile::new(path, size, partition_values)?);
+            } else if let Some(remove) = action.get("remove") {
+                let path = remove.get("path").and_then(serde_json::Value::as_str).ok_or_else(|| DeltaError("`remove` action has no path".to_owned()))?;
+                let path = percent_decode(path).ok_or_else(|| DeltaError(format!("'{path}': Invalid URI encoded path")))?;
+                self.files.remove(&path);
+            } else if let Some(protocol) = action.get("protocol") {
+                let min_reader_version = protocol.get("minReaderVersion").and_then(serde_json::Value::as_i64).ok_or_else(|| DeltaError("`protocol` action has no minReaderVersion".to_owned()))?;
+                let reader_features = match protocol.get("readerFeatures").and_then(serde_json::Value::as_array) {
+                    Some(features) => features.iter().flat_map(serde_json::Value::as_str).map(str::to_owned).collect(),
+                    None => Vec::new(),
+                };
+                self.set_protocol(Protocol {
+                    min_reader_version,
+                    reader_features,
+                });
+            } else if let Some(metadata) = action.get("metaData") {
+                self.set_metadata(Metadata::from_json(metadata)?);
+            }
+        }
+        Ok(())
+    }
+
+    ///Checks that table requires no reader features besides listing of data files (e.g. deletion vectors or column mapping)
+    ///
+    ///Must be called after whole log is replayed
+    pub fn check_support(&self) -> Result<(), DeltaError> {
+        if let Some(protocol) = self.protocol.as_ref() {
+            protocol.check()?;
+        }
+        if let Some(metadata) = self.metadata.as_ref() {
+            metadata.check()?;
+        }
+        Ok(())
+    }
+
+    ///Returns columns of the table schema, including partition columns, in order of the schema
+    ///
+    ///Empty if log has no metadata
+    pub fn schema(&self) -> Result<Vec<Column>, DeltaError> {
+        let metadata = match self.metadata.as_ref() {
+            Some(metadata) => metadata,
+            None => return Ok(Vec::new()),
+        };
+        let schema = serde_json::from_str::<serde_json::Value>(&metadata.schema_string).map_err(|error| DeltaError(format!("Invalid schema: {error}")))?;
+        let fields = schema.get("fields").and_then(serde_json::Value::as_array).ok_or_else(|| DeltaError("Schema has no fields".to_owned()))?;
+        let mut columns = Vec::with_capacity(fields.len());
+        for field in fields {
+            let name = field.get("name").and_then(serde_json::Value::as_str).ok_or_else(|| DeltaError("Schema field has no name".to_owned()))?;
+            columns.push(Column {
+                name: name.to_owned(),
+                //Nested types are objects
+                dtype: field.get("type").and_then(serde_json::Value::as_str).and_then(column_type),
+            });
+        }
+        Ok(columns)
+    }
+
+    ///Returns partition columns of the table
+    ///
+    ///If log has no metadata, columns are collected from partition values of files
+    pub fn partition_columns(&self) -> Vec<String> {
+        match self.metadata.as_ref() {
+            Some(metadata) => metadata.partition_columns.clone(),
+            None => {
+                let mut columns = Vec::new();
+                for (key, _) in self.files.values().flat_map(|file| file.partition_values.iter()) {
+                    if !columns.contains(key) {
+                        columns.push(key.clone());
+                    }
+                }
+                columns.sort_unstable();
+                columns
+            }
+        }
+    }
+
+    #[inline]
+    ///Returns active data files in order of their paths
+    pub fn into_files(self) -> impl ExactSizeIterator<Item = DataFile> {
+        self.files.into_values()
+    }
+}
+
+///Decodes percent-encoded `text`, returning `None` if it is malformed or not UTF-8
+pub fn percent_decode(text: &str) -> Option<String> {
+    let mut bytes = text.bytes();
+    let mut result = Vec::with_capacity(text.len());
+    while let Some(byte) = bytes.next() {
+        if byte == b'%' {
+            let high = (bytes.next()? as char).to_digit(16)?;
+            let low = (bytes.next()? as char).to_digit(16)?;
+            result.push((high * 16 + low) as u8);
+        } else {
+            result.push(byte);
+        }
+    }
+    String::from_utf8(result).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(version: u64, last_modified: i64) -> LogFile {
+        LogFile {
+            name: format!("{version:020}.json"),
+            last_modified,
+        }
+    }
+
+    fn checkpoint(version: u64, part: Option<(u64, u64)>) -> LogFile {
+        let name = match part {
+            Some((part, parts)) => format!("{version:020}.checkpoint.{part:010}.{parts:010}.parquet"),
+            None => format!("{version:020}.checkpoint.parquet"),
+        };
+        LogFile {
+            name,
+            last_modified: 0,
+        }
+    }
+
+    #[test]
+    fn should_select_latest_complete_checkpoint() {
+        let mut log = (0..=5).map(|version| commit(version, 0)).collect::<Vec<_>>();
+        log.push(checkpoint(2, None));
+        log.push(checkpoint(4, Some((1, 2))));
+        log.push(LogFile {
+            name: "_last_checkpoint".to_owned(),
+            last_modified: 0,
+        });
+
+        //Checkpoint 4 is incomplete
+        let segment = LogSegment::new(&log, DeltaVersion::Latest).expect("to select segment");
+        assert_eq!(segment.version, 5);
+        assert_eq!(segment.checkpoint, [checkpoint(2, None).name]);
+        assert_eq!(segment.commits, [commit(3, 0).name, commit(4, 0).name, commit(5, 0).name]);
+
+        log.push(checkpoint(4, Some((2, 2))));
+        let segment = LogSegment::new(&log, DeltaVersion::Latest).expect("to select segment");
+        assert_eq!(segment.checkpoint, [checkpoint(4, Some((1, 2))).name, checkpoint(4, Some((2, 2))).name]);
+        assert_eq!(segment.commits, [commit(5, 0).name]);
+
+        //Checkpoint after requested version is ignored
+        let segment = LogSegment::new(&log, DeltaVersion::Version(3)).expect("to select segment");
+        assert_eq!(segment.version, 3);
+        assert_eq!(segment.checkpoint, [checkpoint(2, None).name]);
+        assert_eq!(segment.commits, [commit(3, 0).name]);
+
+        let segment = LogSegment::new(&log, DeltaVersion::Version(1)).expect("to select segment");
+        assert!(segment.checkpoint.is_empty());
+        assert_eq!(segment.commits, [commit(0, 0).name, commit(1, 0).name]);
+    }
+
+    #[test]
+    fn should_require_every_commit_after_checkpoint() {
+        let mut log = vec![commit(0, 0), commit(1, 0), commit(3, 0)];
+        assert!(LogSegment::new(&log, DeltaVersion::Latest).is_err());
+        assert!(LogSegment::new(&log, DeltaVersion::Version(1)).is_ok());
+        assert!(LogSegment::new(&log, DeltaVersion::Version(4)).is_err());
+
+        log.push(checkpoint(2, None));
+        let segment = LogSegment::new(&log, DeltaVersion::Latest).expect("to select segment");
+        assert_eq!(segment.commits, [commit(3, 0).name]);
+
+        assert!(LogSegment::new(&[], DeltaVersion::Latest).is_err());
+    }
+
+    #[test]
+    fn should_resolve_version_by_timestamp() {
+        let log = [commit(0, 100), commit(1, 200), commit(2, 300)];
+        assert_eq!(LogSegment::new(&log, DeltaVersion::Timestamp(250)).expect("to select segment").version, 1);
+        assert_eq!(LogSegment::new(&log, DeltaVersion::Timestamp(300)).expect("to select segment").version, 2);
+        assert!(LogSegment::new(&log, DeltaVersion::Timestamp(50)).is_err());
+    }
+
+    #[test]
+    fn should_apply_commit() {
+        let mut snapshot = Snapshot::default();
+        snapshot.apply_commit(concat!(
+            r#"{"add":{"path":"day=2024-01-01/part%20one.parquet","partitionValues":{"day":"2024-01-01","tag":null},"size":10}}"#, "\n",
+            "\n",
+            r#"{"add":{"path":"day=2024-01-02/part.parquet","partitionValues":{"day":"2024-01-02","tag":"a"},"size":20}}"#, "\n",
+        )).expect("to apply commit");
+        snapshot.apply_commit(r#"{"remove":{"path":"day=2024-01-02/part.parquet"}}"#).expect("to apply commit");
+        assert_eq!(snapshot.partition_columns(), ["day", "tag"]);
+
+        let files = snapshot.into_files().collect::<Vec<_>>();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, "day=2024-01-01/part one.parquet");
+        assert_eq!(files[0].size, 10);
+        assert_eq!(files[0].partition_value("day"), Some("2024-01-01"));
+        assert_eq!(files[0].partition_value("tag"), None);
+        assert!(!files[0].is_absolute());
+
+        let mut snapshot = Snapshot::default();
+        snapshot.apply_commit(r#"{"add":{"path":"a%20b.parquet","size":1}}"#).expect("to apply commit");
+        //Encoded path is removed by its decoded path
+        snapshot.apply_commit(r#"{"remove":{"path":"a%20b.parquet"}}"#).expect("to apply commit");
+        assert_eq!(snapshot.into_files().len(), 0);
+
+        let mut snapshot = Snapshot::default();
+        assert!(snapshot.apply_commit(r#"{"add":{"path":"a.parquet"}}"#).is_err());
+        assert!(snapshot.apply_commit(r#"{"add":{"path":"a%2.parquet","size":1}}"#).is_err());
+        assert!(snapshot.apply_commit("{").is_err());
+    }
+
+    #[test]
+    fn should_check_protocol() {
+        let protocol = |min_reader_version, reader_features: &[&str]| Protocol {
+            min_reader_version,
+            reader_features: reader_features.iter().map(|feature| (*feature).to_owned()).collect(),
+        };
+        assert!(protocol(1, &[]).check().is_ok());
+        assert!(protocol(2, &[]).check().is_err());
+        assert!(protocol(3, &["timestampNtz", "vacuumProtocolCheck"]).check().is_ok());
+        assert!(protocol(3, &["timestampNtz", "deletionVectors"]).check().is_err());
+        assert!(protocol(4, &[]).check().is_err());
+    }
+
+    #[test]
+    fn should_percent_decode() {
+        assert_eq!(percent_decode("a%20b").as_deref(), Some("a b"));
+        assert_eq!(percent_decode("day=2024-01-01%3A00").as_deref(), Some("day=2024-01-01:00"));
+        assert_eq!(percent_decode("%E2%9C%93").as_deref(), Some("\u{2713}"));
+        assert_eq!(percent_decode("plain").as_deref(), Some("plain"));
+        assert!(percent_decode("%2").is_none());
+        assert!(percent_decode("%zz").is_none());
+        assert!(percent_decode("%FF").is_none());
+    }
+}
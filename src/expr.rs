@@ -0,0 +1,808 @@
+//! Filter and computed column expressions
+//!
+//! Expressions are parsed using SQL syntax and then lowered into backend specific expression.
+
+use core::fmt;
+
+use sqlparser::ast;
+use sqlparser::dialect::GenericDialect;
+use sqlparser::parser::Parser;
+use sqlparser::tokenizer::Token;
+
+#[derive(Debug)]
+///Expression parsing error
+pub struct ParseError(String);
+
+impl fmt::Display for ParseError {
+    #[inline(always)]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+//Parses whole `text` as single SQL expression
+fn parse_sql(text: &str) -> Result<ast::Expr, ParseError> {
+    let dialect = GenericDialect {};
+    let mut parser = Parser::new(&dialect).try_with_sql(text).map_err(|error| ParseError(error.to_string()))?;
+    let expr = parser.parse_expr().map_err(|error| ParseError(error.to_string()))?;
+
+    match parser.peek_token().token {
+        Token::EOF => Ok(expr),
+        token => Err(ParseError(format!("Unexpected token '{token}'"))),
+    }
+}
+
+#[derive(Debug, Clone)]
+///Arithmetic operators
+pub enum ArithmeticOperator {
+    ///Addition `+`
+    Add,
+    ///Subtraction `-`
+    Subtract,
+    ///Multiplication `*`
+    Multiply,
+    ///Division `/`
+    Divide,
+    ///Remainder `%`
+    Modulo,
+}
+
+impl ArithmeticOperator {
+    fn from_sql(op: &ast::BinaryOperator) -> Option<Self> {
+        match op {
+            ast::BinaryOperator::Plus => Some(Self::Add),
+            ast::BinaryOperator::Minus => Some(Self::Subtract),
+            ast::BinaryOperator::Multiply => Some(Self::Multiply),
+            ast::BinaryOperator::Divide => Some(Self::Divide),
+            ast::BinaryOperator::Modulo => Some(Self::Modulo),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+///Unit to truncate timestamp to
+pub enum TruncateUnit {
+    ///Year
+    Year,
+    ///Month
+    Month,
+    ///Week
+    Week,
+    ///Day
+    Day,
+    ///Hour
+    Hour,
+    ///Minute
+    Minute,
+    ///Second
+    Second,
+}
+
+impl TruncateUnit {
+    fn parse(text: &str) -> Option<Self> {
+        match text.to_ascii_lowercase().as_str() {
+            "year" => Some(Self::Year),
+            "month" => Some(Self::Month),
+            "week" => Some(Self::Week),
+            "day" => Some(Self::Day),
+            "hour" => Some(Self::Hour),
+            "minute" => Some(Self::Minute),
+            "second" => Some(Self::Second),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    ///Returns SQL name of the unit
+    pub const fn as_sql_name(&self) -> &'static str {
+        match self {
+            Self::Year => "year",
+            Self::Month => "month",
+            Self::Week => "week",
+            Self::Day => "day",
+            Self::Hour => "hour",
+            Self::Minute => "minute",
+            Self::Second => "second",
+        }
+    }
+
+    #[inline]
+    ///Returns polars duration string of the unit
+    pub const fn as_duration(&self) -> &'static str {
+        match self {
+            Self::Year => "1y",
+            Self::Month => "1mo",
+            Self::Week => "1w",
+            Self::Day => "1d",
+            Self::Hour => "1h",
+            Self::Minute => "1m",
+            Self::Second => "1s",
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+///Supported functions
+pub enum Function {
+    ///`upper(text)`
+    Upper,
+    ///`lower(text)`
+    Lower,
+    ///`length(text)` in characters
+    Length,
+    ///`trim(text)` removing leading and trailing whitespaces
+    Trim,
+    ///`abs(number)`
+    Abs,
+    ///`coalesce(value, ...)` returning first non-null value
+    Coalesce,
+    ///`date_trunc('unit', timestamp)`
+    DateTrunc(TruncateUnit),
+    ///`year(timestamp)`
+    Year,
+    ///`month(timestamp)`
+    Month,
+    ///`day(timestamp)`
+    Day,
+    ///`hour(timestamp)`
+    Hour,
+}
+
+impl Function {
+    fn from_sql(function: ast::Function) -> Result<(Self, Vec<Operand>), ParseError> {
+        let name = function.name.to_string();
+        let mut args = Vec::new();
+        match function.args {
+            ast::FunctionArguments::None => (),
+            ast::FunctionArguments::List(list) => for arg in list.args {
+                match arg {
+                    ast::FunctionArg::Unnamed(ast::FunctionArgExpr::Expr(expr)) => args.push(Operand::from_sql(expr)?),
+                    arg => return Err(ParseError(format!("{name}: Unsupported argument '{arg}'"))),
+                }
+            },
+            ast::FunctionArguments::Subquery(_) => return Err(ParseError(format!("{name}: Subquery is not supported"))),
+        }
+
+        let (function, arity) = match name.to_ascii_lowercase().as_str() {
+            "upper" => (Self::Upper, 1),
+            "lower" => (Self::Lower, 1),
+            "length" | "char_length" | "character_length" => (Self::Length, 1),
+            "trim" | "btrim" => (Self::Trim, 1),
+            "abs" => (Self::Abs, 1),
+            "coalesce" => (Self::Coalesce, args.len().max(1)),
+            "date_trunc" => {
+                let unit = match args.first() {
+                    Some(Operand::Literal(unit)) => TruncateUnit::parse(unit),
+                    _ => None,
+                };
+                match unit {
+                    Some(unit) => {
+                        args.remove(0);
+                        (Self::DateTrunc(unit), 1)
+                    },
+                    None => return Err(ParseError(format!("{name}: First argument must be one of 'year', 'month', 'week', 'day', 'hour', 'minute', 'second'"))),
+                }
+            },
+            "year" => (Self::Year, 1),
+            "month" => (Self::Month, 1),
+            "day" => (Self::Day, 1),
+            "hour" => (Self::Hour, 1),
+            _ => return Err(ParseError(format!("Unsupported function '{name}'"))),
+        };
+
+        if args.len() != arity {
+            return Err(ParseError(format!("{name}: Expected {arity} argument(s), got {}", args.len())));
+        }
+
+        Ok((function, args))
+    }
+}
+
+#[derive(Debug, Clone)]
+///Operand types
+pub enum Operand {
+    ///Literal is always should be treated as constant
+    Literal(String),
+    ///Numeric literal
+    ///
+    ///Treated as number unless compared against column of different type
+    Number(String),
+    ///Identifier assumes existing column name
+    Identifier(String),
+    ///Arithmetic operation `left operator right`
+    Arithmetic {
+        ///Left side
+        left: Box<Operand>,
+        ///Operator
+        operator: ArithmeticOperator,
+        ///Right side
+        right: Box<Operand>,
+    },
+    ///Function call
+    Function {
+        ///Function
+        function: Function,
+        ///Arguments
+        args: Vec<Operand>,
+    },
+}
+
+impl Operand {
+    #[inline]
+    ///Returns column name if operand is identifier
+    pub fn as_identifier(&self) -> Option<&str> {
+        match self {
+            Self::Identifier(ident) => Some(ident.as_str()),
+            _ => None,
+        }
+    }
+
+    fn from_sql(expr: ast::Expr) -> Result<Self, ParseError> {
+        match expr {
+            //Double quotes are kept as literal for compatibility with older syntax
+            //Use backticks to quote column name
+            ast::Expr::Identifier(ident) => match ident.quote_style {
+                Some('"') => Ok(Self::Literal(ident.value)),
+                _ => Ok(Self::Identifier(ident.value)),
+            },
+            ast::Expr::CompoundIdentifier(idents) => {
+                let name = idents.into_iter().map(|ident| ident.value).collect::<Vec<_>>();
+                Ok(Self::Identifier(name.join(".")))
+            },
+            ast::Expr::Value(value) => match value.value {
+                ast::Value::Number(number, _) => Ok(Self::Number(number)),
+                ast::Value::SingleQuotedString(text) | ast::Value::DoubleQuotedString(text) => Ok(Self::Literal(text)),
+                ast::Value::Boolean(value) => Ok(Self::Literal(value.to_string())),
+                value => Err(ParseError(format!("Unsupported literal '{value}'"))),
+            },
+            ast::Expr::UnaryOp { op: ast::UnaryOperator::Minus, expr } => match *expr {
+                ast::Expr::Value(ast::ValueWithSpan { value: ast::Value::Number(number, _), .. }) => Ok(Self::Number(format!("-{number}"))),
+                expr => Err(ParseError(format!("Unsupported operand '-{expr}'"))),
+            },
+            ast::Expr::BinaryOp { left, op, right } => match ArithmeticOperator::from_sql(&op) {
+                Some(operator) => Ok(Self::Arithmetic {
+                    left: Box::new(Self::from_sql(*left)?),
+                    operator,
+                    right: Box::new(Self::from_sql(*right)?),
+                }),
+                None => Err(ParseError(format!("Unsupported operator '{op}'"))),
+            },
+            ast::Expr::Function(function) => {
+                let (function, args) = Function::from_sql(function)?;
+                Ok(Self::Function { function, args })
+            },
+            ast::Expr::Trim { expr, trim_where: None, trim_what: None, .. } => Ok(Self::Function {
+                function: Function::Trim,
+                args: vec![Self::from_sql(*expr)?],
+            }),
+            ast::Expr::Nested(expr) => Self::from_sql(*expr),
+            expr => Err(ParseError(format!("Unsupported operand '{expr}'"))),
+        }
+    }
+
+    fn literal_from_sql(expr: ast::Expr) -> Result<String, ParseError> {
+        match Self::from_sql(expr)? {
+            Self::Literal(literal) | Self::Number(literal) => Ok(literal),
+            operand => Err(ParseError(format!("Expected literal, got '{operand:?}'"))),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+///Possible operators
+pub enum Operator {
+    ///<
+    Less,
+    ///<=
+    LessEq,
+    ///==
+    Eq,
+    /// !=
+    NotEq,
+    ///>=
+    GreaterEq,
+    ///>
+    Greater,
+}
+
+impl Operator {
+    fn from_sql(op: &ast::BinaryOperator) -> Option<Self> {
+        match op {
+            ast::BinaryOperator::Eq => Some(Self::Eq),
+            ast::BinaryOperator::NotEq => Some(Self::NotEq),
+            ast::BinaryOperator::Gt => Some(Self::Greater),
+            ast::BinaryOperator::GtEq => Some(Self::GreaterEq),
+            ast::BinaryOperator::Lt => Some(Self::Less),
+            ast::BinaryOperator::LtEq => Some(Self::LessEq),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+///SQL like boolean expression
+pub enum Expression {
+    ///Comparison `left operator right`
+    Compare {
+        ///Left side
+        left: Operand,
+        ///Operator
+        operator: Operator,
+        ///Right side
+        right: Operand,
+    },
+    ///Both expressions must be true
+    And(Box<Expression>, Box<Expression>),
+    ///Either expression must be true
+    Or(Box<Expression>, Box<Expression>),
+    ///Negation of expression
+    Not(Box<Expression>),
+    ///Set membership `operand [NOT] IN (list)`
+    In {
+        ///Value to look up
+        operand: Operand,
+        ///List of literals
+        list: Vec<String>,
+        ///Indicates `NOT IN`
+        negated: bool,
+    },
+    ///Inclusive range `operand [NOT] BETWEEN low AND high`
+    Between {
+        ///Value to check
+        operand: Operand,
+        ///Lower bound
+        low: Operand,
+        ///Upper bound
+        high: Operand,
+        ///Indicates `NOT BETWEEN`
+        negated: bool,
+    },
+    ///Null check `operand IS [NOT] NULL`
+    IsNull {
+        ///Value to check
+        operand: Operand,
+        ///Indicates `IS NOT NULL`
+        negated: bool,
+    },
+    ///SQL pattern match `operand [NOT] LIKE pattern` or `operand [NOT] ILIKE pattern`
+    Like {
+        ///Value to match
+        operand: Operand,
+        ///Pattern where `%` matches any sequence, `_` matches single character and backslash escapes next character
+        ///
+        ///Custom `ESCAPE` character is rewritten into backslash when parsing
+        pattern: String,
+        ///Indicates `ILIKE`
+        case_insensitive: bool,
+        ///Indicates `NOT LIKE`
+        negated: bool,
+    },
+    ///Regular expression match `operand [NOT] RLIKE pattern` or `operand ~ pattern`
+    Regex {
+        ///Value to match
+        operand: Operand,
+        ///Regular expression
+        pattern: String,
+        ///Indicates `NOT RLIKE`
+        negated: bool,
+    },
+}
+
+#[derive(Debug, Clone)]
+///Computed column `name=expression`
+pub struct ComputedColumn {
+    ///Name of the column
+    pub name: String,
+    ///Expression to compute value of the column
+    pub expr: Operand,
+}
+
+//Splits optional `name=` prefix from `text`, returning whole text as both name and expression when there is none
+//
+//Prefix is only recognized as name when it is bare identifier, so `=` within expression itself is left intact
+fn split_name(text: &str) -> (&str, &str) {
+    if let Some((name, expr)) = text.split_once('=') {
+        let name = name.trim();
+        let mut chars = name.chars();
+        let is_identifier = match chars.next() {
+            Some(ch) => (ch.is_alphabetic() || ch == '_') && chars.all(|ch| ch.is_alphanumeric() || ch == '_'),
+            //Missing name is reported by caller
+            None => true,
+        };
+        //`a == b` is comparison rather than name
+        if is_identifier && !expr.starts_with('=') {
+            return (name, expr);
+        }
+    }
+    (text.trim(), text)
+}
+
+impl core::str::FromStr for ComputedColumn {
+    type Err = ParseError;
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        //Name is optional, expression text is used as name by default
+        let (name, expr) = split_name(text);
+        if name.is_empty() {
+            return Err(ParseError(format!("'{text}': Column name is missing")));
+        }
+
+        Ok(Self {
+            name: name.to_owned(),
+            expr: Operand::from_sql(parse_sql(expr)?)?,
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+///Supported aggregation functions
+pub enum Aggregation {
+    ///`count(column)` number of non-null values or `count(*)` number of rows
+    Count,
+    ///`count_distinct(column)` or `count(DISTINCT column)` number of distinct non-null values
+    CountDistinct,
+    ///`sum(column)`
+    Sum,
+    ///`min(column)`
+    Min,
+    ///`max(column)`
+    Max,
+    ///`mean(column)` or `avg(column)`
+    Mean,
+    ///`first(column)` value within group
+    First,
+    ///`last(column)` value within group
+    Last,
+}
+
+#[derive(Debug, Clone)]
+///Aggregate column `name=function(column)`
+pub struct Aggregate {
+    ///Name of the column
+    pub name: String,
+    ///Aggregation function
+    pub function: Aggregation,
+    ///Aggregated expression. `None` only for `count(*)`
+    pub argument: Option<Operand>,
+}
+
+impl core::str::FromStr for Aggregate {
+    type Err = ParseError;
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        //Name is optional, expression text is used as name by default
+        let (name, expr) = split_name(text);
+        if name.is_empty() {
+            return Err(ParseError(format!("'{text}': Column name is missing")));
+        }
+
+        let function = match parse_sql(expr)? {
+            ast::Expr::Function(function) => function,
+            expr => return Err(ParseError(format!("'{expr}': Expected aggregation function"))),
+        };
+        let function_name = function.name.to_string();
+        let (is_distinct, mut args) = match function.args {
+            ast::FunctionArguments::List(list) => (matches!(list.duplicate_treatment, Some(ast::DuplicateTreatment::Distinct)), list.args),
+            _ => return Err(ParseError(format!("{function_name}: Expected single argument"))),
+        };
+        let argument = match (args.pop(), args.is_empty()) {
+            (Some(ast::FunctionArg::Unnamed(ast::FunctionArgExpr::Wildcard)), true) => None,
+            (Some(ast::FunctionArg::Unnamed(ast::FunctionArgExpr::Expr(expr))), true) => Some(Operand::from_sql(expr)?),
+            _ => return Err(ParseError(format!("{function_name}: Expected single argument"))),
+        };
+
+        let function = match (function_name.to_ascii_lowercase().as_str(), is_distinct) {
+            ("count", false) => Aggregation::Count,
+            ("count", true) | ("count_distinct", false) => Aggregation::CountDistinct,
+            ("sum", false) => Aggregation::Sum,
+            ("min", false) => Aggregation::Min,
+            ("max", false) => Aggregation::Max,
+            ("mean" | "avg", false) => Aggregation::Mean,
+            ("first", false) => Aggregation::First,
+            ("last", false) => Aggregation::Last,
+            _ => return Err(ParseError(format!("Unsupported aggregation '{function_name}'"))),
+        };
+        if argument.is_none() && !matches!(function, Aggregation::Count) {
+            return Err(ParseError(format!("{function_name}: Wildcard is only allowed for count")));
+        }
+
+        Ok(Self {
+            name: name.to_owned(),
+            function,
+            argument,
+        })
+    }
+}
+
+///Converts SQL `LIKE` pattern into anchored regular expression
+///
+///Backslash escapes following character
+pub fn like_to_regex(pattern: &str, case_insensitive: bool) -> String {
+    let mut result = String::with_capacity(pattern.len() + 8);
+    //Make sure `%` matches new lines too
+    if case_insensitive {
+        result.push_str("(?is)^");
+    } else {
+        result.push_str("(?s)^");
+    }
+
+    let mut chars = pattern.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '%' => result.push_str(".*"),
+            '_' => result.push('.'),
+            '\\' => push_regex_char(&mut result, chars.next().unwrap_or(ch)),
+            ch => push_regex_char(&mut result, ch),
+        }
+    }
+
+    result.push('$');
+    result
+}
+
+//Rewrites `pattern` with custom `ESCAPE` character into pattern escaped by backslash, as expected by backends
+fn like_pattern(pattern: String, escape: Option<impl core::fmt::Display>) -> Result<String, ParseError> {
+    let escape = match escape {
+        Some(escape) => escape.to_string(),
+        None => return Ok(pattern),
+    };
+    let escape = escape.strip_prefix('\'').and_then(|escape| escape.strip_suffix('\'')).unwrap_or(&escape);
+    let mut chars = escape.chars();
+    let escape = match (chars.next(), chars.next()) {
+        (Some(escape), None) => escape,
+        _ => return Err(ParseError(format!("ESCAPE must be single character, got '{escape}'"))),
+    };
+    if escape == '\\' {
+        return Ok(pattern);
+    }
+
+    let mut result = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            //Trailing escape is matched literally
+            ch if ch == escape => {
+                result.push('\\');
+                result.push(chars.next().unwrap_or(ch));
+            },
+            '\\' => result.push_str("\\\\"),
+            ch => result.push(ch),
+        }
+    }
+    Ok(result)
+}
+
+///Escapes `text` to be matched literally within regular expression
+pub fn escape_regex(text: &str) -> String {
+    let mut result = String::with_capacity(text.len() + 2);
+    for ch in text.chars() {
+        push_regex_char(&mut result, ch);
+    }
+    result
+}
+
+fn push_regex_char(result: &mut String, ch: char) {
+    if matches!(ch, '\\' | '.' | '+' | '*' | '?' | '(' | ')' | '|' | '[' | ']' | '{' | '}' | '^' | '$' | '#' | '&' | '-' | '~') {
+        result.push('\\');
+    }
+    result.push(ch);
+}
+
+impl Expression {
+    fn from_sql(expr: ast::Expr) -> Result<Self, ParseError> {
+        match expr {
+            ast::Expr::Nested(expr) => Self::from_sql(*expr),
+            ast::Expr::UnaryOp { op: ast::UnaryOperator::Not, expr } => Ok(Self::Not(Box::new(Self::from_sql(*expr)?))),
+            ast::Expr::BinaryOp { left, op: ast::BinaryOperator::And, right } => Ok(Self::And(Box::new(Self::from_sql(*left)?), Box::new(Self::from_sql(*right)?))),
+            ast::Expr::BinaryOp { left, op: ast::BinaryOperator::Or, right } => Ok(Self::Or(Box::new(Self::from_sql(*left)?), Box::new(Self::from_sql(*right)?))),
+            ast::Expr::InList { expr, list, negated } => Ok(Self::In {
+                operand: Operand::from_sql(*expr)?,
+                list: list.into_iter().map(Operand::literal_from_sql).collect::<Result<_, _>>()?,
+                negated,
+            }),
+            ast::Expr::Between { expr, negated, low, high } => Ok(Self::Between {
+                operand: Operand::from_sql(*expr)?,
+                low: Operand::from_sql(*low)?,
+                high: Operand::from_sql(*high)?,
+                negated,
+            }),
+            ast::Expr::IsNull(expr) => Ok(Self::IsNull {
+                operand: Operand::from_sql(*expr)?,
+                negated: false,
+            }),
+            ast::Expr::IsNotNull(expr) => Ok(Self::IsNull {
+                operand: Operand::from_sql(*expr)?,
+                negated: true,
+            }),
+            ast::Expr::Like { negated, any: false, expr, pattern, escape_char } => Ok(Self::Like {
+                operand: Operand::from_sql(*expr)?,
+                pattern: like_pattern(Operand::literal_from_sql(*pattern)?, escape_char)?,
+                case_insensitive: false,
+                negated,
+            }),
+            ast::Expr::ILike { negated, any: false, expr, pattern, escape_char } => Ok(Self::Like {
+                operand: Operand::from_sql(*expr)?,
+                pattern: like_pattern(Operand::literal_from_sql(*pattern)?, escape_char)?,
+                case_insensitive: true,
+                negated,
+            }),
+            ast::Expr::RLike { negated, expr, pattern, .. } => Ok(Self::Regex {
+                operand: Operand::from_sql(*expr)?,
+                pattern: Operand::literal_from_sql(*pattern)?,
+                negated,
+            }),
+            ast::Expr::BinaryOp { left, op: op @ (ast::BinaryOperator::PGRegexMatch | ast::BinaryOperator::PGRegexNotMatch), right } => Ok(Self::Regex {
+                operand: Operand::from_sql(*left)?,
+                pattern: Operand::literal_from_sql(*right)?,
+                negated: matches!(op, ast::BinaryOperator::PGRegexNotMatch),
+            }),
+            ast::Expr::BinaryOp { left, op, right } => match Operator::from_sql(&op) {
+                Some(operator) => Ok(Self::Compare {
+                    left: Operand::from_sql(*left)?,
+                    operator,
+                    right: Operand::from_sql(*right)?,
+                }),
+                None => Err(ParseError(format!("Unsupported operator '{op}'"))),
+            },
+            expr => Err(ParseError(format!("Unsupported expression '{expr}'"))),
+        }
+    }
+}
+
+impl core::str::FromStr for Expression {
+    type Err = ParseError;
+    #[inline(always)]
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Self::from_sql(parse_sql(text)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_lower_comparison_and_logical_operators() {
+        let expr = "a > 5 AND (b = 'x' OR NOT c <= -1.5)".parse::<Expression>().expect("to parse");
+        let (left, right) = match expr {
+            Expression::And(left, right) => (left, right),
+            expr => panic!("Expected AND, got {expr:?}"),
+        };
+        assert!(matches!(*left, Expression::Compare { left: Operand::Identifier(ref column), operator: Operator::Greater, right: Operand::Number(ref number) } if column == "a" && number == "5"));
+        let (left, right) = match *right {
+            Expression::Or(left, right) => (left, right),
+            expr => panic!("Expected OR, got {expr:?}"),
+        };
+        assert!(matches!(*left, Expression::Compare { operator: Operator::Eq, right: Operand::Literal(ref literal), .. } if literal == "x"));
+        match *right {
+            Expression::Not(expr) => assert!(matches!(*expr, Expression::Compare { operator: Operator::LessEq, right: Operand::Number(ref number), .. } if number == "-1.5")),
+            expr => panic!("Expected NOT, got {expr:?}"),
+        }
+    }
+
+    #[test]
+    fn should_keep_double_quoted_identifier_as_literal() {
+        let expr = "`name` = \"value\"".parse::<Expression>().expect("to parse");
+        assert!(matches!(expr, Expression::Compare { left: Operand::Identifier(ref column), right: Operand::Literal(ref literal), .. } if column == "name" && literal == "value"));
+
+        let expr = "t.name != 'value'".parse::<Expression>().expect("to parse");
+        assert!(matches!(expr, Expression::Compare { left: Operand::Identifier(ref column), operator: Operator::NotEq, .. } if column == "t.name"));
+    }
+
+    #[test]
+    fn should_lower_predicates() {
+        let expr = "a NOT IN (1, 'b', -2)".parse::<Expression>().expect("to parse");
+        assert!(matches!(expr, Expression::In { negated: true, ref list, .. } if *list == ["1", "b", "-2"]));
+
+        let expr = "a BETWEEN 1 AND 10".parse::<Expression>().expect("to parse");
+        assert!(matches!(expr, Expression::Between { negated: false, low: Operand::Number(_), high: Operand::Number(_), .. }));
+
+        let expr = "a IS NOT NULL".parse::<Expression>().expect("to parse");
+        assert!(matches!(expr, Expression::IsNull { negated: true, .. }));
+
+        let expr = "a NOT ILIKE 'x%'".parse::<Expression>().expect("to parse");
+        assert!(matches!(expr, Expression::Like { case_insensitive: true, negated: true, ref pattern, .. } if pattern == "x%"));
+
+        let expr = "a !~ '^x'".parse::<Expression>().expect("to parse");
+        assert!(matches!(expr, Expression::Regex { negated: true, ref pattern, .. } if pattern == "^x"));
+    }
+
+    #[test]
+    fn should_reject_invalid_expression() {
+        assert!("a >".parse::<Expression>().is_err());
+        assert!("a = 1 b".parse::<Expression>().is_err());
+        assert!("a IN (b + 1)".parse::<Expression>().is_err());
+        assert!("a LIKE b".parse::<Expression>().is_err());
+        assert!("a & 1".parse::<Expression>().is_err());
+    }
+
+    #[test]
+    fn should_lower_computed_column() {
+        let column = "total = price * (qty + 1)".parse::<ComputedColumn>().expect("to parse");
+        assert_eq!(column.name, "total");
+        let (left, right) = match column.expr {
+            Operand::Arithmetic { left, operator: ArithmeticOperator::Multiply, right } => (left, right),
+            expr => panic!("Expected multiplication, got {expr:?}"),
+        };
+        assert!(matches!(*left, Operand::Identifier(ref column) if column == "price"));
+        assert!(matches!(*right, Operand::Arithmetic { operator: ArithmeticOperator::Add, .. }));
+
+        let column = "upper(name)".parse::<ComputedColumn>().expect("to parse");
+        assert_eq!(column.name, "upper(name)");
+        assert!(matches!(column.expr, Operand::Function { function: Function::Upper, ref args } if args.len() == 1));
+
+        let column = "day=date_trunc('day', ts)".parse::<ComputedColumn>().expect("to parse");
+        assert!(matches!(column.expr, Operand::Function { function: Function::DateTrunc(TruncateUnit::Day), ref args } if args.len() == 1));
+
+        //Only bare identifier is treated as name
+        let column = "coalesce(name, 'a=b')".parse::<ComputedColumn>().expect("to parse");
+        assert_eq!(column.name, "coalesce(name, 'a=b')");
+        assert!(matches!(column.expr, Operand::Function { function: Function::Coalesce, ref args } if args.len() == 2));
+
+        assert!("=a + 1".parse::<ComputedColumn>().is_err());
+        assert!("x=upper(a, b)".parse::<ComputedColumn>().is_err());
+        assert!("x=date_trunc('decade', ts)".parse::<ComputedColumn>().is_err());
+        assert!("x=unknown(a)".parse::<ComputedColumn>().is_err());
+    }
+
+    #[test]
+    fn should_parse_aggregate() {
+        let aggregate = "rows=count(*)".parse::<Aggregate>().expect("to parse");
+        assert_eq!(aggregate.name, "rows");
+        assert!(matches!(aggregate.function, Aggregation::Count));
+        assert!(aggregate.argument.is_none());
+
+        let aggregate = "users=count(DISTINCT user_id)".parse::<Aggregate>().expect("to parse");
+        assert!(matches!(aggregate.function, Aggregation::CountDistinct));
+        assert!(matches!(aggregate.argument, Some(Operand::Identifier(ref column)) if column == "user_id"));
+
+        let aggregate = "AVG(price)".parse::<Aggregate>().expect("to parse");
+        assert_eq!(aggregate.name, "AVG(price)");
+        assert!(matches!(aggregate.function, Aggregation::Mean));
+
+        //Only bare identifier is treated as name
+        let aggregate = "max(coalesce(tag, '='))".parse::<Aggregate>().expect("to parse");
+        assert_eq!(aggregate.name, "max(coalesce(tag, '='))");
+        assert!(matches!(aggregate.function, Aggregation::Max));
+
+        assert!("x=sum(*)".parse::<Aggregate>().is_err());
+        assert!("x=sum(a, b)".parse::<Aggregate>().is_err());
+        assert!("x=median(a)".parse::<Aggregate>().is_err());
+        assert!("x=a + 1".parse::<Aggregate>().is_err());
+    }
+
+    #[test]
+    fn should_convert_like_to_regex() {
+        assert_eq!(like_to_regex("a%b_c", false), "(?s)^a.*b.c$");
+        assert_eq!(like_to_regex("A%", true), "(?is)^A.*$");
+        //Escaped wildcards are matched literally
+        assert_eq!(like_to_regex("100\\%", false), "(?s)^100%$");
+        assert_eq!(like_to_regex("a\\_b", false), "(?s)^a_b$");
+        assert_eq!(like_to_regex("a\\\\b", false), "(?s)^a\\\\b$");
+        //Trailing backslash is matched literally
+        assert_eq!(like_to_regex("a\\", false), "(?s)^a\\\\$");
+        assert_eq!(like_to_regex("1.5+(x)", false), "(?s)^1\\.5\\+\\(x\\)$");
+    }
+
+    #[test]
+    fn should_apply_like_escape() {
+        let pattern = |text: &str| match text.parse::<Expression>() {
+            Ok(Expression::Like { pattern, .. }) => Ok(pattern),
+            Ok(expr) => panic!("Expected LIKE, got {expr:?}"),
+            Err(error) => Err(error),
+        };
+        assert_eq!(pattern("a LIKE 'a_%'").ok().as_deref(), Some("a_%"));
+        assert_eq!(pattern("a ILIKE 'a!_%!!' ESCAPE '!'").ok().as_deref(), Some("a\\_%!"));
+        assert_eq!(pattern("a LIKE '10#%#' ESCAPE '#'").ok().as_deref(), Some("10\\%\\#"));
+        assert_eq!(like_to_regex(&pattern("a LIKE '10#%' ESCAPE '#'").expect("to parse"), false), "(?s)^10%$");
+        assert!(pattern("a LIKE 'a' ESCAPE 'ab'").is_err());
+    }
+
+    #[test]
+    fn should_escape_regex() {
+        assert_eq!(escape_regex("plain"), "plain");
+        assert_eq!(escape_regex("N/A"), "N/A");
+        assert_eq!(escape_regex("a.b*c?[d]{e}^$|-~#&"), "a\\.b\\*c\\?\\[d\\]\\{e\\}\\^\\$\\|\\-\\~\\#\\&");
+    }
+}
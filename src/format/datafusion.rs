@@ -1,5 +1,5 @@
 //!Datafusion formatting
-use super::{Schema, DataFrame};
+use super::{Cell, ColumnInfo, ColumnSource, DataFrame, OutputFormat, RowWriter, Schema};
 
 use core::fmt;
 
@@ -44,6 +44,41 @@ impl fmt::Display for Schema<'_, DatafusionSchema> {
     }
 }
 
+///Describes columns of `schema`
+pub fn columns(schema: &DatafusionSchema, partitions: &[String]) -> Vec<ColumnInfo> {
+    schema.fields().iter().map(|field| ColumnInfo {
+        name: field.name().clone(),
+        dtype: type_name(field.data_type()),
+        nullable: field.is_nullable(),
+        source: match partitions.contains(field.name()) {
+            true => ColumnSource::Partition,
+            false => ColumnSource::File,
+        },
+    }).collect()
+}
+
+//Names `dtype` in the same way as polars backend does
+fn type_name(dtype: &datafusion::common::arrow::datatypes::DataType) -> String {
+    use datafusion::common::arrow::datatypes::DataType;
+
+    match dtype {
+        DataType::Float16 => "float16".to_owned(),
+        DataType::Float32 => "float32".to_owned(),
+        DataType::Float64 => "float64".to_owned(),
+        DataType::Binary | DataType::LargeBinary | DataType::BinaryView | DataType::FixedSizeBinary(_) => "binary".to_owned(),
+        DataType::Time32(_) | DataType::Time64(_) => "time".to_owned(),
+        DataType::List(inner) | DataType::LargeList(inner) | DataType::ListView(inner) | DataType::LargeListView(inner) | DataType::FixedSizeList(inner, _) => format!("list<{}>", type_name(inner.data_type())),
+        DataType::Struct(fields) => {
+            let fields = fields.iter().map(|field| format!("{}: {}", field.name(), type_name(field.data_type()))).collect::<Vec<_>>();
+            format!("struct<{}>", fields.join(", "))
+        },
+        dtype => match crate::datafusion::literal_type(dtype) {
+            Some(typ) => typ.to_string(),
+            None => dtype.to_string(),
+        },
+    }
+}
+
 impl fmt::Display for DataFrame<'_, RecordBatch> {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         use datafusion::common::arrow::util::display::{ArrayFormatter, FormatOptions};
@@ -78,24 +113,91 @@ impl fmt::Display for DataFrame<'_, RecordBatch> {
     }
 }
 
-///Prints partitioned data returning number of rows
-pub async fn format_partitioned_data(stream: Vec<datafusion::execution::SendableRecordBatchStream>) -> Result<usize, DataFusionError> {
-    let mut count = 0usize;
-    let mut is_first = true;
+///Converts value at `idx` of arrow `array` into [Cell]
+pub fn cell(array: &dyn datafusion::common::arrow::array::Array, idx: usize) -> Cell {
+    use datafusion::common::ScalarValue;
+    use crate::literal::{TimeUnit, Value};
+
+    let value = match ScalarValue::try_from_array(array, idx) {
+        Ok(value) => value,
+        Err(_) => return None,
+    };
+    if value.is_null() {
+        return None;
+    }
+
+    let timestamp = |value: i64, unit, timezone: Option<std::sync::Arc<str>>| Value::Timestamp {
+        value,
+        unit,
+        timezone: timezone.map(|timezone| timezone.to_string()),
+    };
+    let value = match value {
+        ScalarValue::Boolean(Some(value)) => Value::Boolean(value),
+        ScalarValue::Int8(Some(value)) => Value::Int(value.into()),
+        ScalarValue::Int16(Some(value)) => Value::Int(value.into()),
+        ScalarValue::Int32(Some(value)) => Value::Int(value.into()),
+        ScalarValue::Int64(Some(value)) => Value::Int(value),
+        ScalarValue::UInt8(Some(value)) => Value::UInt(value.into()),
+        ScalarValue::UInt16(Some(value)) => Value::UInt(value.into()),
+        ScalarValue::UInt32(Some(value)) => Value::UInt(value.into()),
+        ScalarValue::UInt64(Some(value)) => Value::UInt(value),
+        ScalarValue::Float32(Some(value)) => Value::Float(value.into()),
+        ScalarValue::Float64(Some(value)) => Value::Float(value),
+        ScalarValue::Decimal128(Some(value), precision, scale) => Value::Decimal {
+            value,
+            precision,
+            scale,
+        },
+        ScalarValue::Date32(Some(days)) => Value::Date(days),
+        ScalarValue::TimestampSecond(Some(value), timezone) => timestamp(value, TimeUnit::Second, timezone),
+        ScalarValue::TimestampMillisecond(Some(value), timezone) => timestamp(value, TimeUnit::Millisecond, timezone),
+        ScalarValue::TimestampMicrosecond(Some(value), timezone) => timestamp(value, TimeUnit::Microsecond, timezone),
+        ScalarValue::TimestampNanosecond(Some(value), timezone) => timestamp(value, TimeUnit::Nanosecond, timezone),
+        ScalarValue::Time32Second(Some(value)) => return super::time(value.into(), 1),
+        ScalarValue::Time32Millisecond(Some(value)) => return super::time(value.into(), 1_000),
+        ScalarValue::Time64Microsecond(Some(value)) => return super::time(value, 1_000_000),
+        ScalarValue::Time64Nanosecond(Some(value)) => return super::time(value, 1_000_000_000),
+        ScalarValue::Utf8(Some(value)) | ScalarValue::LargeUtf8(Some(value)) | ScalarValue::Utf8View(Some(value)) => Value::String(value),
+        ScalarValue::Binary(Some(value)) | ScalarValue::LargeBinary(Some(value)) | ScalarValue::BinaryView(Some(value)) | ScalarValue::FixedSizeBinary(_, Some(value)) => return super::binary(&value),
+        //Nested and other types are printed as arrow formats them
+        value => Value::String(value.to_string()),
+    };
+    Some(value)
+}
+
+///Prints partitioned data in specified `format` returning number of rows
+pub async fn format_partitioned_data(stream: Vec<datafusion::execution::SendableRecordBatchStream>, format: OutputFormat) -> Result<usize, DataFusionError> {
+    let mut writer = None;
+    let mut row = Vec::new();
 
     for mut partition in stream {
-        if is_first {
-            is_first = false;
-            let schema = partition.schema();
-            println!("{}", Schema(&*schema));
+        if writer.is_none() {
+            let columns = partition.schema().fields().iter().map(|field| field.name().clone()).collect();
+            let mut new_writer = RowWriter::new(format, columns);
+            new_writer.write_header(&mut std::io::stdout().lock())?;
+            writer = Some(new_writer);
         }
+        let writer = match writer.as_mut() {
+            Some(writer) => writer,
+            None => break,
+        };
 
         while let Some(record) = partition.next().await {
             let record = record?;
-            count = count.saturating_add(record.num_rows());
-            print!("{}", DataFrame(&record));
+            let mut out = std::io::stdout().lock();
+            for idx in 0..record.num_rows() {
+                row.clear();
+                row.extend(record.columns().iter().map(|column| cell(column.as_ref(), idx)));
+                writer.write_row(&mut out, &row)?;
+            }
         }
     }
 
-    Ok(count)
+    match writer {
+        Some(mut writer) => {
+            writer.finish(&mut std::io::stdout().lock())?;
+            Ok(writer.row_count())
+        },
+        None => Ok(0),
+    }
 }
@@ -1,5 +1,9 @@
 //!Formatting
 
+use std::io;
+
+use crate::literal::Value;
+
 #[cfg(feature = "datafusion")]
 pub mod datafusion;
 #[cfg(feature = "polars")]
@@ -10,3 +14,579 @@ pub struct Schema<'a, T>(pub &'a T);
 
 ///Formatter for data frame
 pub struct DataFrame<'a, T>(pub &'a T);
+
+///Value of a single cell. `None` is null
+///
+///Both backends convert their values into it, so output is identical regardless of backend
+pub type Cell = Option<Value>;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+///Output format of rows
+pub enum OutputFormat {
+    ///CSV with RFC 4180 quoting
+    Csv,
+    ///Aligned table
+    Table,
+    ///JSON object per line
+    JsonLines,
+    ///Markdown table
+    Markdown,
+}
+
+impl core::str::FromStr for OutputFormat {
+    type Err = &'static str;
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        if text.eq_ignore_ascii_case("csv") {
+            Ok(Self::Csv)
+        } else if text.eq_ignore_ascii_case("table") {
+            Ok(Self::Table)
+        } else if text.eq_ignore_ascii_case("json") || text.eq_ignore_ascii_case("jsonl") || text.eq_ignore_ascii_case("ndjson") {
+            Ok(Self::JsonLines)
+        } else if text.eq_ignore_ascii_case("markdown") || text.eq_ignore_ascii_case("md") {
+            Ok(Self::Markdown)
+        } else {
+            Err("Allowed values: csv, table, json, markdown")
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+///Origin of the column
+pub enum ColumnSource {
+    ///Stored within data files
+    File,
+    ///Hive partition within path (`key=value`)
+    Partition,
+}
+
+impl ColumnSource {
+    #[inline]
+    ///Returns textual representation
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::File => "file",
+            Self::Partition => "partition",
+        }
+    }
+}
+
+///Description of the column
+pub struct ColumnInfo {
+    ///Column name
+    pub name: String,
+    ///Type of the column, named the same way by every backend
+    pub dtype: String,
+    ///Whether column may contain nulls
+    pub nullable: bool,
+    ///Origin of the column
+    pub source: ColumnSource,
+}
+
+///Writes description of `columns` in specified `format`
+pub fn write_columns(format: OutputFormat, columns: &[ColumnInfo], out: &mut impl io::Write) -> io::Result<()> {
+    let mut writer = RowWriter::new(format, ["name", "type", "nullable", "source"].map(str::to_owned).into());
+    writer.write_header(out)?;
+    for column in columns {
+        writer.write_row(out, &[
+            Some(Value::String(column.name.clone())),
+            Some(Value::String(column.dtype.clone())),
+            Some(Value::Boolean(column.nullable)),
+            Some(Value::String(column.source.as_str().to_owned())),
+        ])?;
+    }
+    writer.finish(out)
+}
+
+///Statistics of the column
+pub struct ColumnStats {
+    ///Column name
+    pub name: String,
+    ///Number of rows
+    pub rows: Option<u64>,
+    ///Number of nulls
+    pub nulls: Option<u64>,
+    ///Minimum value
+    pub min: Cell,
+    ///Maximum value
+    pub max: Cell,
+    ///Mean of numeric column
+    pub mean: Option<f64>,
+    ///Approximate number of distinct values
+    pub distinct: Option<u64>,
+}
+
+///Converts counter cell into integer
+pub fn count(cell: Cell) -> Option<u64> {
+    match cell {
+        Some(Value::UInt(value)) => Some(value),
+        Some(Value::Int(value)) => u64::try_from(value).ok(),
+        _ => None,
+    }
+}
+
+///Converts time of day in `units` since midnight, with `per_second` units in second, into cell formatted as `HH:MM:SS[.fraction]`
+pub fn time(units: i64, per_second: i64) -> Cell {
+    let seconds = units.div_euclid(per_second);
+    let fraction = units.rem_euclid(per_second);
+    let mut text = format!("{:02}:{:02}:{:02}", seconds / 3600, seconds % 3600 / 60, seconds % 60);
+    if fraction != 0 {
+        let width = per_second.ilog10() as usize;
+        text.push_str(&format!(".{fraction:0width$}"));
+    }
+    Some(Value::String(text))
+}
+
+///Converts binary into cell formatted as lowercase hex
+pub fn binary(bytes: &[u8]) -> Cell {
+    use core::fmt::Write;
+
+    let mut text = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        let _ = write!(&mut text, "{byte:02x}");
+    }
+    Some(Value::String(text))
+}
+
+///Writes statistics of `columns` in specified `format`
+pub fn write_stats(format: OutputFormat, columns: &[ColumnStats], out: &mut impl io::Write) -> io::Result<()> {
+    let mut writer = RowWriter::new(format, ["column", "rows", "nulls", "min", "max", "mean", "distinct"].map(str::to_owned).into());
+    writer.write_header(out)?;
+    for column in columns {
+        writer.write_row(out, &[
+            Some(Value::String(column.name.clone())),
+            column.rows.map(Value::UInt),
+            column.nulls.map(Value::UInt),
+            column.min.clone(),
+            column.max.clone(),
+            column.mean.map(Value::Float),
+            column.distinct.map(Value::UInt),
+        ])?;
+    }
+    writer.finish(out)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+///Kind of rows within result of comparing data by key
+pub enum DiffKind {
+    ///Rows present in left data only
+    OnlyLeft,
+    ///Rows present in right data only
+    OnlyRight,
+    ///Rows present in both, but with different values
+    Changed,
+}
+
+impl DiffKind {
+    ///All kinds in order of output
+    pub const ALL: [Self; 3] = [Self::OnlyLeft, Self::OnlyRight, Self::Changed];
+
+    #[inline]
+    ///Returns textual representation
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::OnlyLeft => "only_left",
+            Self::OnlyRight => "only_right",
+            Self::Changed => "changed",
+        }
+    }
+}
+
+///Number of rows of the same kind within result of comparing data by key
+pub struct DiffCount {
+    ///Kind of rows
+    pub kind: DiffKind,
+    ///Changed column. `None` for total number of rows
+    pub column: Option<String>,
+    ///Number of rows
+    pub rows: u64,
+}
+
+///Writes summary of comparison in specified `format`
+pub fn write_diff_summary(format: OutputFormat, counts: &[DiffCount], out: &mut impl io::Write) -> io::Result<()> {
+    let mut writer = RowWriter::new(format, ["kind", "column", "rows"].map(str::to_owned).into());
+    writer.write_header(out)?;
+    for count in counts {
+        writer.write_row(out, &[
+            Some(Value::String(count.kind.as_str().to_owned())),
+            count.column.clone().map(Value::String),
+            Some(Value::UInt(count.rows)),
+        ])?;
+    }
+    writer.finish(out)
+}
+
+///Builds summary of comparison from `rows` of every [DiffKind] (in order of [DiffKind::ALL]), followed by number of changes of every compared column
+///
+///Columns without changes are omitted, others are ordered by name
+pub fn diff_summary(columns: &[String], rows: impl Fn(usize) -> u64) -> Vec<DiffCount> {
+    let mut counts = DiffKind::ALL.iter().enumerate().map(|(idx, kind)| DiffCount {
+        kind: *kind,
+        column: None,
+        rows: rows(idx),
+    }).collect::<Vec<_>>();
+
+    let mut changes = columns.iter().enumerate().map(|(idx, column)| (column, rows(DiffKind::ALL.len() + idx))).filter(|(_, rows)| *rows > 0).collect::<Vec<_>>();
+    changes.sort();
+    counts.extend(changes.into_iter().map(|(column, rows)| DiffCount {
+        kind: DiffKind::Changed,
+        column: Some(column.clone()),
+        rows,
+    }));
+    counts
+}
+
+///Changes of rows present in both compared data, one row per changed column
+///
+///Values are formatted as text by [Cell], so that both backends report them identically.
+///Backends unpivot changed rows batch by batch, taking values of every batch by [DiffChanges::take]
+pub struct DiffChanges {
+    ///Names of output columns: key columns followed by `column`, `left` and `right`
+    pub names: Vec<String>,
+    ///Values of every output column since last [DiffChanges::take]
+    pub values: Vec<Vec<Option<String>>>,
+    columns: Vec<String>,
+}
+
+impl DiffChanges {
+    ///Creates empty changes of compared `columns` identified by `keys`
+    pub fn new(keys: &[String], columns: &[String]) -> Self {
+        let names = keys.iter().cloned().chain(["column", "left", "right"].map(str::to_owned)).collect::<Vec<_>>();
+        Self {
+            values: names.iter().map(|_| Vec::new()).collect(),
+            names,
+            columns: columns.to_vec(),
+        }
+    }
+
+    #[inline]
+    ///Returns number of changes
+    pub fn len(&self) -> usize {
+        self.values.first().map_or(0, Vec::len)
+    }
+
+    #[inline]
+    ///Returns whether there are no changes
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    ///Adds changes of `row`, which consists of key values followed by left value, right value and change mark of every compared column
+    pub fn push(&mut self, row: &[Cell]) {
+        let keys = self.names.len() - 3;
+        let (key_values, values) = row.split_at(keys.min(row.len()));
+        for (column, values) in self.columns.iter().zip(values.chunks_exact(3)) {
+            if !matches!(values[2], Some(Value::Boolean(true))) {
+                continue;
+            }
+            let text = |value: &Cell| value.as_ref().map(ToString::to_string);
+            for (output, value) in self.values.iter_mut().zip(key_values) {
+                output.push(text(value));
+            }
+            self.values[keys].push(Some(column.clone()));
+            self.values[keys + 1].push(text(&values[0]));
+            self.values[keys + 2].push(text(&values[1]));
+        }
+    }
+
+    #[inline]
+    ///Takes values of every output column, leaving changes empty
+    pub fn take(&mut self) -> Vec<Vec<Option<String>>> {
+        self.values.iter_mut().map(core::mem::take).collect()
+    }
+}
+
+///Writes number of rows per group of `group_by` columns in specified `format`
+pub fn write_counts(format: OutputFormat, group_by: &[String], counts: &[(Vec<Cell>, u64)], out: &mut impl io::Write) -> io::Result<()> {
+    let mut writer = RowWriter::new(format, group_by.iter().cloned().chain(["count".to_owned()]).collect());
+    writer.write_header(out)?;
+    let mut row = Vec::with_capacity(group_by.len() + 1);
+    for (group, count) in counts {
+        row.clear();
+        row.extend(group.iter().cloned());
+        row.push(Some(Value::UInt(*count)));
+        writer.write_row(out, &row)?;
+    }
+    writer.finish(out)
+}
+
+///Writes rows in specified [OutputFormat]
+///
+///Table format requires width of every column, so its rows are buffered until [RowWriter::finish]
+pub struct RowWriter {
+    format: OutputFormat,
+    columns: Vec<String>,
+    rows: Vec<Vec<String>>,
+    row_count: usize,
+}
+
+impl RowWriter {
+    ///Creates new writer for `columns`
+    pub fn new(format: OutputFormat, columns: Vec<String>) -> Self {
+        Self {
+            format,
+            columns,
+            rows: Vec::new(),
+            row_count: 0,
+        }
+    }
+
+    #[inline]
+    ///Returns number of rows written so far
+    pub fn row_count(&self) -> usize {
+        self.row_count
+    }
+
+    ///Writes header, if format has it
+    pub fn write_header(&mut self, out: &mut impl io::Write) -> io::Result<()> {
+        match self.format {
+            OutputFormat::Csv => {
+                let header = self.columns.iter().map(|column| csv_field(column)).collect::<Vec<_>>();
+                writeln!(out, "{}", header.join(","))
+            },
+            OutputFormat::Markdown => {
+                let header = self.columns.iter().map(|column| markdown_field(column)).collect::<Vec<_>>();
+                writeln!(out, "| {} |", header.join(" | "))?;
+                writeln!(out, "|{}", " --- |".repeat(self.columns.len()))
+            },
+            OutputFormat::Table | OutputFormat::JsonLines => Ok(()),
+        }
+    }
+
+    ///Writes single row
+    pub fn write_row(&mut self, out: &mut impl io::Write, row: &[Cell]) -> io::Result<()> {
+        self.row_count = self.row_count.saturating_add(1);
+        match self.format {
+            OutputFormat::Csv => {
+                let fields = row.iter().map(|cell| match cell {
+                    Some(value) => csv_field(&value.to_string()),
+                    None => String::new(),
+                }).collect::<Vec<_>>();
+                writeln!(out, "{}", fields.join(","))
+            },
+            OutputFormat::Table => {
+                self.rows.push(row.iter().map(|cell| match cell {
+                    Some(value) => value.to_string().replace('\n', "\\n"),
+                    None => "null".to_owned(),
+                }).collect());
+                Ok(())
+            },
+            OutputFormat::JsonLines => {
+                let mut line = String::from("{");
+                for (idx, (column, cell)) in self.columns.iter().zip(row.iter()).enumerate() {
+                    if idx > 0 {
+                        line.push(',');
+                    }
+                    push_json_string(&mut line, column);
+                    line.push(':');
+                    push_json_value(&mut line, cell);
+                }
+                line.push('}');
+                writeln!(out, "{line}")
+            },
+            OutputFormat::Markdown => {
+                let fields = row.iter().map(|cell| match cell {
+                    Some(value) => markdown_field(&value.to_string()),
+                    None => "null".to_owned(),
+                }).collect::<Vec<_>>();
+                writeln!(out, "| {} |", fields.join(" | "))
+            },
+        }
+    }
+
+    ///Writes buffered rows, if any
+    pub fn finish(&mut self, out: &mut impl io::Write) -> io::Result<()> {
+        if self.format != OutputFormat::Table {
+            return Ok(());
+        }
+
+        let mut widths = self.columns.iter().map(|column| column.chars().count()).collect::<Vec<_>>();
+        for row in self.rows.iter() {
+            for (width, field) in widths.iter_mut().zip(row.iter()) {
+                *width = (*width).max(field.chars().count());
+            }
+        }
+
+        let separator = widths.iter().map(|width| "-".repeat(width + 2)).collect::<Vec<_>>().join("+");
+        writeln!(out, "+{separator}+")?;
+        write_table_line(out, self.columns.iter(), &widths)?;
+        writeln!(out, "+{separator}+")?;
+        for row in self.rows.drain(..) {
+            write_table_line(out, row.iter(), &widths)?;
+        }
+        writeln!(out, "+{separator}+")
+    }
+}
+
+fn write_table_line<'a>(out: &mut impl io::Write, fields: impl Iterator<Item = &'a String>, widths: &[usize]) -> io::Result<()> {
+    let fields = fields.zip(widths.iter()).map(|(field, width)| {
+        let padding = width - field.chars().count();
+        format!(" {field}{} ", " ".repeat(padding))
+    }).collect::<Vec<_>>();
+    writeln!(out, "|{}|", fields.join("|"))
+}
+
+//Quotes field if it contains delimiter, quote or line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+fn markdown_field(field: &str) -> String {
+    field.replace('|', "\\|").replace("\r\n", "<br>").replace('\n', "<br>")
+}
+
+fn push_json_string(out: &mut String, text: &str) {
+    out.push('"');
+    for ch in text.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => out.push(ch),
+        }
+    }
+    out.push('"');
+}
+
+fn push_json_value(out: &mut String, cell: &Cell) {
+    match cell {
+        None => out.push_str("null"),
+        Some(Value::Boolean(value)) => out.push_str(if *value { "true" } else { "false" }),
+        Some(Value::Int(value)) => out.push_str(&value.to_string()),
+        Some(Value::UInt(value)) => out.push_str(&value.to_string()),
+        //JSON has no representation for NaN and infinity
+        Some(Value::Float(value)) if !value.is_finite() => out.push_str("null"),
+        Some(Value::Float(value)) => out.push_str(&value.to_string()),
+        //Keep precision of decimal by writing it as it is
+        Some(value @ Value::Decimal { .. }) => out.push_str(&value.to_string()),
+        Some(value) => push_json_string(out, &value.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::literal::TimeUnit;
+
+    fn write(format: OutputFormat, columns: &[&str], rows: &[Vec<Cell>]) -> String {
+        let mut out = Vec::new();
+        let mut writer = RowWriter::new(format, columns.iter().map(|column| (*column).to_owned()).collect());
+        writer.write_header(&mut out).expect("to write header");
+        for row in rows {
+            writer.write_row(&mut out, row).expect("to write row");
+        }
+        writer.finish(&mut out).expect("to finish");
+        assert_eq!(writer.row_count(), rows.len());
+        String::from_utf8(out).expect("utf-8 output")
+    }
+
+    fn text(value: &str) -> Cell {
+        Some(Value::String(value.to_owned()))
+    }
+
+    #[test]
+    fn should_unpivot_diff_changes() {
+        let keys = ["id".to_owned()];
+        let columns = ["name".to_owned(), "amount".to_owned()];
+        let mut changes = DiffChanges::new(&keys, &columns);
+        let changed = Some(Value::Boolean(true));
+        let unchanged = Some(Value::Boolean(false));
+        changes.push(&[Some(Value::Int(1)), text("a"), text("b"), changed.clone(), Some(Value::Int(5)), Some(Value::Int(5)), unchanged.clone()]);
+        changes.push(&[Some(Value::Int(2)), text("c"), text("c"), unchanged, None, Some(Value::Decimal { value: 1250, precision: 5, scale: 2 }), changed]);
+
+        assert_eq!(changes.names, ["id", "column", "left", "right"]);
+        assert_eq!(changes.len(), 2);
+        let text = |values: &[&str]| values.iter().map(|value| (!value.is_empty()).then(|| (*value).to_owned())).collect::<Vec<_>>();
+        assert_eq!(changes.take(), [text(&["1", "2"]), text(&["name", "amount"]), text(&["a", ""]), text(&["b", "12.50"])]);
+        assert!(changes.is_empty());
+        assert_eq!(changes.values.len(), changes.names.len());
+    }
+
+    #[test]
+    fn should_summarize_diff() {
+        let columns = ["name".to_owned(), "amount".to_owned(), "date".to_owned()];
+        let rows = [1, 2, 3, 2, 0, 1];
+        let counts = diff_summary(&columns, |idx| rows[idx]);
+        let counts = counts.iter().map(|count| (count.kind, count.column.as_deref(), count.rows)).collect::<Vec<_>>();
+        assert_eq!(counts, [
+            (DiffKind::OnlyLeft, None, 1),
+            (DiffKind::OnlyRight, None, 2),
+            (DiffKind::Changed, None, 3),
+            (DiffKind::Changed, Some("date"), 1),
+            (DiffKind::Changed, Some("name"), 2),
+        ]);
+    }
+
+    #[test]
+    fn should_escape_csv() {
+        let rows = [
+            vec![text("plain"), None, Some(Value::Int(-1))],
+            vec![text("a,b"), text("say \"hi\""), text("line\nbreak")],
+            vec![text(""), text("cr\r"), Some(Value::Boolean(true))],
+        ];
+        let output = write(OutputFormat::Csv, &["name", "quoted,header", "value"], &rows);
+        assert_eq!(output, "name,\"quoted,header\",value\nplain,,-1\n\"a,b\",\"say \"\"hi\"\"\",\"line\nbreak\"\n,\"cr\r\",true\n");
+    }
+
+    #[test]
+    fn should_escape_json() {
+        let rows = [
+            vec![text("quote \" and \\ slash"), Some(Value::Float(1.5)), None],
+            vec![text("tab\tline\nreturn\r\u{1}"), Some(Value::Float(f64::NAN)), Some(Value::UInt(7))],
+            vec![text("ünïcode"), Some(Value::Decimal { value: -1050, precision: 10, scale: 2 }), Some(Value::Date(0))],
+        ];
+        let output = write(OutputFormat::JsonLines, &["a\"b", "number", "other"], &rows);
+        let mut lines = output.lines();
+        assert_eq!(lines.next(), Some(r#"{"a\"b":"quote \" and \\ slash","number":1.5,"other":null}"#));
+        assert_eq!(lines.next(), Some(r#"{"a\"b":"tab\tline\nreturn\r\u0001","number":null,"other":7}"#));
+        assert_eq!(lines.next(), Some(r#"{"a\"b":"ünïcode","number":-10.50,"other":"1970-01-01"}"#));
+        assert_eq!(lines.next(), None);
+    }
+
+    #[test]
+    fn should_write_json_timestamp_as_string() {
+        let rows = [vec![Some(Value::Timestamp { value: 0, unit: TimeUnit::Second, timezone: Some("UTC".to_owned()) })]];
+        let output = write(OutputFormat::JsonLines, &["ts"], &rows);
+        assert_eq!(output, "{\"ts\":\"1970-01-01T00:00:00Z\"}\n");
+    }
+
+    #[test]
+    fn should_align_table() {
+        let rows = [
+            vec![text("a"), None],
+            vec![text("multi\nline"), Some(Value::Int(10))],
+        ];
+        let output = write(OutputFormat::Table, &["name", "n"], &rows);
+        let expected = "\
++-------------+------+
+| name        | n    |
++-------------+------+
+| a           | null |
+| multi\\nline | 10   |
++-------------+------+
+";
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn should_escape_markdown() {
+        let rows = [vec![text("a|b"), text("line\r\nbreak")]];
+        let output = write(OutputFormat::Markdown, &["x|y", "z"], &rows);
+        assert_eq!(output, "| x\\|y | z |\n| --- | --- |\n| a\\|b | line<br>break |\n");
+    }
+
+    #[test]
+    fn should_format_time_and_binary() {
+        assert!(matches!(time(3_723, 1), Some(Value::String(ref value)) if value == "01:02:03"));
+        assert!(matches!(time(3_723_004_000, 1_000_000), Some(Value::String(ref value)) if value == "01:02:03.004000"));
+        assert!(matches!(time(86_399_999_999_999, 1_000_000_000), Some(Value::String(ref value)) if value == "23:59:59.999999999"));
+        assert!(matches!(binary(&[0x00, 0xAB, 0x10]), Some(Value::String(ref value)) if value == "00ab10"));
+        assert!(matches!(binary(&[]), Some(Value::String(ref value)) if value.is_empty()));
+    }
+}
@@ -1,7 +1,8 @@
 //! Polars formatting module
 
 use core::fmt;
-use core::sync::atomic::{self, AtomicBool, AtomicUsize};
+use std::io;
+use std::sync::Mutex;
 
 use ::polars as pl;
 
@@ -23,6 +24,42 @@ impl fmt::Display for Schema<'_, pl::prelude::Schema> {
     }
 }
 
+///Describes columns of `schema`
+///
+///Polars does not track nullability, so every column is reported as nullable
+pub fn columns(schema: &pl::prelude::Schema, partitions: &[String]) -> Vec<ColumnInfo> {
+    schema.iter().map(|(name, dtype)| ColumnInfo {
+        name: name.to_string(),
+        dtype: type_name(dtype),
+        nullable: true,
+        source: match partitions.iter().any(|partition| partition == name.as_str()) {
+            true => ColumnSource::Partition,
+            false => ColumnSource::File,
+        },
+    }).collect()
+}
+
+//Names `dtype` in the same way as datafusion backend does
+fn type_name(dtype: &pl::prelude::DataType) -> String {
+    use pl::prelude::DataType;
+
+    match dtype {
+        DataType::Float32 => "float32".to_owned(),
+        DataType::Float64 => "float64".to_owned(),
+        DataType::Binary | DataType::BinaryOffset => "binary".to_owned(),
+        DataType::Time => "time".to_owned(),
+        DataType::List(inner) => format!("list<{}>", type_name(inner)),
+        DataType::Struct(fields) => {
+            let fields = fields.iter().map(|field| format!("{}: {}", field.name(), type_name(field.dtype()))).collect::<Vec<_>>();
+            format!("struct<{}>", fields.join(", "))
+        },
+        dtype => match crate::polars::literal_type(dtype) {
+            Some(typ) => typ.to_string(),
+            None => dtype.to_string(),
+        },
+    }
+}
+
 impl fmt::Display for DataFrame<'_, pl::prelude::DataFrame> {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         let height = self.0.height();
@@ -52,40 +89,131 @@ impl fmt::Display for DataFrame<'_, pl::prelude::DataFrame> {
     }
 }
 
+///Converts polars value into [Cell]
+pub fn cell(value: pl::prelude::AnyValue<'_>) -> Cell {
+    use pl::prelude::{AnyValue, TimeUnit};
+    use crate::literal::{self, Value};
+
+    let unit = |unit: &TimeUnit| match unit {
+        TimeUnit::Nanoseconds => literal::TimeUnit::Nanosecond,
+        TimeUnit::Microseconds => literal::TimeUnit::Microsecond,
+        TimeUnit::Milliseconds => literal::TimeUnit::Millisecond,
+    };
+
+    let value = match value {
+        AnyValue::Null => return None,
+        AnyValue::Boolean(value) => Value::Boolean(value),
+        AnyValue::Int8(value) => Value::Int(value.into()),
+        AnyValue::Int16(value) => Value::Int(value.into()),
+        AnyValue::Int32(value) => Value::Int(value.into()),
+        AnyValue::Int64(value) => Value::Int(value),
+        AnyValue::UInt8(value) => Value::UInt(value.into()),
+        AnyValue::UInt16(value) => Value::UInt(value.into()),
+        AnyValue::UInt32(value) => Value::UInt(value.into()),
+        AnyValue::UInt64(value) => Value::UInt(value),
+        AnyValue::Float32(value) => Value::Float(value.into()),
+        AnyValue::Float64(value) => Value::Float(value),
+        AnyValue::Date(days) => Value::Date(days),
+        AnyValue::Datetime(value, time_unit, timezone) => Value::Timestamp {
+            value,
+            unit: unit(&time_unit),
+            timezone: timezone.map(|timezone| timezone.to_string()),
+        },
+        AnyValue::DatetimeOwned(value, time_unit, timezone) => Value::Timestamp {
+            value,
+            unit: unit(&time_unit),
+            timezone: timezone.map(|timezone| timezone.to_string()),
+        },
+        AnyValue::Decimal(value, scale) => Value::Decimal {
+            value,
+            precision: 38,
+            scale: scale as i8,
+        },
+        AnyValue::Time(nanoseconds) => return super::time(nanoseconds, 1_000_000_000),
+        AnyValue::String(value) => Value::String(value.to_owned()),
+        AnyValue::StringOwned(value) => Value::String(value.to_string()),
+        AnyValue::Binary(value) => return super::binary(value),
+        AnyValue::BinaryOwned(value) => return super::binary(&value),
+        //Nested and other types are printed as polars formats them
+        value => Value::String(value.to_string()),
+    };
+    Some(value)
+}
+
 ///[batch_function] state
 pub struct State {
-    header_done: AtomicBool,
-    row_count: AtomicUsize,
+    writer: Mutex<Option<RowWriter>>,
 }
 
 impl State {
     const fn new() -> Self {
         Self {
-            header_done: AtomicBool::new(false),
-            row_count: AtomicUsize::new(0),
+            writer: Mutex::new(None),
         }
     }
 
     #[inline]
     ///Returns row count
     pub fn row_count(&self) -> usize {
-        self.row_count.load(atomic::Ordering::Acquire)
+        match self.writer.lock() {
+            Ok(writer) => writer.as_ref().map_or(0, RowWriter::row_count),
+            Err(_) => 0,
+        }
+    }
+
+    ///Writes buffered rows, if output format requires it
+    pub fn finish(&self) -> io::Result<()> {
+        match self.writer.lock() {
+            Ok(mut writer) => match writer.as_mut() {
+                Some(writer) => writer.finish(&mut io::stdout().lock()),
+                None => Ok(()),
+            },
+            Err(_) => Ok(()),
+        }
     }
 }
 
+fn write_data_frame(writer: &mut Option<RowWriter>, format: OutputFormat, df: &pl::prelude::DataFrame) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    if writer.is_none() {
+        let columns = df.schema().iter_names().map(|name| name.to_string()).collect();
+        let mut new_writer = RowWriter::new(format, columns);
+        new_writer.write_header(&mut out)?;
+        *writer = Some(new_writer);
+    }
+    let writer = match writer.as_mut() {
+        Some(writer) => writer,
+        None => return Ok(()),
+    };
+
+    let columns = df.columns();
+    let mut row = Vec::with_capacity(columns.len());
+    for idx in 0..df.height() {
+        row.clear();
+        row.extend(columns.iter().map(|column| column.get(idx).ok().and_then(cell)));
+        writer.write_row(&mut out, &row)?;
+    }
+    Ok(())
+}
+
 ///Returns global state and formatting batch function
 ///
-///It prints every `DataFrame` in loosely CSV format
-pub fn batch_function() -> (&'static State, pl::prelude::PlanCallback<pl::prelude::DataFrame, bool>) {
+///It prints every `DataFrame` in specified `format`. Call [State::finish] after data is collected
+pub fn batch_function(format: OutputFormat) -> (&'static State, pl::prelude::PlanCallback<pl::prelude::DataFrame, bool>) {
     static STATE: State = State::new();
+    //State is shared, so discard writer of previous output
+    match STATE.writer.lock() {
+        Ok(mut writer) => *writer = None,
+        Err(error) => *error.into_inner() = None,
+    }
     (
         &STATE,
-        pl::prelude::PlanCallback::new(|df: pl::prelude::DataFrame| {
-            if STATE.header_done.compare_exchange(false, true, atomic::Ordering::AcqRel, atomic::Ordering::Relaxed).is_ok() {
-                println!("{}", Schema(df.schema().as_ref()))
-            }
-            STATE.row_count.fetch_add(df.height(), atomic::Ordering::AcqRel);
-            print!("{}", DataFrame(&df));
+        pl::prelude::PlanCallback::new(move |df: pl::prelude::DataFrame| {
+            let mut writer = match STATE.writer.lock() {
+                Ok(writer) => writer,
+                Err(error) => error.into_inner(),
+            };
+            write_data_frame(&mut writer, format, &df)?;
             Ok(false)
         }),
     )
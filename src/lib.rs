@@ -4,17 +4,38 @@
 #![allow(clippy::style)]
 
 const DUPLICATE_COLUMN: &str = "dup_count";
+//Temporary column holding pseudo random key of the row during sampling
+#[cfg(any(feature = "polars", feature = "datafusion"))]
+const SAMPLE_KEY_COLUMN: &str = "__mishka_sample_key";
+//Suffix of right side columns while comparing data by key
+#[cfg(any(feature = "polars", feature = "datafusion"))]
+const DIFF_RIGHT_SUFFIX: &str = "__mishka_right";
+//Suffix of columns marking changed values while comparing data by key
+#[cfg(any(feature = "polars", feature = "datafusion"))]
+const DIFF_CHANGED_SUFFIX: &str = "__mishka_changed";
+//Marks presence of row on either side while comparing data by key
+#[cfg(any(feature = "polars", feature = "datafusion"))]
+const DIFF_PRESENT_COLUMN: &str = "__mishka_present";
+//Mersenne prime 2^31-1, small enough to avoid overflow of i64 when squaring key
+#[cfg(any(feature = "polars", feature = "datafusion"))]
+const SAMPLE_MODULUS: i64 = 2_147_483_647;
 
 pub mod utils;
+pub mod expr;
+pub mod literal;
 #[cfg(feature = "cli")]
 pub mod cli;
 pub mod format;
+#[cfg(any(feature = "polars", feature = "datafusion"))]
+pub mod delta;
+#[cfg(any(feature = "polars", feature = "datafusion"))]
+pub mod parquet;
 #[cfg(feature = "polars")]
 pub mod polars;
 #[cfg(feature = "datafusion")]
 pub mod datafusion;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 ///User's input on file format
 pub enum ExpectFormat {
     ///No formaat. Default value
@@ -23,9 +44,30 @@ pub enum ExpectFormat {
     Csv,
     ///Expect Parquet
     Parquet,
+    ///Expect JSON array of objects
+    Json,
+    ///Expect newline delimited JSON (JSON lines)
+    NdJson,
+    ///Expect Arrow IPC file (Feather v2)
+    Ipc,
+    ///Expect Arrow IPC stream
+    IpcStream,
+    ///Expect Avro object container file
+    Avro,
+    ///Expect Delta Lake table
+    Delta,
 }
 
 impl ExpectFormat {
+    #[inline]
+    ///Returns file format specified by user, if any
+    pub fn select(&self) -> Option<crate::FileFormat> {
+        match self {
+            Self::Infer => None,
+            format => format.select_or_infer(""),
+        }
+    }
+
     ///Returns file format based on user's input
     ///
     ///If no specific file format is specified, attempts to infer from `path`
@@ -34,16 +76,34 @@ impl ExpectFormat {
     pub fn select_or_infer(&self, path: &str) -> Option<crate::FileFormat> {
         match self {
             Self::Infer => {
+                //Compression suffix is on top of format's extension (e.g. `events.csv.gz`)
+                let (path, _) = Compression::split_path(path);
                 if path.ends_with("parquet") {
                     Some(crate::FileFormat::Parquet)
                 } else if path.ends_with("csv") {
                     Some(crate::FileFormat::Csv)
+                } else if path.ends_with("ndjson") || path.ends_with("jsonl") {
+                    Some(crate::FileFormat::NdJson)
+                } else if path.ends_with("json") {
+                    Some(crate::FileFormat::Json)
+                } else if path.ends_with("arrow") || path.ends_with("feather") || path.ends_with("ipc") {
+                    Some(crate::FileFormat::Ipc)
+                } else if path.ends_with("arrows") {
+                    Some(crate::FileFormat::IpcStream)
+                } else if path.ends_with("avro") {
+                    Some(crate::FileFormat::Avro)
                 } else {
                     None
                 }
             }
             Self::Csv => Some(crate::FileFormat::Csv),
             Self::Parquet => Some(crate::FileFormat::Parquet),
+            Self::Json => Some(crate::FileFormat::Json),
+            Self::NdJson => Some(crate::FileFormat::NdJson),
+            Self::Ipc => Some(crate::FileFormat::Ipc),
+            Self::IpcStream => Some(crate::FileFormat::IpcStream),
+            Self::Avro => Some(crate::FileFormat::Avro),
+            Self::Delta => Some(crate::FileFormat::Delta),
         }
     }
 }
@@ -56,12 +116,203 @@ impl core::str::FromStr for ExpectFormat {
             Ok(Self::Csv)
         } else if text.eq_ignore_ascii_case("parquet") {
             Ok(Self::Parquet)
+        } else if text.eq_ignore_ascii_case("json") {
+            Ok(Self::Json)
+        } else if text.eq_ignore_ascii_case("ndjson") || text.eq_ignore_ascii_case("jsonl") {
+            Ok(Self::NdJson)
+        } else if text.eq_ignore_ascii_case("arrow") || text.eq_ignore_ascii_case("ipc") || text.eq_ignore_ascii_case("feather") {
+            Ok(Self::Ipc)
+        } else if text.eq_ignore_ascii_case("arrows") || text.eq_ignore_ascii_case("ipc_stream") {
+            Ok(Self::IpcStream)
+        } else if text.eq_ignore_ascii_case("avro") {
+            Ok(Self::Avro)
+        } else if text.eq_ignore_ascii_case("delta") {
+            Ok(Self::Delta)
+        } else {
+            Err("Invalid format. Allowed: 'csv', 'parquet', 'json', 'ndjson', 'arrow', 'arrows', 'avro' or 'delta'")
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+///Compression of the whole file
+pub enum Compression {
+    ///gzip
+    Gzip,
+    ///zstd
+    Zstd,
+    ///bzip2
+    Bzip2,
+    ///xz
+    Xz,
+}
+
+impl Compression {
+    ///Returns file extension
+    pub const fn extension(&self) -> &'static str {
+        match self {
+            Self::Gzip => "gz",
+            Self::Zstd => "zst",
+            Self::Bzip2 => "bz2",
+            Self::Xz => "xz",
+        }
+    }
+
+    ///Splits `path` into path without compression suffix and compression indicated by suffix
+    pub fn split_path(path: &str) -> (&str, Option<Self>) {
+        for compression in [Self::Gzip, Self::Zstd, Self::Bzip2, Self::Xz] {
+            if let Some(path) = path.strip_suffix(compression.extension()).and_then(|path| path.strip_suffix('.')) {
+                return (path, Some(compression));
+            }
+        }
+        (path, None)
+    }
+
+    ///Detects compression by magic bytes at the start of the file
+    pub fn from_magic(head: &[u8]) -> Option<Self> {
+        if head.starts_with(&[0x1F, 0x8B]) {
+            Some(Self::Gzip)
+        } else if head.starts_with(&[0x28, 0xB5, 0x2F, 0xFD]) {
+            Some(Self::Zstd)
+        } else if head.starts_with(b"BZh") {
+            Some(Self::Bzip2)
+        } else if head.starts_with(&[0xFD, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(Self::Xz)
+        } else {
+            None
+        }
+    }
+}
+
+impl core::str::FromStr for Compression {
+    type Err = &'static str;
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        if text.eq_ignore_ascii_case("gzip") || text.eq_ignore_ascii_case("gz") {
+            Ok(Self::Gzip)
+        } else if text.eq_ignore_ascii_case("zstd") || text.eq_ignore_ascii_case("zst") {
+            Ok(Self::Zstd)
+        } else if text.eq_ignore_ascii_case("bzip2") || text.eq_ignore_ascii_case("bz2") {
+            Ok(Self::Bzip2)
+        } else if text.eq_ignore_ascii_case("xz") {
+            Ok(Self::Xz)
         } else {
-            Err("Invalid format. Allowed: 'csv' or 'parquet'")
+            Err("Invalid compression. Allowed: 'gzip', 'zstd', 'bzip2' or 'xz'")
         }
     }
 }
 
+#[derive(Debug, Clone)]
+///CSV dialect used for reading and writing
+pub struct CsvOptions {
+    ///Field delimiter. Defaults to `,`
+    pub delimiter: u8,
+    ///Quote character. Defaults to `"`
+    pub quote: u8,
+    ///Escape character. By default quotes are escaped by doubling them
+    pub escape: Option<u8>,
+    ///Specifies whether first line is header. Defaults to `true`
+    pub has_header: bool,
+    ///String representing null value. By default empty field is null
+    pub null_value: Option<String>,
+    ///Prefix of comment lines to skip
+    pub comment: Option<u8>,
+    ///Number of lines to skip at the beginning of file (before header)
+    pub skip_rows: usize,
+    ///Specifies to truncate lines with more fields than in header instead of failing
+    pub truncate_ragged_lines: bool,
+}
+
+impl CsvOptions {
+    #[inline]
+    ///Creates default value
+    pub const fn new() -> Self {
+        Self {
+            delimiter: b',',
+            quote: b'"',
+            escape: None,
+            has_header: true,
+            null_value: None,
+            comment: None,
+            skip_rows: 0,
+            truncate_ragged_lines: false,
+        }
+    }
+}
+
+impl Default for CsvOptions {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+///Parquet compression codec with optional level
+pub enum ParquetCodec {
+    ///No compression
+    Uncompressed,
+    ///Snappy
+    Snappy,
+    ///Gzip with level 0-9
+    Gzip(Option<u8>),
+    ///LZ4 (raw)
+    Lz4,
+    ///Zstd with level 1-22
+    Zstd(Option<i32>),
+    ///Brotli with level 0-11
+    Brotli(Option<u32>),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+///Level of statistics to write into Parquet
+pub enum ParquetStatistics {
+    ///No statistics
+    None,
+    ///Statistics per column chunk
+    Chunk,
+    ///Statistics per column chunk and data page
+    Page,
+}
+
+#[derive(Debug, Clone)]
+///Parquet writer options
+pub struct ParquetWriteConfig {
+    ///Compression codec. Defaults to Snappy
+    pub codec: ParquetCodec,
+    ///Maximum number of rows in row group. Backend's default if not specified
+    pub row_group_size: Option<usize>,
+    ///Maximum size of data page in bytes. Backend's default if not specified
+    pub data_page_size: Option<usize>,
+    ///Specifies whether dictionary encoding is enabled. Defaults to `true`
+    pub dictionary: bool,
+    ///Level of statistics to write. Backend's default if not specified
+    pub statistics: Option<ParquetStatistics>,
+    ///Columns to write bloom filters for
+    pub bloom_filter_columns: Vec<String>,
+}
+
+impl ParquetWriteConfig {
+    #[inline]
+    ///Creates default value
+    pub const fn new() -> Self {
+        Self {
+            codec: ParquetCodec::Snappy,
+            row_group_size: None,
+            data_page_size: None,
+            dictionary: true,
+            statistics: None,
+            bloom_filter_columns: Vec::new(),
+        }
+    }
+}
+
+impl Default for ParquetWriteConfig {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 ///INT96 timestamp resolution to assume
 ///
@@ -115,16 +366,106 @@ pub enum FileFormat {
     Csv,
     ///Parquet (including compressed)
     Parquet,
+    ///JSON array of objects
+    Json,
+    ///Newline delimited JSON (JSON lines)
+    NdJson,
+    ///Arrow IPC file (Feather v2)
+    Ipc,
+    ///Arrow IPC stream
+    IpcStream,
+    ///Avro object container file
+    Avro,
+    ///Delta Lake table: parquet files listed by transaction log
+    Delta,
 }
 
 impl FileFormat {
+    #[inline]
+    ///Returns whether format can be compressed as whole file
+    pub const fn is_compressible(&self) -> bool {
+        matches!(self, Self::Csv | Self::Json | Self::NdJson)
+    }
+
     ///Returns file extension
     pub const fn extension(&self) -> &'static str {
         match self {
             Self::Csv => "csv",
             Self::Parquet => "parquet",
+            Self::Json => "json",
+            Self::NdJson => "ndjson",
+            Self::Ipc => "arrow",
+            Self::IpcStream => "arrows",
+            Self::Avro => "avro",
+            Self::Delta => "delta",
+        }
+    }
+
+    ///Detects format by magic bytes at the start of the uncompressed file
+    ///
+    ///Text formats have no magic, so only JSON is recognized by its leading character.
+    pub fn from_magic(head: &[u8]) -> Option<Self> {
+        if head.starts_with(b"PAR1") {
+            Some(Self::Parquet)
+        } else if head.starts_with(b"ARROW1") {
+            Some(Self::Ipc)
+        } else if head.starts_with(&[0xFF, 0xFF, 0xFF, 0xFF]) {
+            //Stream starts with continuation marker of the schema message
+            Some(Self::IpcStream)
+        } else if head.starts_with(b"Obj\x01") {
+            Some(Self::Avro)
+        } else {
+            match head.iter().find(|byte| !byte.is_ascii_whitespace()) {
+                Some(b'[') => Some(Self::Json),
+                Some(b'{') => Some(Self::NdJson),
+                _ => None,
+            }
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+///File format detected from the data file
+pub struct DetectedFormat {
+    ///Format of the file
+    pub format: FileFormat,
+    ///Compression of the whole file
+    pub compression: Option<Compression>,
+}
+
+impl DetectedFormat {
+    ///Number of leading bytes required by [DetectedFormat::detect]
+    pub const MAGIC_LEN: usize = 8;
+
+    ///Detects format of the file with `name` and leading bytes `head`
+    ///
+    ///Extension is used first, then magic bytes
+    pub fn detect(name: &str, head: &[u8]) -> Option<Self> {
+        let (name, compression) = Compression::split_path(name);
+        if let Some(format) = ExpectFormat::Infer.select_or_infer(name) {
+            return Some(Self {
+                format,
+                compression,
+            });
+        }
+
+        //Content of compressed file cannot be inspected without decompressing it
+        match compression.or_else(|| Compression::from_magic(head)) {
+            Some(_) => None,
+            None => FileFormat::from_magic(head).map(|format| Self {
+                format,
+                compression: None,
+            }),
         }
     }
+
+    #[inline]
+    ///Returns whether file with `name` should be skipped when looking for data file
+    ///
+    ///Such files are metadata or markers (e.g. `_SUCCESS`, `_delta_log`, `.crc`)
+    pub fn is_hidden(name: &str) -> bool {
+        name.starts_with('_') || name.starts_with('.')
+    }
 }
 
 ///Unique scan
@@ -135,24 +476,159 @@ pub struct Unique<CI: ExactSizeIterator<Item = String>> {
     pub is_stable: bool,
 }
 
+///Group by aggregation
+pub struct GroupBy<GCI: ExactSizeIterator<Item = String>, AI: ExactSizeIterator<Item = expr::Aggregate>> {
+    ///Iterator of columns to group by. If empty, aggregates whole data
+    pub columns: GCI,
+    ///Iterator of aggregations to compute per group
+    pub aggregates: AI,
+}
+
+#[derive(Copy, Clone, Debug)]
+///Size of the sample
+pub enum SampleSize {
+    ///Takes every row with specified probability
+    Fraction(f64),
+    ///Takes at most N rows (per group if grouped)
+    Count(usize),
+}
+
+///Random sampling of rows
+pub struct Sample<SCI: ExactSizeIterator<Item = String>> {
+    ///Size of the sample
+    pub size: SampleSize,
+    ///Seed of pseudo random generator. Same seed produces same sample of the same data
+    pub seed: u64,
+    ///Iterator of columns to stratify sample by. If not empty, [SampleSize::Count] is taken per group
+    pub by: SCI,
+}
+
+#[cfg(any(feature = "polars", feature = "datafusion"))]
+///Returns next value of SplitMix64 generator, used to derive sampling constants from seed
+fn split_mix(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut value = *state;
+    value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    value ^ (value >> 31)
+}
+
+#[cfg(any(feature = "polars", feature = "datafusion"))]
+///Builds expression computing pseudo random key in range `0..SAMPLE_MODULUS` from row number
+///
+///Every round is permutation of integers modulo `SAMPLE_MODULUS` with constants derived from `seed`,
+///so distinct rows within block of `SAMPLE_MODULUS` rows get distinct keys and different seeds produce unrelated orders.
+///Number of the block is permuted separately and offsets rows within block, so that blocks are not sampled alike.
+///
+///Only uses operations on non-negative `i64` that cannot overflow so that backends produce the same key
+fn sample_key<E: core::ops::Add<Output = E> + core::ops::Mul<Output = E> + core::ops::Div<Output = E> + core::ops::Rem<Output = E> + Clone>(row: E, seed: u64, lit: impl Fn(i64) -> E) -> E {
+    const ROUNDS: usize = 3;
+    let modulus = SAMPLE_MODULUS as u64;
+
+    let mut state = seed;
+    let mut permute = |mut key: E| {
+        for _ in 0..ROUNDS {
+            let multiplier = 1 + split_mix(&mut state) % (modulus - 1);
+            let increment = split_mix(&mut state) % modulus;
+            //Affine step mixes in seed, while x^5 is non-linear and still bijective as 5 does not divide `SAMPLE_MODULUS - 1`
+            key = (key * lit(multiplier as i64) + lit(increment as i64)) % lit(SAMPLE_MODULUS);
+            let square = (key.clone() * key.clone()) % lit(SAMPLE_MODULUS);
+            let fourth = (square.clone() * square) % lit(SAMPLE_MODULUS);
+            key = (fourth * key) % lit(SAMPLE_MODULUS);
+        }
+        key
+    };
+
+    let block = permute(row.clone() / lit(SAMPLE_MODULUS) % lit(SAMPLE_MODULUS));
+    permute((row % lit(SAMPLE_MODULUS) + block) % lit(SAMPLE_MODULUS))
+}
+
+#[cfg(any(feature = "polars", feature = "datafusion"))]
+///Returns upper bound of sample key for sampling `fraction` of rows
+fn sample_threshold(fraction: f64) -> i64 {
+    (fraction.clamp(0.0, 1.0) * SAMPLE_MODULUS as f64) as i64
+}
+
+///Range of rows to output
+pub enum Slice {
+    ///Skips `offset` rows and takes at most `limit` rows
+    Head {
+        ///Number of rows to skip
+        offset: usize,
+        ///Maximum number of rows to take. Unlimited if `None`
+        limit: Option<usize>,
+    },
+    ///Takes last N rows
+    Tail(usize),
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+///Version of Delta table to read
+pub enum DeltaVersion {
+    #[default]
+    ///Latest version
+    Latest,
+    ///Specific version
+    Version(u64),
+    ///Latest version committed at or before timestamp (milliseconds since UNIX epoch)
+    Timestamp(i64),
+}
+
+#[derive(Debug, Clone)]
+///User supplied type of the column
+pub struct ColumnSchema {
+    ///Column name
+    pub name: String,
+    ///Type to read column as
+    pub dtype: literal::LiteralType,
+    ///Specifies whether column may contain nulls. If `false`, reading fails on null
+    pub nullable: bool,
+}
+
 ///Sort by expression
 pub struct SortBy {
     ///Field to sort by
     pub column: String,
     ///Indicates whether to sort in descending order or not
     pub desc: bool,
+    ///Indicates whether nulls are placed before values or after
+    pub nulls_first: bool,
 }
 
 ///Filters to query data
-pub struct Query<CI: ExactSizeIterator<Item = String>, SBI: ExactSizeIterator<Item = SortBy>, UCI: ExactSizeIterator<Item = String>, WHERE: ExactSizeIterator<Item = cli::Expression>> {
+pub struct Query<CI: ExactSizeIterator<Item = String>, SBI: ExactSizeIterator<Item = SortBy>, UCI: ExactSizeIterator<Item = String>, WHERE: ExactSizeIterator<Item = expr::Expression>, WC: ExactSizeIterator<Item = expr::ComputedColumn>, GCI: ExactSizeIterator<Item = String>, AI: ExactSizeIterator<Item = expr::Aggregate>, SCI: ExactSizeIterator<Item = String>> {
     ///Iterator of columns to select
     pub column: CI,
     ///Iterator over [SortBy]
     pub sort_by: SBI,
+    ///Specifies to keep original order of rows that are equal according to `sort_by`
+    pub stable_sort: bool,
     ///If specified request deduplication data frames
     pub unique: Option<Unique<UCI>>,
+    ///Computed columns to add to data.
+    ///
+    ///Applied before anything else, so computed columns can be used everywhere else
+    pub with_column: WC,
     ///Filtering expressions to use when selecting data.
+    ///
+    ///Applied before aggregation
     pub filter: WHERE,
+    ///If specified takes random sample of rows.
+    ///
+    ///Applied after filtering and before aggregation
+    pub sample: Option<Sample<SCI>>,
+    ///If specified request aggregation of data.
+    ///
+    ///Applied after filtering and before selecting columns
+    pub group_by: Option<GroupBy<GCI, AI>>,
+    ///Filtering expressions to apply after aggregation
+    pub having: WHERE,
+    ///If specified limits number of rows in output
+    ///
+    ///Applied last
+    pub slice: Option<Slice>,
+    ///CSV dialect to read input
+    pub csv: CsvOptions,
     ///Specifies coercing method for int96.
     ///
     ///Prefer default value unless you know what you're doing
@@ -161,9 +637,19 @@ pub struct Query<CI: ExactSizeIterator<Item = String>, SBI: ExactSizeIterator<It
     pub keep_partition: bool,
     ///Specify to count duplicate records under column `dup_count`
     pub count_duplicates: bool,
+    ///Compression of input files.
+    ///
+    ///If `None`, it is inferred from path
+    pub compression: Option<Compression>,
+    ///Types of columns overriding inferred schema.
+    ///
+    ///Columns not listed keep inferred type
+    pub schema: Vec<ColumnSchema>,
+    ///Version of Delta table to read
+    pub delta_version: DeltaVersion,
 }
 
-impl<CI: ExactSizeIterator<Item = String>, SBI: ExactSizeIterator<Item = SortBy>, UCI: ExactSizeIterator<Item = String>, WHERE: ExactSizeIterator<Item = cli::Expression>> Query<CI, SBI, UCI, WHERE> {
+impl<CI: ExactSizeIterator<Item = String>, SBI: ExactSizeIterator<Item = SortBy>, UCI: ExactSizeIterator<Item = String>, WHERE: ExactSizeIterator<Item = expr::Expression>, WC: ExactSizeIterator<Item = expr::ComputedColumn>, GCI: ExactSizeIterator<Item = String>, AI: ExactSizeIterator<Item = expr::Aggregate>, SCI: ExactSizeIterator<Item = String>> Query<CI, SBI, UCI, WHERE, WC, GCI, AI, SCI> {
     #[inline]
     ///Modifies whether to keep partitions or not
     ///
@@ -172,4 +658,32 @@ impl<CI: ExactSizeIterator<Item = String>, SBI: ExactSizeIterator<Item = SortBy>
         self.keep_partition = keep_partition;
         self
     }
+
+    #[inline]
+    ///Specifies compression of input files, overriding inference from path
+    pub fn with_compression(mut self, compression: Option<Compression>) -> Self {
+        self.compression = compression;
+        self
+    }
+}
+
+#[cfg(all(test, any(feature = "polars", feature = "datafusion")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_compute_distinct_sample_keys() {
+        let key = |row: i64| sample_key(row, 42, |value| value);
+        let mut keys = (0..1000).map(key).collect::<Vec<_>>();
+        assert!(keys.iter().all(|key| (0..SAMPLE_MODULUS).contains(key)));
+        keys.sort_unstable();
+        keys.dedup();
+        assert_eq!(keys.len(), 1000);
+
+        //Rows of different blocks are not sampled alike
+        assert_ne!(key(5), key(SAMPLE_MODULUS + 5));
+        assert_ne!(key(SAMPLE_MODULUS + 5), key(2 * SAMPLE_MODULUS + 5));
+        assert!((0..SAMPLE_MODULUS).contains(&key(i64::MAX)));
+        assert_ne!(sample_key(5, 1, |value| value), key(5));
+    }
 }
@@ -0,0 +1,657 @@
+//! Typed literals
+//!
+//! Literals in expressions are written as text and cast to the type of the column they are compared against.
+//! Parsing is shared between backends to make sure both interpret literal identically.
+
+use core::fmt;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+///Time unit of timestamp
+pub enum TimeUnit {
+    ///Second
+    Second,
+    ///Millisecond
+    Millisecond,
+    ///Microsecond
+    Microsecond,
+    ///Nanosecond
+    Nanosecond,
+}
+
+impl TimeUnit {
+    #[inline]
+    ///Returns number of units in a second
+    pub const fn per_second(&self) -> i64 {
+        match self {
+            Self::Second => 1,
+            Self::Millisecond => 1_000,
+            Self::Microsecond => 1_000_000,
+            Self::Nanosecond => 1_000_000_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+///Column type literal should be cast to
+pub enum LiteralType {
+    ///Boolean
+    Boolean,
+    ///Signed integer with specified number of bits
+    Int(u8),
+    ///Unsigned integer with specified number of bits
+    UInt(u8),
+    ///Floating point with specified number of bits
+    Float(u8),
+    ///Decimal
+    Decimal {
+        ///Maximum number of digits
+        precision: u8,
+        ///Number of fractional digits
+        scale: i8,
+    },
+    ///Number of days since UNIX epoch
+    Date,
+    ///Timestamp since UNIX epoch
+    Timestamp {
+        ///Time unit
+        unit: TimeUnit,
+        ///Optional timezone of the column
+        timezone: Option<String>,
+    },
+    ///Text
+    String,
+}
+
+impl fmt::Display for LiteralType {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Boolean => fmt.write_str("boolean"),
+            Self::Int(bits) => fmt.write_fmt(format_args!("int{bits}")),
+            Self::UInt(bits) => fmt.write_fmt(format_args!("uint{bits}")),
+            Self::Float(bits) => fmt.write_fmt(format_args!("float{bits}")),
+            Self::Decimal { precision, scale } => fmt.write_fmt(format_args!("decimal({precision}, {scale})")),
+            Self::Date => fmt.write_str("date"),
+            Self::Timestamp { unit, timezone: Some(timezone) } => fmt.write_fmt(format_args!("timestamp({unit:?}, {timezone})")),
+            Self::Timestamp { unit, timezone: None } => fmt.write_fmt(format_args!("timestamp({unit:?})")),
+            Self::String => fmt.write_str("string"),
+        }
+    }
+}
+
+impl core::str::FromStr for TimeUnit {
+    type Err = &'static str;
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        if text.eq_ignore_ascii_case("s") || text.eq_ignore_ascii_case("second") {
+            Ok(Self::Second)
+        } else if text.eq_ignore_ascii_case("ms") || text.eq_ignore_ascii_case("millisecond") {
+            Ok(Self::Millisecond)
+        } else if text.eq_ignore_ascii_case("us") || text.eq_ignore_ascii_case("microsecond") {
+            Ok(Self::Microsecond)
+        } else if text.eq_ignore_ascii_case("ns") || text.eq_ignore_ascii_case("nanosecond") {
+            Ok(Self::Nanosecond)
+        } else {
+            Err("Invalid time unit. Allowed: s, ms, us, ns")
+        }
+    }
+}
+
+impl core::str::FromStr for LiteralType {
+    type Err = &'static str;
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        const INVALID: &str = "Invalid type. Allowed: boolean, int8-int64, uint8-uint64, float32, float64, decimal(precision, scale), date, timestamp(unit[, timezone]), string";
+
+        let text = text.trim();
+        let (name, args) = match text.split_once('(') {
+            Some((name, args)) => match args.strip_suffix(')') {
+                Some(args) => (name.trim(), Some(args)),
+                None => return Err(INVALID),
+            },
+            None => (text, None),
+        };
+        let name = name.to_ascii_lowercase();
+
+        let typ = match (name.as_str(), args) {
+            ("bool" | "boolean", None) => Self::Boolean,
+            ("int8", None) => Self::Int(8),
+            ("int16", None) => Self::Int(16),
+            ("int32", None) => Self::Int(32),
+            ("int" | "int64", None) => Self::Int(64),
+            ("uint8", None) => Self::UInt(8),
+            ("uint16", None) => Self::UInt(16),
+            ("uint32", None) => Self::UInt(32),
+            ("uint" | "uint64", None) => Self::UInt(64),
+            ("float32", None) => Self::Float(32),
+            ("float" | "float64" | "double", None) => Self::Float(64),
+            ("decimal", Some(args)) => {
+                let (precision, scale) = match args.split_once(',') {
+                    Some((precision, scale)) => (precision, scale),
+                    None => (args, "0"),
+                };
+                match (precision.trim().parse(), scale.trim().parse()) {
+                    (Ok(precision @ 1..=38), Ok(scale)) => Self::Decimal { precision, scale },
+                    _ => return Err("Invalid decimal. Expected decimal(precision, scale) with precision 1..=38"),
+                }
+            },
+            ("date", None) => Self::Date,
+            ("timestamp" | "datetime", None) => Self::Timestamp {
+                unit: TimeUnit::Microsecond,
+                timezone: None,
+            },
+            ("timestamp" | "datetime", Some(args)) => {
+                let (unit, timezone) = match args.split_once(',') {
+                    Some((unit, timezone)) => (unit, Some(timezone.trim().to_owned())),
+                    None => (args, None),
+                };
+                Self::Timestamp {
+                    unit: unit.trim().parse()?,
+                    timezone,
+                }
+            },
+            ("string" | "str" | "utf8" | "text", None) => Self::String,
+            _ => return Err(INVALID),
+        };
+        Ok(typ)
+    }
+}
+
+#[derive(Debug)]
+///Literal cast error
+pub struct CastError {
+    text: String,
+    typ: LiteralType,
+    reason: &'static str,
+}
+
+impl fmt::Display for CastError {
+    #[inline(always)]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_fmt(format_args!("Cannot cast '{}' to {}: {}", self.text, self.typ, self.reason))
+    }
+}
+
+impl std::error::Error for CastError {}
+
+#[derive(Debug, Clone)]
+///Literal value cast to column type
+pub enum Value {
+    ///Boolean
+    Boolean(bool),
+    ///Signed integer
+    Int(i64),
+    ///Unsigned integer
+    UInt(u64),
+    ///Floating point
+    Float(f64),
+    ///Decimal
+    Decimal {
+        ///Unscaled value
+        value: i128,
+        ///Maximum number of digits
+        precision: u8,
+        ///Number of fractional digits
+        scale: i8,
+    },
+    ///Number of days since UNIX epoch
+    Date(i32),
+    ///Timestamp in UTC
+    Timestamp {
+        ///Number of units since UNIX epoch
+        value: i64,
+        ///Time unit
+        unit: TimeUnit,
+        ///Timezone of the column
+        timezone: Option<String>,
+    },
+    ///Text
+    String(String),
+}
+
+impl Value {
+    ///Parses numeric literal without known type
+    ///
+    ///Integer is preferred, falling back to float and then to text
+    pub fn from_number(text: &str) -> Self {
+        if let Ok(value) = text.parse::<i64>() {
+            Self::Int(value)
+        } else if let Ok(value) = text.parse::<f64>() {
+            Self::Float(value)
+        } else {
+            Self::String(text.to_owned())
+        }
+    }
+
+    ///Parses `text` as literal of specified type
+    pub fn parse(text: &str, typ: &LiteralType) -> Result<Self, CastError> {
+        let error = |reason| CastError {
+            text: text.to_owned(),
+            typ: typ.clone(),
+            reason,
+        };
+
+        match typ {
+            LiteralType::Boolean => {
+                if text.eq_ignore_ascii_case("true") || text == "1" {
+                    Ok(Self::Boolean(true))
+                } else if text.eq_ignore_ascii_case("false") || text == "0" {
+                    Ok(Self::Boolean(false))
+                } else {
+                    Err(error("expected true or false"))
+                }
+            },
+            LiteralType::Int(bits) => {
+                let value = text.parse::<i64>().map_err(|_| error("not an integer"))?;
+                let max = i64::MAX >> (64 - *bits as u32).min(63);
+                if value > max || value < -max - 1 {
+                    Err(error("out of range"))
+                } else {
+                    Ok(Self::Int(value))
+                }
+            },
+            LiteralType::UInt(bits) => {
+                let value = text.parse::<u64>().map_err(|_| error("not an unsigned integer"))?;
+                let max = u64::MAX >> (64 - *bits as u32).min(63);
+                if value > max {
+                    Err(error("out of range"))
+                } else {
+                    Ok(Self::UInt(value))
+                }
+            },
+            LiteralType::Float(_) => match text.parse::<f64>() {
+                Ok(value) => Ok(Self::Float(value)),
+                Err(_) => Err(error("not a number")),
+            },
+            LiteralType::Decimal { precision, scale } => match parse_decimal(text, *precision, *scale) {
+                Ok(value) => Ok(Self::Decimal {
+                    value,
+                    precision: *precision,
+                    scale: *scale,
+                }),
+                Err(reason) => Err(error(reason)),
+            },
+            LiteralType::Date => match parse_date(text) {
+                Some(days) => match i32::try_from(days) {
+                    Ok(days) => Ok(Self::Date(days)),
+                    Err(_) => Err(error("out of range")),
+                },
+                None => Err(error("expected YYYY-MM-DD")),
+            },
+            LiteralType::Timestamp { unit, timezone } => match parse_timestamp(text, *unit, timezone.as_deref()) {
+                Ok(value) => Ok(Self::Timestamp {
+                    value,
+                    unit: *unit,
+                    timezone: timezone.clone(),
+                }),
+                Err(reason) => Err(error(reason)),
+            },
+            LiteralType::String => Ok(Self::String(text.to_owned())),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Boolean(value) => fmt::Display::fmt(value, fmt),
+            Self::Int(value) => fmt::Display::fmt(value, fmt),
+            Self::UInt(value) => fmt::Display::fmt(value, fmt),
+            Self::Float(value) => fmt::Display::fmt(value, fmt),
+            Self::Decimal { value, scale, .. } => {
+                if *scale <= 0 {
+                    return fmt::Display::fmt(value, fmt);
+                }
+                let scale = *scale as usize;
+                let digits = value.unsigned_abs().to_string();
+                let digits = format!("{digits:0>width$}", width = scale + 1);
+                let (int, frac) = digits.split_at(digits.len() - scale);
+                if *value < 0 {
+                    fmt.write_str("-")?;
+                }
+                fmt.write_fmt(format_args!("{int}.{frac}"))
+            },
+            Self::Date(days) => {
+                let (year, month, day) = civil_from_days(*days as i64);
+                fmt.write_fmt(format_args!("{year:04}-{month:02}-{day:02}"))
+            },
+            Self::Timestamp { value, unit, timezone } => {
+                let per_second = unit.per_second();
+                let seconds = value.div_euclid(per_second);
+                let fraction = value.rem_euclid(per_second);
+                let (year, month, day) = civil_from_days(seconds.div_euclid(86_400));
+                let time = seconds.rem_euclid(86_400);
+                fmt.write_fmt(format_args!("{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}", time / 3600, time % 3600 / 60, time % 60))?;
+                if fraction != 0 {
+                    let width = per_second.ilog10() as usize;
+                    fmt.write_fmt(format_args!(".{fraction:0width$}"))?;
+                }
+                //Value is always in UTC, so mark it explicitly when column has timezone
+                if timezone.is_some() {
+                    fmt.write_str("Z")?;
+                }
+                Ok(())
+            },
+            Self::String(value) => fmt.write_str(value),
+        }
+    }
+}
+
+fn parse_decimal(text: &str, precision: u8, scale: i8) -> Result<i128, &'static str> {
+    if scale < 0 {
+        return Err("negative scale is not supported");
+    }
+    let scale = scale as usize;
+
+    let (is_negative, text) = match text.strip_prefix('-') {
+        Some(text) => (true, text),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let (int, frac) = text.split_once('.').unwrap_or((text, ""));
+    if int.is_empty() && frac.is_empty() {
+        return Err("not a decimal");
+    }
+    if !int.bytes().chain(frac.bytes()).all(|byte| byte.is_ascii_digit()) {
+        return Err("not a decimal");
+    }
+
+    let frac = frac.trim_end_matches('0');
+    if frac.len() > scale {
+        return Err("too many fractional digits");
+    }
+    let int = int.trim_start_matches('0');
+    if int.len() + scale > precision as usize {
+        return Err("exceeds precision");
+    }
+
+    //Precision is not limited by type itself, so guard against overflow of i128
+    let mut value = 0i128;
+    for byte in int.bytes().chain(frac.bytes()) {
+        value = value.checked_mul(10).and_then(|value| value.checked_add((byte - b'0') as i128)).ok_or("exceeds precision")?;
+    }
+    for _ in frac.len()..scale {
+        value = value.checked_mul(10).ok_or("exceeds precision")?;
+    }
+
+    if is_negative {
+        Ok(-value)
+    } else {
+        Ok(value)
+    }
+}
+
+//Days since UNIX epoch using proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+//Year, month and day from days since UNIX epoch using proleptic Gregorian calendar
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = if days >= 0 { days } else { days - 146096 } / 146097;
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month + 2) / 5 + 1) as u32;
+    let month = if month < 10 { month + 3 } else { month - 9 } as u32;
+    let year = year_of_era + era * 400;
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+const fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+fn parse_number<T: core::str::FromStr>(text: &str, len: usize) -> Option<T> {
+    if text.len() == len && text.bytes().all(|byte| byte.is_ascii_digit()) {
+        text.parse().ok()
+    } else {
+        None
+    }
+}
+
+//Parses YYYY-MM-DD returning number of days since UNIX epoch
+fn parse_date(text: &str) -> Option<i64> {
+    let mut parts = text.splitn(3, '-');
+    let year = parse_number::<i64>(parts.next()?, 4)?;
+    let month = parse_number::<u32>(parts.next()?, 2)?;
+    let day = parse_number::<u32>(parts.next()?, 2)?;
+
+    if month == 0 || month > 12 || day == 0 || day > days_in_month(year, month) {
+        return None;
+    }
+
+    Some(days_from_civil(year, month, day))
+}
+
+//Parses UTC offset in format Z, +HH, +HH:MM or +HHMM returning offset in seconds
+fn parse_offset(text: &str) -> Option<i64> {
+    if text == "Z" || text.eq_ignore_ascii_case("UTC") || text.eq_ignore_ascii_case("Etc/UTC") {
+        return Some(0);
+    }
+
+    let (sign, text) = if let Some(text) = text.strip_prefix('+') {
+        (1, text)
+    } else if let Some(text) = text.strip_prefix('-') {
+        (-1, text)
+    } else {
+        return None;
+    };
+    let (hours, minutes) = match text.len() {
+        2 => (text, "00"),
+        4 => text.split_at(2),
+        5 if text.as_bytes()[2] == b':' => (&text[..2], &text[3..]),
+        _ => return None,
+    };
+    let hours = parse_number::<i64>(hours, 2)?;
+    let minutes = parse_number::<i64>(minutes, 2)?;
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+//Parses `YYYY-MM-DD[( |T)HH:MM[:SS[.fraction]]][offset]` returning number of `unit` since UNIX epoch
+fn parse_timestamp(text: &str, unit: TimeUnit, timezone: Option<&str>) -> Result<i64, &'static str> {
+    const FORMAT: &str = "expected YYYY-MM-DD[THH:MM[:SS[.fraction]]][Z|+HH:MM]";
+
+    let (date, time) = match text.split_once(['T', ' ']) {
+        Some((date, time)) => (date, time),
+        None => (text, ""),
+    };
+    let days = parse_date(date).ok_or(FORMAT)?;
+
+    let (time, offset) = match time.find(['Z', '+', '-']) {
+        Some(idx) => (&time[..idx], Some(parse_offset(&time[idx..]).ok_or(FORMAT)?)),
+        None => (time, None),
+    };
+
+    let mut seconds = days * 86400;
+    let mut fraction = "";
+    if !time.is_empty() {
+        let mut parts = time.splitn(3, ':');
+        let hours = parse_number::<i64>(parts.next().ok_or(FORMAT)?, 2).ok_or(FORMAT)?;
+        let minutes = parse_number::<i64>(parts.next().ok_or(FORMAT)?, 2).ok_or(FORMAT)?;
+        let secs = match parts.next() {
+            Some(secs) => {
+                let (secs, frac) = secs.split_once('.').unwrap_or((secs, ""));
+                fraction = frac;
+                parse_number::<i64>(secs, 2).ok_or(FORMAT)?
+            },
+            None => 0,
+        };
+        if hours > 23 || minutes > 59 || secs > 59 {
+            return Err(FORMAT);
+        }
+        seconds += hours * 3600 + minutes * 60 + secs;
+    }
+
+    //Literal without explicit offset is assumed to be in column's timezone
+    let offset = match (offset, timezone) {
+        (Some(offset), _) => offset,
+        (None, None) => 0,
+        (None, Some(timezone)) => parse_offset(timezone).ok_or("timezone offset must be specified explicitly for column with named timezone")?,
+    };
+    seconds -= offset;
+
+    let per_second = unit.per_second();
+    let digits = per_second.ilog10() as usize;
+    if fraction.len() > digits || !fraction.bytes().all(|byte| byte.is_ascii_digit()) {
+        return Err("fraction exceeds time unit precision");
+    }
+    let mut fraction_value = 0i64;
+    for byte in fraction.bytes() {
+        fraction_value = fraction_value * 10 + (byte - b'0') as i64;
+    }
+    for _ in fraction.len()..digits {
+        fraction_value *= 10;
+    }
+
+    seconds.checked_mul(per_second).and_then(|value| value.checked_add(fraction_value)).ok_or("out of range")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str, typ: &str) -> Result<Value, CastError> {
+        Value::parse(text, &typ.parse().expect("valid type"))
+    }
+
+    #[test]
+    fn should_parse_literal_type() {
+        assert!(matches!("INT16".parse(), Ok(LiteralType::Int(16))));
+        assert!(matches!("decimal(10, 2)".parse(), Ok(LiteralType::Decimal { precision: 10, scale: 2 })));
+        assert!(matches!("decimal(5)".parse(), Ok(LiteralType::Decimal { precision: 5, scale: 0 })));
+        assert!(matches!("timestamp".parse(), Ok(LiteralType::Timestamp { unit: TimeUnit::Microsecond, timezone: None })));
+        assert!(matches!("timestamp(ms, +02:00)".parse(), Ok(LiteralType::Timestamp { unit: TimeUnit::Millisecond, timezone: Some(ref timezone) }) if timezone == "+02:00"));
+
+        assert!("decimal(39, 2)".parse::<LiteralType>().is_err());
+        assert!("decimal(0, 0)".parse::<LiteralType>().is_err());
+        assert!("decimal(10, 2".parse::<LiteralType>().is_err());
+        assert!("timestamp(minute)".parse::<LiteralType>().is_err());
+        assert!("int128".parse::<LiteralType>().is_err());
+    }
+
+    #[test]
+    fn should_parse_integer_within_range() {
+        assert!(matches!(parse("-128", "int8"), Ok(Value::Int(-128))));
+        assert!(parse("128", "int8").is_err());
+        assert!(matches!(parse("255", "uint8"), Ok(Value::UInt(255))));
+        assert!(parse("256", "uint8").is_err());
+        assert!(parse("-1", "uint64").is_err());
+        assert!(matches!(parse("18446744073709551615", "uint64"), Ok(Value::UInt(u64::MAX))));
+        assert!(matches!(parse("1", "bool"), Ok(Value::Boolean(true))));
+        assert!(parse("yes", "bool").is_err());
+    }
+
+    #[test]
+    fn should_parse_decimal() {
+        let decimal = |text, typ| match parse(text, typ) {
+            Ok(Value::Decimal { value, .. }) => Ok(value),
+            Ok(value) => panic!("Expected decimal, got {value:?}"),
+            Err(error) => Err(error.reason),
+        };
+
+        assert_eq!(decimal("12.5", "decimal(5, 2)"), Ok(1250));
+        assert_eq!(decimal("-0.01", "decimal(5, 2)"), Ok(-1));
+        assert_eq!(decimal("+7", "decimal(5, 2)"), Ok(700));
+        assert_eq!(decimal(".5", "decimal(5, 2)"), Ok(50));
+        assert_eq!(decimal("007.500", "decimal(3, 2)"), Ok(750));
+        assert_eq!(decimal("99999999999999999999999999999999999999", "decimal(38, 0)"), Ok(99_999_999_999_999_999_999_999_999_999_999_999_999));
+
+        assert_eq!(decimal("1.234", "decimal(5, 2)"), Err("too many fractional digits"));
+        assert_eq!(decimal("1000", "decimal(5, 2)"), Err("exceeds precision"));
+        assert_eq!(decimal(".", "decimal(5, 2)"), Err("not a decimal"));
+        assert_eq!(decimal("1e3", "decimal(5, 2)"), Err("not a decimal"));
+        assert_eq!(decimal("--1", "decimal(5, 2)"), Err("not a decimal"));
+
+        //Type inferred from data may exceed precision of i128
+        let wide = LiteralType::Decimal { precision: 76, scale: 0 };
+        assert!(matches!(Value::parse(&"9".repeat(40), &wide), Err(CastError { reason: "exceeds precision", .. })));
+        let wide = LiteralType::Decimal { precision: 76, scale: 50 };
+        assert!(matches!(Value::parse("1", &wide), Err(CastError { reason: "exceeds precision", .. })));
+    }
+
+    #[test]
+    fn should_format_decimal() {
+        let format = |value, scale| Value::Decimal { value, precision: 38, scale }.to_string();
+        assert_eq!(format(1250, 2), "12.50");
+        assert_eq!(format(-1, 2), "-0.01");
+        assert_eq!(format(5, 0), "5");
+        assert_eq!(format(-5, 3), "-0.005");
+    }
+
+    #[test]
+    fn should_convert_civil_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(-719_468), (0, 3, 1));
+        assert_eq!(civil_from_days(2_932_896), (9999, 12, 31));
+
+        //Round trip over several 400 year cycles, including negative years
+        for days in (-800_000..800_000).step_by(97) {
+            let (year, month, day) = civil_from_days(days);
+            assert!((1..=12).contains(&month));
+            assert!(day >= 1 && day <= days_in_month(year, month));
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+    }
+
+    #[test]
+    fn should_parse_date() {
+        assert!(matches!(parse("1970-01-01", "date"), Ok(Value::Date(0))));
+        assert!(matches!(parse("2000-02-29", "date"), Ok(Value::Date(11_016))));
+        assert!(matches!(parse("1969-12-31", "date"), Ok(Value::Date(-1))));
+        assert!(parse("1900-02-29", "date").is_err());
+        assert!(parse("2023-13-01", "date").is_err());
+        assert!(parse("2023-1-01", "date").is_err());
+        assert!(parse("2023-01-01T00:00", "date").is_err());
+
+        assert_eq!(Value::Date(11_016).to_string(), "2000-02-29");
+        assert_eq!(Value::Date(-1).to_string(), "1969-12-31");
+    }
+
+    #[test]
+    fn should_parse_timestamp() {
+        let timestamp = |text, typ| match parse(text, typ) {
+            Ok(Value::Timestamp { value, .. }) => Ok(value),
+            Ok(value) => panic!("Expected timestamp, got {value:?}"),
+            Err(error) => Err(error.reason),
+        };
+
+        assert_eq!(timestamp("1970-01-02", "timestamp(s)"), Ok(86_400));
+        assert_eq!(timestamp("1970-01-01T00:00:01.5", "timestamp(ms)"), Ok(1_500));
+        assert_eq!(timestamp("1970-01-01 01:00", "timestamp(s)"), Ok(3_600));
+        assert_eq!(timestamp("1970-01-01T01:00:00+01:00", "timestamp(s)"), Ok(0));
+        assert_eq!(timestamp("1970-01-01T00:00:00-0130", "timestamp(s)"), Ok(5_400));
+        assert_eq!(timestamp("1969-12-31T23:59:59.999999", "timestamp(us)"), Ok(-1));
+        //Literal without offset is in column's timezone
+        assert_eq!(timestamp("1970-01-01T02:00:00", "timestamp(s, +02:00)"), Ok(0));
+        assert_eq!(timestamp("1970-01-01T02:00:00Z", "timestamp(s, +02:00)"), Ok(7_200));
+
+        assert!(timestamp("1970-01-01T00:00:00", "timestamp(s, Europe/Berlin)").is_err());
+        assert_eq!(timestamp("1970-01-01T00:00:00.5", "timestamp(s)"), Err("fraction exceeds time unit precision"));
+        assert!(timestamp("1970-01-01T24:00", "timestamp(s)").is_err());
+        assert!(timestamp("1970-01-01T00:00+25:00", "timestamp(s)").is_err());
+        assert_eq!(timestamp("9999-12-31", "timestamp(ns)"), Err("out of range"));
+    }
+
+    #[test]
+    fn should_format_timestamp() {
+        let format = |value, unit, timezone: Option<&str>| Value::Timestamp { value, unit, timezone: timezone.map(str::to_owned) }.to_string();
+        assert_eq!(format(0, TimeUnit::Second, None), "1970-01-01T00:00:00");
+        assert_eq!(format(-1, TimeUnit::Microsecond, None), "1969-12-31T23:59:59.999999");
+        assert_eq!(format(1_500, TimeUnit::Millisecond, Some("UTC")), "1970-01-01T00:00:01.500Z");
+    }
+}
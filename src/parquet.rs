@@ -0,0 +1,1040 @@
+//! Parquet footer
+//!
+//! Footer is decoded by parquet crate and summarized here, so that metadata is reported identically by both backends.
+//! Backends only read the tail of every file (see [footer_len]) and the footer itself.
+
+use core::{cmp, fmt};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io;
+
+#[cfg(feature = "datafusion")]
+use datafusion::parquet as parquet_rs;
+#[cfg(not(feature = "datafusion"))]
+use ::parquet as parquet_rs;
+use parquet_rs::basic::{ConvertedType, LogicalType, SortOrder, Type as PhysicalType};
+use parquet_rs::file::metadata::{ColumnChunkMetaData, ParquetMetaData, ParquetMetaDataReader};
+use parquet_rs::file::statistics::{Statistics, ValueStatistics};
+use parquet_rs::schema::types::ColumnDescriptor;
+
+use crate::delta;
+use crate::format::{Cell, ColumnStats, OutputFormat, RowWriter};
+use crate::literal::{LiteralType, TimeUnit, Value};
+
+///Length of the file tail: footer length followed by magic
+pub const TAIL_LEN: usize = 8;
+//Value of partition in path for null
+const HIVE_NULL: &str = "__HIVE_DEFAULT_PARTITION__";
+///Maximum length of key-value metadata value to print as it is
+pub const MAX_METADATA_VALUE: usize = 64;
+
+#[derive(Debug)]
+///Parquet footer error
+pub struct ParquetError(String);
+
+impl fmt::Display for ParquetError {
+    #[inline(always)]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ParquetError {}
+
+impl From<parquet_rs::errors::ParquetError> for ParquetError {
+    #[inline(always)]
+    fn from(error: parquet_rs::errors::ParquetError) -> Self {
+        Self(error.to_string())
+    }
+}
+
+///Returns length of footer from the last [TAIL_LEN] bytes of the file
+pub fn footer_len(tail: &[u8]) -> Result<usize, ParquetError> {
+    let tail = <&[u8; TAIL_LEN]>::try_from(tail).map_err(|_| ParquetError("Not a parquet file".to_owned()))?;
+    Ok(ParquetMetaDataReader::decode_footer_tail(tail)?.metadata_length())
+}
+
+//Meaning of statistics values of column
+//
+//Files written without logical types are described by converted type, which is interpreted the same way
+enum ValueType {
+    Plain,
+    Signed,
+    Unsigned,
+    Decimal {
+        precision: i32,
+        scale: i32,
+    },
+    Date,
+    Timestamp {
+        unit: TimeUnit,
+        utc: bool,
+    },
+    Text,
+    Other,
+}
+
+impl ValueType {
+    fn new(column: &ColumnDescriptor) -> Self {
+        match column.logical_type() {
+            Some(LogicalType::Integer { is_signed: false, .. }) => Self::Unsigned,
+            Some(LogicalType::Integer { .. }) => Self::Signed,
+            Some(LogicalType::Decimal { precision, scale }) => Self::Decimal { precision, scale },
+            Some(LogicalType::Date) => Self::Date,
+            Some(LogicalType::Timestamp { is_adjusted_to_u_t_c, unit }) => Self::Timestamp {
+                unit: time_unit(&unit),
+                utc: is_adjusted_to_u_t_c,
+            },
+            Some(LogicalType::String | LogicalType::Enum | LogicalType::Json) => Self::Text,
+            Some(_) => Self::Other,
+            None => match column.converted_type() {
+                ConvertedType::NONE => Self::Plain,
+                ConvertedType::INT_8 | ConvertedType::INT_16 | ConvertedType::INT_32 | ConvertedType::INT_64 => Self::Signed,
+                ConvertedType::UINT_8 | ConvertedType::UINT_16 | ConvertedType::UINT_32 | ConvertedType::UINT_64 => Self::Unsigned,
+                ConvertedType::DECIMAL => Self::Decimal {
+                    precision: column.type_precision(),
+                    scale: column.type_scale(),
+                },
+                ConvertedType::DATE => Self::Date,
+                ConvertedType::TIMESTAMP_MILLIS => Self::Timestamp {
+                    unit: TimeUnit::Millisecond,
+                    utc: true,
+                },
+                ConvertedType::TIMESTAMP_MICROS => Self::Timestamp {
+                    unit: TimeUnit::Microsecond,
+                    utc: true,
+                },
+                ConvertedType::UTF8 | ConvertedType::ENUM | ConvertedType::JSON => Self::Text,
+                _ => Self::Other,
+            },
+        }
+    }
+}
+
+fn time_unit(unit: &parquet_rs::basic::TimeUnit) -> TimeUnit {
+    match unit {
+        parquet_rs::basic::TimeUnit::MILLIS => TimeUnit::Millisecond,
+        parquet_rs::basic::TimeUnit::MICROS => TimeUnit::Microsecond,
+        parquet_rs::basic::TimeUnit::NANOS => TimeUnit::Nanosecond,
+    }
+}
+
+//Name of top level column `column` belongs to
+fn root(column: &ColumnDescriptor) -> &str {
+    column.path().parts().first().map_or("", String::as_str)
+}
+
+//Names physical type, including length of fixed length byte array
+fn physical_type_name(column: &ColumnDescriptor) -> String {
+    match column.physical_type() {
+        PhysicalType::FIXED_LEN_BYTE_ARRAY => format!("FIXED_LEN_BYTE_ARRAY({})", column.type_length()),
+        typ => format!("{typ:?}"),
+    }
+}
+
+//Names logical type, or converted type if file was written without logical types
+fn logical_type_name(column: &ColumnDescriptor) -> Option<String> {
+    let unit = |unit: &parquet_rs::basic::TimeUnit| match unit {
+        parquet_rs::basic::TimeUnit::MILLIS => "MILLIS",
+        parquet_rs::basic::TimeUnit::MICROS => "MICROS",
+        parquet_rs::basic::TimeUnit::NANOS => "NANOS",
+    };
+    match column.logical_type() {
+        Some(LogicalType::Decimal { precision, scale }) => Some(format!("DECIMAL({precision},{scale})")),
+        Some(LogicalType::Time { is_adjusted_to_u_t_c, unit: time_unit }) => Some(format!("TIME({},{is_adjusted_to_u_t_c})", unit(&time_unit))),
+        Some(LogicalType::Timestamp { is_adjusted_to_u_t_c, unit: time_unit }) => Some(format!("TIMESTAMP({},{is_adjusted_to_u_t_c})", unit(&time_unit))),
+        Some(LogicalType::Integer { bit_width, is_signed }) => Some(format!("INTEGER({bit_width},{is_signed})")),
+        Some(logical_type) => Some(format!("{logical_type:?}").to_ascii_uppercase()),
+        None => match column.converted_type() {
+            ConvertedType::NONE => None,
+            ConvertedType::DECIMAL => Some(format!("DECIMAL({},{})", column.type_precision(), column.type_scale())),
+            converted_type => Some(format!("{converted_type:?}")),
+        },
+    }
+}
+
+//Names compression codec without its level
+fn codec_name(chunk: &ColumnChunkMetaData) -> String {
+    let codec = format!("{:?}", chunk.compression());
+    match codec.split_once('(') {
+        Some((name, _)) => name.to_owned(),
+        None => codec,
+    }
+}
+
+#[inline]
+fn size(value: i64) -> u64 {
+    u64::try_from(value).unwrap_or_default()
+}
+
+fn decimal(value: i128, precision: i32, scale: i32) -> Option<Value> {
+    Some(Value::Decimal {
+        value,
+        precision: u8::try_from(precision).ok()?,
+        scale: i8::try_from(scale).ok()?,
+    })
+}
+
+//Converts byte array statistics value
+fn bytes_value(bytes: &[u8], value_type: &ValueType) -> Option<Value> {
+    match value_type {
+        ValueType::Decimal { precision, scale } if !bytes.is_empty() && bytes.len() <= 16 => {
+            //Big-endian two's complement, sign extended to 128 bits
+            let fill = if bytes[0] & 0x80 != 0 { 0xFF } else { 0 };
+            let mut value = [fill; 16];
+            value[16 - bytes.len()..].copy_from_slice(bytes);
+            decimal(i128::from_be_bytes(value), *precision, *scale)
+        },
+        ValueType::Text => core::str::from_utf8(bytes).ok().map(|text| Value::String(text.to_owned())),
+        _ => None,
+    }
+}
+
+//Returns minimum or maximum of statistics
+#[inline]
+fn bound<T>(statistics: &ValueStatistics<T>, is_max: bool) -> Option<&T> {
+    if is_max { statistics.max_opt() } else { statistics.min_opt() }
+}
+
+//Converts minimum or maximum of statistics into value of column's type
+//
+//Returns `None` for types without meaningful order (e.g. INT96)
+fn value(statistics: &Statistics, value_type: &ValueType, is_max: bool) -> Option<Value> {
+    match statistics {
+        Statistics::Boolean(statistics) => bound(statistics, is_max).map(|value| Value::Boolean(*value)),
+        Statistics::Int32(statistics) => {
+            let value = *bound(statistics, is_max)?;
+            match value_type {
+                ValueType::Date => Some(Value::Date(value)),
+                ValueType::Decimal { precision, scale } => decimal(value.into(), *precision, *scale),
+                ValueType::Unsigned => Some(Value::UInt(value as u32 as u64)),
+                ValueType::Signed | ValueType::Plain => Some(Value::Int(value.into())),
+                _ => None,
+            }
+        },
+        Statistics::Int64(statistics) => {
+            let value = *bound(statistics, is_max)?;
+            match value_type {
+                ValueType::Timestamp { unit, utc } => Some(Value::Timestamp {
+                    value,
+                    unit: *unit,
+                    timezone: utc.then(|| "UTC".to_owned()),
+                }),
+                ValueType::Decimal { precision, scale } => decimal(value.into(), *precision, *scale),
+                ValueType::Unsigned => Some(Value::UInt(value as u64)),
+                ValueType::Signed | ValueType::Plain => Some(Value::Int(value)),
+                _ => None,
+            }
+        },
+        Statistics::Float(statistics) if matches!(value_type, ValueType::Plain) => bound(statistics, is_max).map(|value| Value::Float((*value).into())),
+        Statistics::Double(statistics) if matches!(value_type, ValueType::Plain) => bound(statistics, is_max).map(|value| Value::Float(*value)),
+        Statistics::ByteArray(statistics) => bytes_value(bound(statistics, is_max)?.data(), value_type),
+        Statistics::FixedLenByteArray(statistics) => bytes_value(bound(statistics, is_max)?.data(), value_type),
+        _ => None,
+    }
+}
+
+//Returns minimum and maximum of column chunk
+//
+//Deprecated statistics are ordered as signed values, so they are only used for columns ordered the same way
+fn bounds(column: &ColumnDescriptor, statistics: &Statistics) -> (Cell, Cell) {
+    if statistics.is_min_max_deprecated() && !matches!(column.sort_order(), SortOrder::SIGNED) {
+        return (None, None);
+    }
+    let value_type = ValueType::new(column);
+    (value(statistics, &value_type, false), value(statistics, &value_type, true))
+}
+
+///Compares two values of the same type
+///
+///Returns `None` if values are of different types or not comparable
+pub fn compare(left: &Value, right: &Value) -> Option<cmp::Ordering> {
+    match (left, right) {
+        (Value::Boolean(left), Value::Boolean(right)) => Some(left.cmp(right)),
+        (Value::Int(left), Value::Int(right)) => Some(left.cmp(right)),
+        (Value::UInt(left), Value::UInt(right)) => Some(left.cmp(right)),
+        (Value::Float(left), Value::Float(right)) => left.partial_cmp(right),
+        (Value::Decimal { value: left, scale: left_scale, .. }, Value::Decimal { value: right, scale: right_scale, .. }) if left_scale == right_scale => Some(left.cmp(right)),
+        (Value::Date(left), Value::Date(right)) => Some(left.cmp(right)),
+        (Value::Timestamp { value: left, unit: left_unit, .. }, Value::Timestamp { value: right, unit: right_unit, .. }) if left_unit == right_unit => Some(left.cmp(right)),
+        (Value::String(left), Value::String(right)) => Some(left.cmp(right)),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone)]
+///Decoded footer of parquet file
+pub struct FileMetadata {
+    ///Metadata decoded from footer
+    pub metadata: ParquetMetaData,
+    ///Size of footer, including [TAIL_LEN]
+    pub footer_size: u64,
+}
+
+impl FileMetadata {
+    ///Decodes footer (without tail)
+    pub fn parse(footer: &[u8]) -> Result<Self, ParquetError> {
+        Ok(Self {
+            metadata: ParquetMetaDataReader::decode_metadata(footer)?,
+            footer_size: (footer.len() + TAIL_LEN) as u64,
+        })
+    }
+
+    #[inline]
+    ///Returns number of rows
+    pub fn num_rows(&self) -> u64 {
+        size(self.metadata.file_metadata().num_rows())
+    }
+
+    #[inline]
+    ///Returns leaf columns of the schema
+    pub fn columns(&self) -> &[parquet_rs::schema::types::ColumnDescPtr] {
+        self.metadata.file_metadata().schema_descr().columns()
+    }
+
+    ///Returns names of top level columns in order of the schema
+    pub fn root_columns(&self) -> Vec<&str> {
+        let mut names = Vec::<&str>::new();
+        for column in self.columns() {
+            if !names.contains(&root(column)) {
+                names.push(root(column));
+            }
+        }
+        names
+    }
+}
+
+///Summarizes statistics of top level `columns` from footers of `files`
+///
+///If `columns` is empty, every top level column is summarized.
+///Only primitive columns have statistics, nested columns report number of rows only.
+///Mean is never stored within footer, while distinct count is used only if data consists of single column chunk
+pub fn column_stats(files: &[FileMetadata], columns: &[String]) -> Vec<ColumnStats> {
+    let mut names = columns.to_vec();
+    if names.is_empty() {
+        for file in files {
+            for name in file.root_columns() {
+                if !names.iter().any(|known| known == name) {
+                    names.push(name.to_owned());
+                }
+            }
+        }
+    }
+
+    let rows = files.iter().map(FileMetadata::num_rows).sum::<u64>();
+    names.into_iter().map(|name| {
+        let mut stats = ColumnStats {
+            name,
+            rows: Some(rows),
+            nulls: Some(0),
+            min: None,
+            max: None,
+            mean: None,
+            distinct: None,
+        };
+        let mut chunks = 0usize;
+        let mut distinct = None;
+
+        for file in files {
+            let columns = file.columns();
+            let idx = match columns.iter().position(|column| column.path().parts().len() == 1 && root(column) == stats.name) {
+                Some(idx) => idx,
+                //Nested column has no statistics of its own
+                None if columns.iter().any(|column| root(column) == stats.name) => {
+                    stats.nulls = None;
+                    continue;
+                },
+                //Column is missing from file, so all of its rows are null
+                None => {
+                    stats.nulls = stats.nulls.map(|nulls| nulls + file.num_rows());
+                    continue;
+                },
+            };
+
+            for row_group in file.metadata.row_groups() {
+                chunks += 1;
+                let statistics = match row_group.column(idx).statistics() {
+                    Some(statistics) => statistics,
+                    None => {
+                        stats.nulls = None;
+                        continue;
+                    },
+                };
+                stats.nulls = stats.nulls.zip(statistics.null_count_opt()).map(|(total, nulls)| total + nulls);
+                distinct = statistics.distinct_count_opt();
+                let (min, max) = bounds(&columns[idx], statistics);
+                if let Some(min) = min {
+                    match stats.min.as_ref() {
+                        Some(current) if compare(current, &min) != Some(cmp::Ordering::Greater) => (),
+                        _ => stats.min = Some(min),
+                    }
+                }
+                if let Some(max) = max {
+                    match stats.max.as_ref() {
+                        Some(current) if compare(current, &max) != Some(cmp::Ordering::Less) => (),
+                        _ => stats.max = Some(max),
+                    }
+                }
+            }
+        }
+        if chunks == 1 {
+            stats.distinct = distinct;
+        }
+        stats
+    }).collect()
+}
+
+///Sums number of rows of `files` (path and its footer) grouped by partition `columns` within their paths
+///
+///Partition values are percent-decoded and parsed as `types` of `columns` within scanned data, so that groups are the same as counted by scan.
+///Groups are sorted by partition values with nulls first.
+///Returns `None` if path of any file has no value for one of `columns` or value cannot be parsed
+pub fn count_rows<'a>(files: impl IntoIterator<Item = (&'a str, &'a FileMetadata)>, columns: &[String], types: &[LiteralType]) -> Option<Vec<(Vec<Cell>, u64)>> {
+    if types.len() != columns.len() {
+        return None;
+    }
+    let mut groups = BTreeMap::<Vec<Option<String>>, u64>::new();
+    //Total is reported even without files
+    if columns.is_empty() {
+        groups.insert(Vec::new(), 0);
+    }
+    for (path, footer) in files {
+        let mut key = Vec::with_capacity(columns.len());
+        for column in columns {
+            let value = path.split(['/', '\\']).filter_map(|part| part.split_once('=')).find(|(name, _)| *name == column.as_str()).map(|(_, value)| value)?;
+            key.push(match value {
+                HIVE_NULL => None,
+                value => Some(delta::percent_decode(value)?),
+            });
+        }
+        *groups.entry(key).or_default() += footer.num_rows();
+    }
+
+    let mut counts = Vec::with_capacity(groups.len());
+    for (key, rows) in groups {
+        let mut group = Vec::with_capacity(key.len());
+        for (value, typ) in key.into_iter().zip(types.iter()) {
+            group.push(match value {
+                Some(value) => Some(Value::parse(&value, typ).ok()?),
+                None => None,
+            });
+        }
+        counts.push((group, rows));
+    }
+    //Text of the same value may differ (e.g. `01` and `1`), so groups are merged after parsing
+    counts.sort_by(|(left, _), (right, _)| compare_group(left, right));
+    counts.dedup_by(|(group, rows), (previous, total)| match compare_group(group, previous) {
+        cmp::Ordering::Equal => {
+            *total += *rows;
+            true
+        },
+        _ => false,
+    });
+    Some(counts)
+}
+
+//Compares groups by their values, with nulls first
+fn compare_group(left: &[Cell], right: &[Cell]) -> cmp::Ordering {
+    for (left, right) in left.iter().zip(right.iter()) {
+        let ordering = match (left, right) {
+            (Some(left), Some(right)) => compare(left, right).unwrap_or(cmp::Ordering::Equal),
+            (left, right) => left.is_some().cmp(&right.is_some()),
+        };
+        if ordering != cmp::Ordering::Equal {
+            return ordering;
+        }
+    }
+    cmp::Ordering::Equal
+}
+
+//Joins distinct values in sorted order
+fn join<T: Ord + fmt::Display>(values: impl IntoIterator<Item = T>) -> String {
+    values.into_iter().collect::<BTreeSet<_>>().iter().map(|value| value.to_string()).collect::<Vec<_>>().join(",")
+}
+
+#[inline]
+fn text(value: impl fmt::Display) -> Cell {
+    Some(Value::String(value.to_string()))
+}
+
+//Writer of inspected section
+//
+//Every section is printed into the same output, so JSON lines name their section within `section` field
+struct SectionWriter {
+    writer: RowWriter,
+    section: Option<&'static str>,
+    row: Vec<Cell>,
+}
+
+impl SectionWriter {
+    fn new(format: OutputFormat, section: &'static str, columns: &[&str], out: &mut impl io::Write) -> io::Result<Self> {
+        let section = matches!(format, OutputFormat::JsonLines).then_some(section);
+        let columns = section.map(|_| "section").into_iter().chain(columns.iter().copied()).map(str::to_owned).collect();
+        let mut writer = RowWriter::new(format, columns);
+        writer.write_header(out)?;
+        Ok(Self {
+            writer,
+            section,
+            row: Vec::new(),
+        })
+    }
+
+    fn write_row(&mut self, out: &mut impl io::Write, row: &[Cell]) -> io::Result<()> {
+        match self.section {
+            Some(section) => {
+                self.row.clear();
+                self.row.push(text(section));
+                self.row.extend(row.iter().cloned());
+                self.writer.write_row(out, &self.row)
+            },
+            None => self.writer.write_row(out, row),
+        }
+    }
+
+    #[inline]
+    fn finish(mut self, out: &mut impl io::Write) -> io::Result<()> {
+        self.writer.finish(out)
+    }
+}
+
+///Writes file level metadata of `files` (path and its footer) in specified `format`
+///
+///Values of key-value metadata longer than [MAX_METADATA_VALUE] are reported by their size only
+pub fn write_files(format: OutputFormat, files: &[(&str, &FileMetadata)], out: &mut impl io::Write) -> io::Result<()> {
+    let columns = ["file", "version", "created_by", "rows", "row_groups", "columns", "compressed", "uncompressed", "footer", "metadata"];
+    let mut writer = SectionWriter::new(format, "files", &columns, out)?;
+    for (path, file) in files {
+        let file_metadata = file.metadata.file_metadata();
+        let chunks = file.metadata.row_groups().iter().flat_map(|row_group| row_group.columns().iter());
+        let (compressed, uncompressed) = chunks.fold((0u64, 0u64), |(compressed, uncompressed), chunk| (compressed + size(chunk.compressed_size()), uncompressed + size(chunk.uncompressed_size())));
+        let metadata = file_metadata.key_value_metadata().into_iter().flatten().map(|pair| match pair.value.as_ref() {
+            Some(value) if value.len() > MAX_METADATA_VALUE => format!("{}=<{} bytes>", pair.key, value.len()),
+            Some(value) => format!("{}={value}", pair.key),
+            None => pair.key.clone(),
+        }).collect::<Vec<_>>();
+        writer.write_row(out, &[
+            text(path),
+            Some(Value::Int(file_metadata.version().into())),
+            file_metadata.created_by().map(|created_by| Value::String(created_by.to_owned())),
+            Some(Value::UInt(file.num_rows())),
+            Some(Value::UInt(file.metadata.num_row_groups() as u64)),
+            Some(Value::UInt(file.columns().len() as u64)),
+            Some(Value::UInt(compressed)),
+            Some(Value::UInt(uncompressed)),
+            Some(Value::UInt(file.footer_size)),
+            text(metadata.join("; ")),
+        ])?;
+    }
+    writer.finish(out)
+}
+
+///Writes summary of every leaf column across all row groups of `files` (path and its footer) in specified `format`
+///
+///Columns are identified by path and types, so that column stored with different types across files is reported once per type.
+///Statistics are reported as number of column chunks having them
+pub fn write_column_summary(format: OutputFormat, files: &[(&str, &FileMetadata)], out: &mut impl io::Write) -> io::Result<()> {
+    struct Summary {
+        path: String,
+        physical_type: String,
+        logical_type: Option<String>,
+        codecs: BTreeSet<String>,
+        encodings: BTreeSet<String>,
+        chunks: u64,
+        values: u64,
+        compressed: u64,
+        uncompressed: u64,
+        statistics: u64,
+        min_max: u64,
+        null_count: u64,
+    }
+
+    let mut summaries = Vec::<Summary>::new();
+    for (_, file) in files {
+        for (idx, column) in file.columns().iter().enumerate() {
+            let path = column.path().string();
+            let physical_type = physical_type_name(column);
+            let logical_type = logical_type_name(column);
+            let summary = match summaries.iter().position(|summary| summary.path == path && summary.physical_type == physical_type && summary.logical_type == logical_type) {
+                Some(summary) => &mut summaries[summary],
+                None => {
+                    summaries.push(Summary {
+                        path,
+                        physical_type,
+                        logical_type,
+                        codecs: BTreeSet::new(),
+                        encodings: BTreeSet::new(),
+                        chunks: 0,
+                        values: 0,
+                        compressed: 0,
+                        uncompressed: 0,
+                        statistics: 0,
+                        min_max: 0,
+                        null_count: 0,
+                    });
+                    let last = summaries.len() - 1;
+                    &mut summaries[last]
+                },
+            };
+            for row_group in file.metadata.row_groups() {
+                let chunk = row_group.column(idx);
+                summary.codecs.insert(codec_name(chunk));
+                summary.encodings.extend(chunk.encodings().map(|encoding| format!("{encoding:?}")));
+                summary.chunks += 1;
+                summary.values += size(chunk.num_values());
+                summary.compressed += size(chunk.compressed_size());
+                summary.uncompressed += size(chunk.uncompressed_size());
+                if let Some(statistics) = chunk.statistics() {
+                    let (min, max) = bounds(column, statistics);
+                    summary.statistics += 1;
+                    summary.min_max += u64::from(min.is_some() && max.is_some());
+                    summary.null_count += u64::from(statistics.null_count_opt().is_some());
+                }
+            }
+        }
+    }
+
+    let columns = ["column", "physical_type", "logical_type", "codecs", "encodings", "chunks", "values", "compressed", "uncompressed", "statistics", "min_max", "null_count"];
+    let mut writer = SectionWriter::new(format, "columns", &columns, out)?;
+    for summary in summaries {
+        writer.write_row(out, &[
+            text(summary.path),
+            text(summary.physical_type),
+            summary.logical_type.map(Value::String),
+            text(join(summary.codecs)),
+            text(join(summary.encodings)),
+            Some(Value::UInt(summary.chunks)),
+            Some(Value::UInt(summary.values)),
+            Some(Value::UInt(summary.compressed)),
+            Some(Value::UInt(summary.uncompressed)),
+            Some(Value::UInt(summary.statistics)),
+            Some(Value::UInt(summary.min_max)),
+            Some(Value::UInt(summary.null_count)),
+        ])?;
+    }
+    writer.finish(out)
+}
+
+///Writes every column chunk of every row group of `files` (path and its footer) in specified `format`
+pub fn write_column_chunks(format: OutputFormat, files: &[(&str, &FileMetadata)], out: &mut impl io::Write) -> io::Result<()> {
+    let columns = ["file", "row_group", "rows", "column", "physical_type", "logical_type", "codec", "encodings", "values", "compressed", "uncompressed", "nulls", "min", "max"];
+    let mut writer = SectionWriter::new(format, "row_groups", &columns, out)?;
+    for (path, file) in files {
+        for (idx, row_group) in file.metadata.row_groups().iter().enumerate() {
+            for (column, chunk) in file.columns().iter().zip(row_group.columns().iter()) {
+                let statistics = chunk.statistics();
+                let (min, max) = statistics.map_or((None, None), |statistics| bounds(column, statistics));
+                writer.write_row(out, &[
+                    text(path),
+                    Some(Value::UInt(idx as u64)),
+                    Some(Value::UInt(size(row_group.num_rows()))),
+                    text(column.path().string()),
+                    text(physical_type_name(column)),
+                    logical_type_name(column).map(Value::String),
+                    text(codec_name(chunk)),
+                    text(join(chunk.encodings().map(|encoding| format!("{encoding:?}")))),
+                    Some(Value::UInt(size(chunk.num_values()))),
+                    Some(Value::UInt(size(chunk.compressed_size()))),
+                    Some(Value::UInt(size(chunk.uncompressed_size()))),
+                    statistics.and_then(Statistics::null_count_opt).map(Value::UInt),
+                    min,
+                    max,
+                ])?;
+            }
+        }
+    }
+    writer.finish(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //Compact protocol types
+    const TRUE: u8 = 1;
+    const FALSE: u8 = 2;
+    const BYTE: u8 = 3;
+    const I32: u8 = 5;
+    const I64: u8 = 6;
+    const BINARY: u8 = 8;
+    const LIST: u8 = 9;
+    const STRUCT: u8 = 12;
+
+    //Writer of thrift compact protocol
+    struct Writer {
+        out: Vec<u8>,
+        last: Vec<i16>,
+    }
+
+    impl Writer {
+        fn new() -> Self {
+            Self {
+                out: Vec::new(),
+                last: vec![0],
+            }
+        }
+
+        fn varint(&mut self, mut value: u64) {
+            while value >= 0x80 {
+                self.out.push(value as u8 | 0x80);
+                value >>= 7;
+            }
+            self.out.push(value as u8);
+        }
+
+        fn zigzag(&mut self, value: i64) {
+            self.varint(((value << 1) ^ (value >> 63)) as u64);
+        }
+
+        fn field(&mut self, id: i16, typ: u8) {
+            let last = self.last.last_mut().expect("to be within struct");
+            let delta = id - *last;
+            *last = id;
+            match delta {
+                1..=15 => self.out.push((delta as u8) << 4 | typ),
+                _ => {
+                    self.out.push(typ);
+                    self.zigzag(id.into());
+                },
+            }
+        }
+
+        fn int(&mut self, id: i16, value: i32) {
+            self.field(id, I32);
+            self.zigzag(value.into());
+        }
+
+        fn long(&mut self, id: i16, value: i64) {
+            self.field(id, I64);
+            self.zigzag(value);
+        }
+
+        fn binary(&mut self, id: i16, value: &[u8]) {
+            self.field(id, BINARY);
+            self.varint(value.len() as u64);
+            self.out.extend_from_slice(value);
+        }
+
+        fn boolean(&mut self, id: i16, value: bool) {
+            self.field(id, if value { TRUE } else { FALSE });
+        }
+
+        fn list(&mut self, id: i16, typ: u8, size: usize) {
+            self.field(id, LIST);
+            match size {
+                0..15 => self.out.push((size as u8) << 4 | typ),
+                _ => {
+                    self.out.push(0xF0 | typ);
+                    self.varint(size as u64);
+                },
+            }
+        }
+
+        //Starts struct field
+        fn begin(&mut self, id: i16) {
+            self.field(id, STRUCT);
+            self.element();
+        }
+
+        //Starts struct element of list
+        fn element(&mut self) {
+            self.last.push(0);
+        }
+
+        fn end(&mut self) {
+            self.out.push(0);
+            self.last.pop();
+        }
+    }
+
+    #[derive(Default)]
+    struct Column<'a> {
+        name: &'a str,
+        physical_type: i32,
+        //Length of fixed length byte array
+        type_length: Option<i32>,
+        //Converted type, precision and scale
+        converted_type: Option<(i32, i32, i32)>,
+        logical_type: Option<&'a dyn Fn(&mut Writer)>,
+        statistics: Option<&'a dyn Fn(&mut Writer)>,
+    }
+
+    //Encodes footer of file with single row group of flat `columns`
+    fn footer(columns: &[Column<'_>]) -> Vec<u8> {
+        let mut writer = Writer::new();
+        writer.int(1, 2);
+        writer.list(2, STRUCT, columns.len() + 1);
+        writer.element();
+        writer.binary(4, b"schema");
+        writer.int(5, columns.len() as i32);
+        writer.end();
+        for column in columns {
+            writer.element();
+            writer.int(1, column.physical_type);
+            if let Some(type_length) = column.type_length {
+                writer.int(2, type_length);
+            }
+            writer.int(3, 1);
+            writer.binary(4, column.name.as_bytes());
+            if let Some((typ, precision, scale)) = column.converted_type {
+                writer.int(6, typ);
+                if precision != 0 {
+                    writer.int(7, scale);
+                    writer.int(8, precision);
+                }
+            }
+            if let Some(logical_type) = column.logical_type {
+                writer.begin(10);
+                logical_type(&mut writer);
+                writer.end();
+            }
+            writer.end();
+        }
+        writer.long(3, 3);
+
+        writer.list(4, STRUCT, 1);
+        writer.element();
+        writer.list(1, STRUCT, columns.len());
+        for column in columns {
+            writer.element();
+            writer.long(2, 4);
+            writer.begin(3);
+            writer.int(1, column.physical_type);
+            writer.list(2, I32, 1);
+            writer.zigzag(0);
+            writer.list(3, BINARY, 1);
+            writer.varint(column.name.len() as u64);
+            writer.out.extend_from_slice(column.name.as_bytes());
+            writer.int(4, 1);
+            writer.long(5, 3);
+            writer.long(6, 10);
+            writer.long(7, 8);
+            //Key-value metadata is skipped
+            writer.list(8, STRUCT, 1);
+            writer.element();
+            writer.binary(1, b"key");
+            writer.end();
+            writer.long(9, 4);
+            if let Some(statistics) = column.statistics {
+                writer.begin(12);
+                statistics(&mut writer);
+                writer.end();
+            }
+            writer.end();
+            writer.end();
+        }
+        writer.long(2, 10);
+        writer.long(3, 3);
+        writer.end();
+
+        writer.binary(6, b"test");
+        writer.end();
+        writer.out
+    }
+
+    //Encodes statistics with the same minimum and maximum
+    fn exact(value: Vec<u8>) -> impl Fn(&mut Writer) {
+        move |writer: &mut Writer| {
+            writer.binary(5, &value);
+            writer.binary(6, &value);
+        }
+    }
+
+    //Returns minimum and maximum of every column within the first row group
+    fn chunk_bounds(metadata: &FileMetadata) -> Vec<(Option<String>, Option<String>)> {
+        metadata.metadata.row_group(0).columns().iter().zip(metadata.columns().iter()).map(|(chunk, column)| {
+            let statistics = chunk.statistics().expect("statistics");
+            let (min, max) = bounds(column, statistics);
+            (min.map(|value| value.to_string()), max.map(|value| value.to_string()))
+        }).collect()
+    }
+
+    #[test]
+    fn should_map_converted_and_logical_types() {
+        let timestamp = |writer: &mut Writer| {
+            writer.begin(8);
+            writer.boolean(1, false);
+            writer.begin(2);
+            writer.begin(3);
+            writer.end();
+            writer.end();
+            writer.end();
+        };
+        let integer = |writer: &mut Writer| {
+            writer.begin(10);
+            writer.field(1, BYTE);
+            writer.out.push(8);
+            writer.boolean(2, false);
+            writer.end();
+        };
+        let name = exact(b"abc".to_vec());
+        let price = exact(12345i32.to_le_bytes().to_vec());
+        let count = exact((-1i32).to_le_bytes().to_vec());
+        let created = exact(5i64.to_le_bytes().to_vec());
+        let small = exact(200i32.to_le_bytes().to_vec());
+        let metadata = FileMetadata::parse(&footer(&[
+            Column { name: "name", physical_type: 6, converted_type: Some((0, 0, 0)), statistics: Some(&name), ..Column::default() },
+            Column { name: "price", physical_type: 1, converted_type: Some((5, 9, 2)), statistics: Some(&price), ..Column::default() },
+            Column { name: "count", physical_type: 1, converted_type: Some((13, 0, 0)), statistics: Some(&count), ..Column::default() },
+            Column { name: "created", physical_type: 2, logical_type: Some(&timestamp), statistics: Some(&created), ..Column::default() },
+            Column { name: "small", physical_type: 1, logical_type: Some(&integer), statistics: Some(&small), ..Column::default() },
+        ])).expect("valid footer");
+
+        let file_metadata = metadata.metadata.file_metadata();
+        assert_eq!(file_metadata.version(), 2);
+        assert_eq!(metadata.num_rows(), 3);
+        assert_eq!(file_metadata.created_by(), Some("test"));
+        assert_eq!(metadata.root_columns(), ["name", "price", "count", "created", "small"]);
+        let types = metadata.columns().iter().map(|column| (physical_type_name(column), logical_type_name(column))).collect::<Vec<_>>();
+        assert_eq!(types, [
+            ("BYTE_ARRAY".to_owned(), Some("UTF8".to_owned())),
+            ("INT32".to_owned(), Some("DECIMAL(9,2)".to_owned())),
+            ("INT32".to_owned(), Some("UINT_32".to_owned())),
+            ("INT64".to_owned(), Some("TIMESTAMP(NANOS,false)".to_owned())),
+            ("INT32".to_owned(), Some("INTEGER(8,false)".to_owned())),
+        ]);
+
+        let statistics = |idx: usize| metadata.metadata.row_group(0).column(idx).statistics().expect("statistics");
+        let min = |idx: usize| bounds(&metadata.columns()[idx], statistics(idx)).0;
+        assert!(matches!(min(0), Some(Value::String(ref text)) if text == "abc"));
+        assert_eq!(min(1).map(|value| value.to_string()).as_deref(), Some("123.45"));
+        assert!(matches!(min(2), Some(Value::UInt(4_294_967_295))));
+        assert!(matches!(min(3), Some(Value::Timestamp { value: 5, unit: TimeUnit::Nanosecond, timezone: None })));
+        assert!(matches!(min(4), Some(Value::UInt(200))));
+    }
+
+    #[test]
+    fn should_decode_fixed_length_decimal() {
+        let statistics = |writer: &mut Writer| {
+            writer.binary(5, &[0x00, 0x30, 0x39]);
+            writer.binary(6, &[0xFF, 0xFF, 0x85]);
+        };
+        let metadata = FileMetadata::parse(&footer(&[
+            Column { name: "amount", physical_type: 7, type_length: Some(3), converted_type: Some((5, 6, 2)), statistics: Some(&statistics), ..Column::default() },
+        ])).expect("valid footer");
+
+        assert_eq!(physical_type_name(&metadata.columns()[0]), "FIXED_LEN_BYTE_ARRAY(3)");
+        assert_eq!(chunk_bounds(&metadata), [(Some("-1.23".to_owned()), Some("123.45".to_owned()))]);
+        assert!(bytes_value(&[0; 17], &ValueType::Decimal { precision: 6, scale: 2 }).is_none());
+    }
+
+    #[test]
+    fn should_not_decode_int96() {
+        let statistics = |writer: &mut Writer| {
+            writer.long(3, 1);
+            writer.binary(5, &[0xFF; 12]);
+            writer.binary(6, &[0; 12]);
+        };
+        let metadata = FileMetadata::parse(&footer(&[
+            Column { name: "legacy", physical_type: 3, statistics: Some(&statistics), ..Column::default() },
+        ])).expect("valid footer");
+
+        assert_eq!(physical_type_name(&metadata.columns()[0]), "INT96");
+        let statistics = metadata.metadata.row_group(0).column(0).statistics().expect("statistics");
+        assert_eq!(statistics.null_count_opt(), Some(1));
+        assert_eq!(chunk_bounds(&metadata), [(None, None)]);
+
+        let stats = column_stats(&[metadata], &[]);
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].nulls, Some(1));
+        assert!(stats[0].min.is_none());
+        assert!(stats[0].max.is_none());
+    }
+
+    #[test]
+    fn should_use_deprecated_statistics_of_signed_columns_only() {
+        let deprecated = |writer: &mut Writer| {
+            writer.binary(1, &9i64.to_le_bytes());
+            writer.binary(2, &1i64.to_le_bytes());
+            writer.long(3, 0);
+        };
+        let deprecated_text = |writer: &mut Writer| {
+            writer.binary(1, b"z");
+            writer.binary(2, b"a");
+        };
+        let both = |writer: &mut Writer| {
+            writer.binary(1, &9i64.to_le_bytes());
+            writer.binary(2, &1i64.to_le_bytes());
+            writer.binary(5, &7i64.to_le_bytes());
+            writer.binary(6, &3i64.to_le_bytes());
+        };
+        let metadata = FileMetadata::parse(&footer(&[
+            Column { name: "id", physical_type: 2, statistics: Some(&deprecated), ..Column::default() },
+            Column { name: "name", physical_type: 6, converted_type: Some((0, 0, 0)), statistics: Some(&deprecated_text), ..Column::default() },
+            Column { name: "count", physical_type: 2, converted_type: Some((14, 0, 0)), statistics: Some(&deprecated), ..Column::default() },
+            Column { name: "score", physical_type: 2, statistics: Some(&both), ..Column::default() },
+        ])).expect("valid footer");
+
+        assert_eq!(chunk_bounds(&metadata), [
+            (Some("1".to_owned()), Some("9".to_owned())),
+            //Byte arrays and unsigned integers are not ordered as signed values
+            (None, None),
+            (None, None),
+            //Deprecated statistics are ignored if current ones are present
+            (Some("3".to_owned()), Some("7".to_owned())),
+        ]);
+    }
+
+    #[test]
+    fn should_name_section_within_json_lines() {
+        let metadata = FileMetadata::parse(&footer(&[Column { name: "id", physical_type: 2, ..Column::default() }])).expect("valid footer");
+        let files = [("data/part-0.parquet", &metadata)];
+        let mut out = Vec::new();
+        write_files(OutputFormat::JsonLines, &files, &mut out).expect("to write files");
+        write_column_summary(OutputFormat::JsonLines, &files, &mut out).expect("to write columns");
+        write_column_chunks(OutputFormat::JsonLines, &files, &mut out).expect("to write row groups");
+        let out = String::from_utf8(out).expect("utf-8 output");
+        let sections = out.lines().map(|line| line.split(',').next().unwrap_or_default()).collect::<Vec<_>>();
+        assert_eq!(sections, [r#"{"section":"files""#, r#"{"section":"columns""#, r#"{"section":"row_groups""#]);
+
+        let mut out = Vec::new();
+        write_files(OutputFormat::Csv, &files, &mut out).expect("to write files");
+        assert!(String::from_utf8(out).expect("utf-8 output").starts_with("file,version,"));
+    }
+
+    #[test]
+    fn should_count_rows_by_typed_partitions() {
+        let metadata = FileMetadata::parse(&footer(&[Column { name: "id", physical_type: 2, ..Column::default() }])).expect("valid footer");
+        let files = [
+            "data/year=2024/city=New%20York/part-0.parquet",
+            "data/year=02024/city=New%20York/part-0.parquet",
+            "data/year=__HIVE_DEFAULT_PARTITION__/city=Oslo/part-0.parquet",
+            "data/year=10/city=Oslo/part-0.parquet",
+            "data/year=9/city=Oslo/part-0.parquet",
+        ];
+        let columns = ["year".to_owned(), "city".to_owned()];
+        let counts = count_rows(files.iter().map(|path| (*path, &metadata)), &columns, &[LiteralType::Int(64), LiteralType::String]).expect("partitions");
+        let counts = counts.iter().map(|(group, rows)| (group.iter().map(|cell| cell.as_ref().map(|value| value.to_string())).collect::<Vec<_>>(), *rows)).collect::<Vec<_>>();
+        let group = |year: Option<&str>, city: &str| vec![year.map(str::to_owned), Some(city.to_owned())];
+        assert_eq!(counts, [
+            (group(None, "Oslo"), 3),
+            (group(Some("9"), "Oslo"), 3),
+            (group(Some("10"), "Oslo"), 3),
+            (group(Some("2024"), "New York"), 6),
+        ]);
+
+        //Values which cannot be parsed or are missing are left to scan
+        assert!(count_rows(files.iter().map(|path| (*path, &metadata)), &columns[1..], &[LiteralType::Int(64)]).is_none());
+        assert!(count_rows([("data/part-0.parquet", &metadata)], &columns[..1], &[LiteralType::Int(64)]).is_none());
+        let total = count_rows(files.iter().map(|path| (*path, &metadata)), &[], &[]).expect("total");
+        assert_eq!(total.len(), 1);
+        assert_eq!(total[0].1, 15);
+    }
+
+    #[test]
+    fn should_reject_malformed_footer() {
+        assert_eq!(footer_len(b"\x10\x00\x00\x00PAR1").ok(), Some(16));
+        assert!(footer_len(b"\x10\x00\x00\x00PAR2").is_err());
+        assert!(footer_len(b"PAR1").is_err());
+
+        assert!(FileMetadata::parse(&[]).is_err());
+
+        let valid = footer(&[Column { name: "id", physical_type: 2, ..Column::default() }]);
+        assert!(FileMetadata::parse(&valid).is_ok());
+        for len in 0..valid.len() {
+            assert!(FileMetadata::parse(&valid[..len]).is_err(), "truncated footer of {len} bytes");
+        }
+
+        //Unknown physical type
+        let unknown = footer(&[Column { name: "id", physical_type: 9, ..Column::default() }]);
+        assert!(FileMetadata::parse(&unknown).is_err());
+    }
+}
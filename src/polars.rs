@@ -1,48 +1,105 @@
 //!Polars module
 
-use super::{cli, FileFormat, Query, SortBy, DUPLICATE_COLUMN};
+use super::{delta, expr, format, literal, parquet, ColumnSchema, Compression, CsvOptions, DeltaVersion, DetectedFormat, FileFormat, ParquetCodec, ParquetStatistics, ParquetWriteConfig, Query, SampleSize, Slice, SortBy, DIFF_CHANGED_SUFFIX, DIFF_PRESENT_COLUMN, DIFF_RIGHT_SUFFIX, DUPLICATE_COLUMN, SAMPLE_KEY_COLUMN};
+use literal::{LiteralType, Value};
 
 pub use polars::error::PolarsError;
 pub use polars::prelude::{Expr, PlRefPath, PlSmallStr};
 pub use polars::prelude::{LazyCsvReader, LazyFileListReader, LazyFrame, col, lit};
 pub use polars::prelude::{ScanArgsParquet, SortMultipleOptions, UniqueKeepStrategy};
+pub use polars::prelude::{AnyValue, DataType, IdxSize, Schema, Series, TimeUnit};
 
-impl<CI: ExactSizeIterator<Item = String>, SBI: ExactSizeIterator<Item = SortBy>, UCI: ExactSizeIterator<Item = String>, WHERE: ExactSizeIterator<Item = cli::Expression>> Query<CI, SBI, UCI, WHERE> {
+//Temporary column holding position of the row during sampling
+const SAMPLE_ROW_COLUMN: &str = "__mishka_sample_row";
+
+impl<CI: ExactSizeIterator<Item = String>, SBI: ExactSizeIterator<Item = SortBy>, UCI: ExactSizeIterator<Item = String>, WHERE: ExactSizeIterator<Item = expr::Expression>, WC: ExactSizeIterator<Item = expr::ComputedColumn>, GCI: ExactSizeIterator<Item = String>, AI: ExactSizeIterator<Item = expr::Aggregate>, SCI: ExactSizeIterator<Item = String>> Query<CI, SBI, UCI, WHERE, WC, GCI, AI, SCI> {
     ///Scans `path` expecting specified `format`
     pub fn create_lazy_polars(self, path: &str, format: FileFormat, partition_by: &[String]) -> Result<LazyFrame, polars::error::PolarsError> {
+        //gzip and zstd are decompressed by polars transparently
+        match self.compression.or_else(|| Compression::split_path(path).1) {
+            Some(compression) if !format.is_compressible() => return Err(PolarsError::InvalidOperation(format!("{path}: Compression '{}' is not supported for {} format", compression.extension(), format.extension()).into())),
+            Some(compression @ (Compression::Bzip2 | Compression::Xz)) => return Err(PolarsError::InvalidOperation(format!("{path}: Compression '{}' is not supported by polars. Use --backend datafusion", compression.extension()).into())),
+            Some(Compression::Gzip | Compression::Zstd) | None => (),
+        }
+
         let mut df = match format {
-            FileFormat::Csv => scan_csv(path)?,
+            FileFormat::Csv => scan_csv(path, &self.csv, &self.schema)?,
             FileFormat::Parquet => scan_parquet(path, partition_by)?,
+            FileFormat::Json => read_json(path)?,
+            FileFormat::NdJson => scan_ndjson(path)?,
+            FileFormat::Ipc => scan_ipc(path)?,
+            FileFormat::IpcStream => read_ipc_stream(path)?,
+            FileFormat::Avro => read_avro(path)?,
+            FileFormat::Delta => scan_delta(path, self.delta_version)?,
         };
+        df = apply_schema(df, &self.schema)?;
+
+        for computed in self.with_column {
+            //Computed column may refer to previous ones, so resolve schema every time
+            let schema = df.collect_schema()?;
+            df = df.with_column(computed.expr.into_polars_expr(&schema).alias(computed.name));
+        }
+
+        //Filter before aggregation to allow pushing predicates into scan
+        let schema = df.collect_schema()?;
+        for filter in self.filter {
+            df = df.filter(filter.into_polars_expr(&schema)?);
+        }
+
+        if let Some(sample) = self.sample {
+            df = df.with_row_index(SAMPLE_ROW_COLUMN, None);
+            let key = super::sample_key(col(SAMPLE_ROW_COLUMN).cast(DataType::Int64), sample.seed, |value| lit(value));
+            df = df.with_column(key.alias(SAMPLE_KEY_COLUMN));
+            df = match sample.size {
+                SampleSize::Fraction(fraction) => df.filter(col(SAMPLE_KEY_COLUMN).lt(lit(super::sample_threshold(fraction)))),
+                //Taking N rows with smallest random keys is equivalent to reservoir sampling and runs as streaming top-k
+                SampleSize::Count(count) => {
+                    if sample.by.len() == 0 {
+                        let count = IdxSize::try_from(count).unwrap_or(IdxSize::MAX);
+                        df.sort_by_exprs([col(SAMPLE_KEY_COLUMN)], SortMultipleOptions::default()).limit(count)
+                    } else {
+                        //Take N rows with smallest keys within every group instead of ranking all rows, then restore order of rows and columns
+                        let schema = df.collect_schema()?;
+                        let by = sample.by.map(PlSmallStr::from).collect::<Vec<_>>();
+                        let sampled = schema.iter_names().filter(|name| !by.contains(name)).cloned().collect::<Vec<_>>();
+                        let aggregates = sampled.iter().map(|name| col(name.clone()).sort_by([col(SAMPLE_KEY_COLUMN)], SortMultipleOptions::default()).head(Some(count))).collect::<Vec<_>>();
+                        df.group_by(by.into_iter().map(col).collect::<Vec<_>>())
+                          .agg(aggregates)
+                          .explode(polars::lazy::dsl::Selector::ByName {
+                              names: sampled.into(),
+                              strict: true,
+                          })
+                          .sort_by_exprs([col(SAMPLE_ROW_COLUMN)], SortMultipleOptions::default())
+                          .select(schema.iter_names().cloned().map(col).collect::<Vec<_>>())
+                    }
+                }
+            };
+            df = df.drop(polars::lazy::dsl::Selector::ByName {
+                names: [PlSmallStr::from_static(SAMPLE_ROW_COLUMN), PlSmallStr::from_static(SAMPLE_KEY_COLUMN)].into(),
+                strict: true,
+            });
+        }
+
+        if let Some(group_by) = self.group_by {
+            let schema = df.collect_schema()?;
+            let aggregates = group_by.aggregates.map(|aggregate| aggregate.into_polars_expr(&schema)).collect::<Vec<_>>();
+            df = if group_by.columns.len() == 0 {
+                df.select(aggregates)
+            } else {
+                let columns = group_by.columns.map(col).collect::<Vec<_>>();
+                df.group_by(columns).agg(aggregates)
+            };
+        }
 
         let mut select = Vec::new();
-        let mut group_by = Vec::new();
         for column in self.column {
-            select.push(col(column.as_str()));
-            group_by.push(col(column));
+            select.push(col(column));
         }
         if select.is_empty() {
             select.push(col(PlSmallStr::from_static("*")));
         }
         df = df.select(&select);
 
-        if self.sort_by.len() != 0 {
-            let mut descending = Vec::new();
-            let mut columns = Vec::new();
-
-            for sort_by in self.sort_by {
-                columns.push(Expr::Column(sort_by.column.into()));
-                descending.push(sort_by.desc)
-            }
-
-            let options = SortMultipleOptions {
-                descending,
-                ..Default::default()
-            };
-            //Figure out how sort supposed to work (it doesn't right now)
-            df = df.sort_by_exprs(&columns, options)
-        }
-
         if let Some(unique) = self.unique {
             let subset = (unique.columns.len() != 0).then(|| polars::lazy::dsl::Selector::ByName {
                 //Next polars version will change it into array of Expr
@@ -56,39 +113,292 @@ impl<CI: ExactSizeIterator<Item = String>, SBI: ExactSizeIterator<Item = SortBy>
             } else {
                 df.unique(subset, strategy)
             };
+        } else if self.count_duplicates {
+            //Group by every selected column, so that each distinct record is counted
+            let group_by = df.collect_schema()?.iter_names().map(|name| col(name.clone())).collect::<Vec<_>>();
+            let agg_expr = polars::prelude::len().alias(DUPLICATE_COLUMN);
+            df = df.group_by(group_by).agg([agg_expr]);
         }
 
-        if !group_by.is_empty() {
-            let agg_expr = col("*").count().alias(DUPLICATE_COLUMN);
-            df = df.group_by(&group_by).agg([agg_expr]);
-        }
+        if self.sort_by.len() != 0 {
+            let mut descending = Vec::new();
+            let mut nulls_last = Vec::new();
+            let mut columns = Vec::new();
 
-        for filter in self.filter {
-            let left = match filter.left {
-                cli::Operand::Literal(literal) => lit(literal),
-                cli::Operand::Identifier(ident) => col(ident),
-            };
+            for sort_by in self.sort_by {
+                columns.push(col(sort_by.column));
+                descending.push(sort_by.desc);
+                nulls_last.push(!sort_by.nulls_first);
+            }
 
-            let right = match filter.right {
-                cli::Operand::Literal(literal) => lit(literal),
-                cli::Operand::Identifier(ident) => col(ident),
+            let options = SortMultipleOptions {
+                descending,
+                nulls_last,
+                maintain_order: self.stable_sort,
+                ..Default::default()
             };
+            df = df.sort_by_exprs(&columns, options)
+        }
 
-            let filter = match filter.operator {
-                cli::Operator::Less => left.lt(right),
-                cli::Operator::LessEq => left.lt_eq(right),
-                cli::Operator::Eq => left.eq(right),
-                cli::Operator::NotEq => left.neq(right),
-                cli::Operator::GreaterEq => left.gt_eq(right),
-                cli::Operator::Greater => left.gt(right),
-            };
-            df = df.filter(filter);
+        let schema = df.collect_schema()?;
+        for having in self.having {
+            df = df.filter(having.into_polars_expr(&schema)?);
         }
 
+        df = match self.slice {
+            Some(Slice::Head { offset, limit }) => {
+                let limit = limit.map_or(IdxSize::MAX, |limit| IdxSize::try_from(limit).unwrap_or(IdxSize::MAX));
+                df.slice(offset as i64, limit)
+            },
+            Some(Slice::Tail(tail)) => df.tail(IdxSize::try_from(tail).unwrap_or(IdxSize::MAX)),
+            None => df,
+        };
+
         Ok(df)
     }
 }
 
+///Returns literal types of `columns` within `df`, if literal can be cast to every one of them
+pub fn literal_types(df: &mut LazyFrame, columns: &[String]) -> Result<Option<Vec<LiteralType>>, PolarsError> {
+    let schema = df.collect_schema()?;
+    Ok(columns.iter().map(|column| schema.get(column.as_str()).and_then(literal_type)).collect())
+}
+
+///Returns literal type corresponding to polars `dtype`, if literal can be cast to it
+pub fn literal_type(dtype: &DataType) -> Option<LiteralType> {
+    match dtype {
+        DataType::Boolean => Some(LiteralType::Boolean),
+        DataType::Int8 => Some(LiteralType::Int(8)),
+        DataType::Int16 => Some(LiteralType::Int(16)),
+        DataType::Int32 => Some(LiteralType::Int(32)),
+        DataType::Int64 => Some(LiteralType::Int(64)),
+        DataType::UInt8 => Some(LiteralType::UInt(8)),
+        DataType::UInt16 => Some(LiteralType::UInt(16)),
+        DataType::UInt32 => Some(LiteralType::UInt(32)),
+        DataType::UInt64 => Some(LiteralType::UInt(64)),
+        DataType::Float32 => Some(LiteralType::Float(32)),
+        DataType::Float64 => Some(LiteralType::Float(64)),
+        DataType::Decimal(precision, scale) => Some(LiteralType::Decimal {
+            precision: precision.unwrap_or(38) as u8,
+            scale: scale.unwrap_or(0) as i8,
+        }),
+        DataType::Date => Some(LiteralType::Date),
+        DataType::Datetime(unit, timezone) => Some(LiteralType::Timestamp {
+            unit: match unit {
+                TimeUnit::Nanoseconds => literal::TimeUnit::Nanosecond,
+                TimeUnit::Microseconds => literal::TimeUnit::Microsecond,
+                TimeUnit::Milliseconds => literal::TimeUnit::Millisecond,
+            },
+            timezone: timezone.as_ref().map(|timezone| timezone.to_string()),
+        }),
+        DataType::String => Some(LiteralType::String),
+        _ => None,
+    }
+}
+
+fn any_value(value: &Value) -> AnyValue<'_> {
+    match value {
+        Value::Boolean(value) => AnyValue::Boolean(*value),
+        Value::Int(value) => AnyValue::Int64(*value),
+        Value::UInt(value) => AnyValue::UInt64(*value),
+        Value::Float(value) => AnyValue::Float64(*value),
+        //Let polars to convert decimal from its normalized representation
+        Value::Decimal { .. } => AnyValue::StringOwned(value.to_string().into()),
+        Value::Date(value) => AnyValue::Int32(*value),
+        Value::Timestamp { value, .. } => AnyValue::Int64(*value),
+        Value::String(value) => AnyValue::String(value),
+    }
+}
+
+//Casts literals to the type of `column`, if it exists within `schema`
+fn typed_literals(schema: &Schema, column: Option<&str>, literals: &[String]) -> Result<Option<Series>, PolarsError> {
+    let (column, dtype) = match column.and_then(|column| schema.get(column).map(|dtype| (column, dtype))) {
+        Some(result) => result,
+        None => return Ok(None),
+    };
+    let typ = match literal_type(dtype) {
+        Some(typ) => typ,
+        None => return Ok(None),
+    };
+
+    let mut values = Vec::with_capacity(literals.len());
+    for literal in literals {
+        match Value::parse(literal, &typ) {
+            Ok(value) => values.push(value),
+            Err(error) => return Err(PolarsError::ComputeError(format!("{column}: {error}").into())),
+        }
+    }
+    let values = values.iter().map(any_value).collect::<Vec<_>>();
+    Series::from_any_values(PlSmallStr::EMPTY, &values, true)?.strict_cast(dtype).map(Some)
+}
+
+fn polars_function(function: expr::Function, args: Vec<expr::Operand>, schema: &Schema) -> Expr {
+    let args = args.into_iter().map(|arg| arg.into_polars_expr(schema)).collect::<Vec<_>>();
+    let arg = || args.first().cloned().unwrap_or_else(|| lit(polars::prelude::NULL));
+    match function {
+        expr::Function::Upper => arg().str().to_uppercase(),
+        expr::Function::Lower => arg().str().to_lowercase(),
+        expr::Function::Length => arg().str().len_chars(),
+        expr::Function::Trim => arg().str().strip_chars(lit(polars::prelude::NULL)),
+        expr::Function::Abs => arg().abs(),
+        expr::Function::Coalesce => polars::prelude::coalesce(&args),
+        expr::Function::DateTrunc(unit) => arg().dt().truncate(lit(unit.as_duration())),
+        expr::Function::Year => arg().dt().year(),
+        expr::Function::Month => arg().dt().month(),
+        expr::Function::Day => arg().dt().day(),
+        expr::Function::Hour => arg().dt().hour(),
+    }
+}
+
+//Returns whether `operand` evaluates to integer within `schema`
+fn is_integer(operand: &expr::Operand, schema: &Schema) -> bool {
+    match operand {
+        expr::Operand::Literal(_) => false,
+        expr::Operand::Number(number) => matches!(Value::from_number(number), Value::Int(_)),
+        expr::Operand::Identifier(ident) => schema.get(ident.as_str()).is_some_and(DataType::is_integer),
+        expr::Operand::Arithmetic { left, right, .. } => is_integer(left, schema) && is_integer(right, schema),
+        expr::Operand::Function { function, args } => match function {
+            expr::Function::Length | expr::Function::Year | expr::Function::Month | expr::Function::Day | expr::Function::Hour => true,
+            expr::Function::Abs | expr::Function::Coalesce => args.iter().all(|arg| is_integer(arg, schema)),
+            _ => false,
+        },
+    }
+}
+
+//Remainder with sign of dividend, as in SQL, while polars' remainder has sign of divisor
+fn truncated_remainder(left: Expr, right: Expr) -> Expr {
+    let remainder = left.clone() % right.clone();
+    let is_opposite = remainder.clone().neq(lit(0)).and(remainder.clone().lt(lit(0)).neq(left.lt(lit(0))));
+    polars::prelude::when(is_opposite).then(remainder.clone() - right).otherwise(remainder)
+}
+
+impl expr::Operand {
+    ///Converts into polars expression
+    ///
+    ///Integer division truncates towards zero, same as in SQL
+    pub fn into_polars_expr(self, schema: &Schema) -> Expr {
+        match self {
+            expr::Operand::Literal(literal) => lit(literal),
+            expr::Operand::Number(number) => match Value::from_number(&number) {
+                Value::Int(value) => lit(value),
+                Value::Float(value) => lit(value),
+                _ => lit(number),
+            },
+            expr::Operand::Identifier(ident) => col(ident),
+            expr::Operand::Arithmetic { left, operator, right } => {
+                let is_integer = is_integer(&left, schema) && is_integer(&right, schema);
+                let left = left.into_polars_expr(schema);
+                let right = right.into_polars_expr(schema);
+                match operator {
+                    expr::ArithmeticOperator::Add => left + right,
+                    expr::ArithmeticOperator::Subtract => left - right,
+                    expr::ArithmeticOperator::Multiply => left * right,
+                    //Polars rounds integer quotient down, so divide exact multiple instead
+                    expr::ArithmeticOperator::Divide if is_integer => (left.clone() - truncated_remainder(left, right.clone())) / right,
+                    expr::ArithmeticOperator::Divide => left / right,
+                    expr::ArithmeticOperator::Modulo => truncated_remainder(left, right),
+                }
+            },
+            expr::Operand::Function { function, args } => polars_function(function, args, schema),
+        }
+    }
+
+    ///Converts into polars expression, casting literal to the type of `column` within `schema`
+    pub fn into_polars_typed_expr(self, schema: &Schema, column: Option<&str>) -> Result<Expr, PolarsError> {
+        match self {
+            expr::Operand::Literal(ref literal) | expr::Operand::Number(ref literal) => match typed_literals(schema, column, core::slice::from_ref(literal))? {
+                Some(series) => Ok(lit(series.first())),
+                None => Ok(self.into_polars_expr(schema)),
+            },
+            operand => Ok(operand.into_polars_expr(schema)),
+        }
+    }
+}
+
+impl expr::Aggregate {
+    ///Converts into polars aggregation expression
+    pub fn into_polars_expr(self, schema: &Schema) -> Expr {
+        let argument = match self.argument {
+            Some(argument) => argument.into_polars_expr(schema),
+            None => return polars::prelude::len().alias(self.name),
+        };
+        let expr = match self.function {
+            expr::Aggregation::Count => argument.count(),
+            //Nulls are not considered distinct values, same as in SQL
+            expr::Aggregation::CountDistinct => argument.drop_nulls().n_unique(),
+            expr::Aggregation::Sum => argument.sum(),
+            expr::Aggregation::Min => argument.min(),
+            expr::Aggregation::Max => argument.max(),
+            expr::Aggregation::Mean => argument.mean(),
+            expr::Aggregation::First => argument.first(),
+            expr::Aggregation::Last => argument.last(),
+        };
+        expr.alias(self.name)
+    }
+}
+
+impl expr::Expression {
+    ///Converts into polars expression
+    ///
+    ///Literals are cast to the type of column from `schema` they are compared against
+    pub fn into_polars_expr(self, schema: &Schema) -> Result<Expr, PolarsError> {
+        let expr = match self {
+            expr::Expression::Compare { left, operator, right } => {
+                let left_column = left.as_identifier().map(str::to_owned);
+                let right_column = right.as_identifier().map(str::to_owned);
+                let left = left.into_polars_typed_expr(schema, right_column.as_deref())?;
+                let right = right.into_polars_typed_expr(schema, left_column.as_deref())?;
+                match operator {
+                    expr::Operator::Less => left.lt(right),
+                    expr::Operator::LessEq => left.lt_eq(right),
+                    expr::Operator::Eq => left.eq(right),
+                    expr::Operator::NotEq => left.neq(right),
+                    expr::Operator::GreaterEq => left.gt_eq(right),
+                    expr::Operator::Greater => left.gt(right),
+                }
+            },
+            expr::Expression::And(left, right) => left.into_polars_expr(schema)?.and(right.into_polars_expr(schema)?),
+            expr::Expression::Or(left, right) => left.into_polars_expr(schema)?.or(right.into_polars_expr(schema)?),
+            expr::Expression::Not(expression) => expression.into_polars_expr(schema)?.not(),
+            expr::Expression::In { operand, list, negated } => {
+                let list = match typed_literals(schema, operand.as_identifier(), &list)? {
+                    Some(list) => list,
+                    None => Series::new(PlSmallStr::EMPTY, list),
+                };
+                let expr = operand.into_polars_expr(schema).is_in(lit(list), false);
+                if negated { expr.not() } else { expr }
+            },
+            expr::Expression::Between { operand, low, high, negated } => {
+                let column = operand.as_identifier().map(str::to_owned);
+                let low = low.into_polars_typed_expr(schema, column.as_deref())?;
+                let high = high.into_polars_typed_expr(schema, column.as_deref())?;
+                let expr = operand.into_polars_expr(schema).is_between(low, high, polars::prelude::ClosedInterval::Both);
+                if negated { expr.not() } else { expr }
+            },
+            expr::Expression::IsNull { operand, negated: false } => operand.into_polars_expr(schema).is_null(),
+            expr::Expression::IsNull { operand, negated: true } => operand.into_polars_expr(schema).is_not_null(),
+            expr::Expression::Like { operand, pattern, case_insensitive, negated } => {
+                let pattern = expr::like_to_regex(&pattern, case_insensitive);
+                let expr = operand.into_polars_expr(schema).str().contains(lit(pattern), true);
+                if negated { expr.not() } else { expr }
+            },
+            expr::Expression::Regex { operand, pattern, negated } => {
+                let expr = operand.into_polars_expr(schema).str().contains(lit(pattern), true);
+                if negated { expr.not() } else { expr }
+            },
+        };
+
+        Ok(expr)
+    }
+}
+
+///Executes SQL `statement` over `df` registered as table `name`
+pub fn sql(df: LazyFrame, name: &str, statement: &str) -> Result<LazyFrame, polars::error::PolarsError> {
+    let mut ctx = polars::sql::SQLContext::new();
+    ctx.register(name, df);
+    ctx.execute(statement)
+}
+
 ///Scan parquet through `path`
 pub fn scan_parquet(path: &str, partition_by: &[String]) -> Result<LazyFrame, polars::error::PolarsError> {
     let uri = PlRefPath::new(path);
@@ -117,14 +427,1037 @@ pub fn scan_parquet(path: &str, partition_by: &[String]) -> Result<LazyFrame, po
     LazyFrame::scan_parquet(uri, args)
 }
 
+///Returns polars type corresponding to literal type
+pub fn polars_type(dtype: &LiteralType) -> Result<DataType, PolarsError> {
+    let dtype = match dtype {
+        LiteralType::Boolean => DataType::Boolean,
+        LiteralType::Int(8) => DataType::Int8,
+        LiteralType::Int(16) => DataType::Int16,
+        LiteralType::Int(32) => DataType::Int32,
+        LiteralType::Int(_) => DataType::Int64,
+        LiteralType::UInt(8) => DataType::UInt8,
+        LiteralType::UInt(16) => DataType::UInt16,
+        LiteralType::UInt(32) => DataType::UInt32,
+        LiteralType::UInt(_) => DataType::UInt64,
+        LiteralType::Float(32) => DataType::Float32,
+        LiteralType::Float(_) => DataType::Float64,
+        LiteralType::Decimal { precision, scale } => match usize::try_from(*scale) {
+            Ok(scale) => DataType::Decimal(Some(*precision as usize), Some(scale)),
+            Err(_) => return Err(PolarsError::InvalidOperation(format!("{dtype}: Negative scale is not supported by polars").into())),
+        },
+        LiteralType::Date => DataType::Date,
+        LiteralType::Timestamp { unit, timezone } => {
+            let unit = match unit {
+                literal::TimeUnit::Second => return Err(PolarsError::InvalidOperation(format!("{dtype}: Seconds are not supported by polars").into())),
+                literal::TimeUnit::Millisecond => TimeUnit::Milliseconds,
+                literal::TimeUnit::Microsecond => TimeUnit::Microseconds,
+                literal::TimeUnit::Nanosecond => TimeUnit::Nanoseconds,
+            };
+            DataType::Datetime(unit, polars::prelude::TimeZone::opt_try_new(timezone.as_deref())?)
+        },
+        LiteralType::String => DataType::String,
+    };
+    Ok(dtype)
+}
+
+//Casts columns to user supplied types, failing on values that cannot be cast or on nulls in not nullable columns
+fn apply_schema(mut df: LazyFrame, schema: &[ColumnSchema]) -> Result<LazyFrame, PolarsError> {
+    for column in schema {
+        let mut expr = col(column.name.as_str()).strict_cast(polars_type(&column.dtype)?);
+        if !column.nullable {
+            let name = column.name.clone();
+            expr = expr.map(move |values| match values.null_count() {
+                0 => Ok(values),
+                count => Err(PolarsError::ComputeError(format!("Column '{name}' is not nullable, but contains {count} nulls").into())),
+            }, |_, field| Ok(field.clone()));
+        }
+        df = df.with_column(expr.alias(column.name.as_str()));
+    }
+    Ok(df)
+}
+
 ///Scan CSV through `path`
-pub fn scan_csv(path: &str) -> Result<LazyFrame, polars::error::PolarsError> {
+///
+///Columns within `schema` are parsed as specified type instead of inferring it
+pub fn scan_csv(path: &str, options: &CsvOptions, schema: &[ColumnSchema]) -> Result<LazyFrame, polars::error::PolarsError> {
     use LazyFileListReader;
 
+    if options.escape.is_some() {
+        return Err(PolarsError::InvalidOperation("CSV escape character is not supported by polars".into()));
+    }
+
+    let mut schema_overwrite = None;
+    for column in schema {
+        schema_overwrite.get_or_insert_with(Schema::default).with_column(column.name.as_str().into(), polars_type(&column.dtype)?);
+    }
+    let schema_overwrite = schema_overwrite.map(std::sync::Arc::new);
+
     let path = PlRefPath::new(path);
     LazyCsvReader::new(path)
         .with_glob(true)
         .with_cache(true)
-        .with_has_header(true)
+        .with_has_header(options.has_header)
+        .with_separator(options.delimiter)
+        .with_quote_char(Some(options.quote))
+        .with_null_values(options.null_value.as_deref().map(|null| polars::prelude::NullValues::AllColumnsSingle(null.into())))
+        .with_comment_prefix(options.comment.map(|comment| PlSmallStr::from((comment as char).to_string())))
+        .with_skip_rows(options.skip_rows)
+        .with_truncate_ragged_lines(options.truncate_ragged_lines)
+        .with_schema_overwrite(schema_overwrite)
+        .finish()
+}
+
+///Creates CSV writer options
+pub fn csv_writer_options(options: &CsvOptions) -> polars::prelude::CsvWriterOptions {
+    let serialize_options = polars::prelude::SerializeOptions {
+        separator: options.delimiter,
+        quote_char: options.quote,
+        null: options.null_value.clone().unwrap_or_default(),
+        ..Default::default()
+    };
+    polars::prelude::CsvWriterOptions {
+        include_header: options.has_header,
+        serialize_options: serialize_options.into(),
+        ..Default::default()
+    }
+}
+
+///Creates parquet writer options
+pub fn parquet_write_options(config: &ParquetWriteConfig) -> Result<polars::prelude::ParquetWriteOptions, PolarsError> {
+    use polars::prelude::{BrotliLevel, GzipLevel, ParquetCompression, StatisticsOptions, ZstdLevel};
+
+    if !config.dictionary {
+        return Err(PolarsError::InvalidOperation("Disabling parquet dictionary encoding is not supported by polars".into()));
+    }
+    if !config.bloom_filter_columns.is_empty() {
+        return Err(PolarsError::InvalidOperation("Parquet bloom filters are not supported by polars".into()));
+    }
+
+    let compression = match config.codec {
+        ParquetCodec::Uncompressed => ParquetCompression::Uncompressed,
+        ParquetCodec::Snappy => ParquetCompression::Snappy,
+        ParquetCodec::Gzip(level) => ParquetCompression::Gzip(level.map(GzipLevel::try_new).transpose()?),
+        ParquetCodec::Lz4 => ParquetCompression::Lz4Raw,
+        ParquetCodec::Zstd(level) => ParquetCompression::Zstd(level.map(ZstdLevel::try_new).transpose()?),
+        ParquetCodec::Brotli(level) => ParquetCompression::Brotli(level.map(BrotliLevel::try_new).transpose()?),
+    };
+    let statistics = match config.statistics {
+        None | Some(ParquetStatistics::Chunk) => StatisticsOptions::default(),
+        Some(ParquetStatistics::None) => StatisticsOptions::empty(),
+        Some(ParquetStatistics::Page) => return Err(PolarsError::InvalidOperation("Parquet page statistics are not supported by polars".into())),
+    };
+
+    Ok(polars::prelude::ParquetWriteOptions {
+        compression,
+        statistics,
+        row_group_size: config.row_group_size,
+        data_page_size: config.data_page_size,
+        ..Default::default()
+    })
+}
+
+///Scan newline delimited JSON through `path`
+pub fn scan_ndjson(path: &str) -> Result<LazyFrame, polars::error::PolarsError> {
+    use LazyFileListReader;
+
+    let path = PlRefPath::new(path);
+    polars::prelude::LazyJsonLineReader::new(path)
+        .with_glob(true)
         .finish()
 }
+
+///Reads JSON arrays within `path` (file, directory or URI prefix)
+///
+///JSON array cannot be scanned lazily, so every file is loaded into memory
+pub fn read_json(path: &str) -> Result<LazyFrame, polars::error::PolarsError> {
+    use polars::prelude::{JsonFormat, JsonReader, SerReader};
+
+    read_files(path, FileFormat::Json, |file| JsonReader::new(file).with_json_format(JsonFormat::Json).finish())
+}
+
+///Scan Arrow IPC file through `path`
+pub fn scan_ipc(path: &str) -> Result<LazyFrame, polars::error::PolarsError> {
+    let uri = PlRefPath::new(path);
+
+    let args = polars::prelude::ScanArgsIpc {
+        cache: true,
+        ..Default::default()
+    };
+    LazyFrame::scan_ipc(uri, args)
+}
+
+///Reads Arrow IPC streams within `path` (file, directory or URI prefix)
+///
+///IPC stream has no footer to scan lazily, so every file is loaded into memory
+pub fn read_ipc_stream(path: &str) -> Result<LazyFrame, polars::error::PolarsError> {
+    use polars::prelude::{IpcStreamReader, SerReader};
+
+    read_files(path, FileFormat::IpcStream, |file| IpcStreamReader::new(file).finish())
+}
+
+//Finds first data file within local `path` in order of names, skipping hidden files
+fn first_data_file(path: &std::path::Path) -> std::io::Result<Option<std::path::PathBuf>> {
+    if !path.is_dir() {
+        return Ok(Some(path.to_owned()));
+    }
+
+    let mut entries = std::fs::read_dir(path)?.map(|entry| entry.map(|entry| entry.path())).collect::<Result<Vec<_>, _>>()?;
+    entries.sort();
+    for entry in entries {
+        if entry.file_name().and_then(|name| name.to_str()).is_none_or(DetectedFormat::is_hidden) {
+            continue;
+        } else if entry.is_dir() {
+            if let Some(file) = first_data_file(&entry)? {
+                return Ok(Some(file));
+            }
+        } else {
+            return Ok(Some(entry));
+        }
+    }
+    Ok(None)
+}
+
+///Returns names of partition columns of data within `path`
+///
+///Delta table's partition columns are taken from its transaction log at `version`.
+///Otherwise these are hive partitions (`key=value` directories) of first data file
+pub fn partition_columns(path: &str, format: FileFormat, version: DeltaVersion) -> Result<Vec<String>, PolarsError> {
+    if let FileFormat::Delta = format {
+        return Ok(read_delta_snapshot(path, version)?.partition_columns());
+    }
+
+    let file = match path.contains("://") {
+        true => {
+            let store = RemoteStore::open(path)?;
+            let file = store.find(&store.prefix, |file| file.size > 0 && !store.is_hidden(&file.location))?;
+            file.map(|file| std::path::PathBuf::from(file.location.as_ref()))
+        },
+        false => first_data_file(std::path::Path::new(path))?,
+    };
+    let file = file.unwrap_or_else(|| std::path::PathBuf::from(path));
+
+    let mut columns = Vec::new();
+    for (key, _) in file.iter().flat_map(|component| component.to_str()).flat_map(|component| component.split_once('=')) {
+        if !columns.iter().any(|column| column == key) {
+            columns.push(key.to_owned());
+        }
+    }
+    Ok(columns)
+}
+
+///Detects format of data within `path` (file, directory or URI prefix) by probing first data file
+///
+///Returns `None` if format cannot be detected
+pub fn detect_format(path: &str) -> Result<Option<DetectedFormat>, PolarsError> {
+    use std::io::Read;
+
+    if path.contains("://") {
+        return detect_remote_format(path);
+    }
+    if std::path::Path::new(path).join(delta::LOG_DIR).is_dir() {
+        return Ok(Some(DetectedFormat {
+            format: FileFormat::Delta,
+            compression: None,
+        }));
+    }
+
+    let file = match first_data_file(std::path::Path::new(path))? {
+        Some(file) => file,
+        None => return Ok(None),
+    };
+    let mut head = Vec::with_capacity(DetectedFormat::MAGIC_LEN);
+    std::fs::File::open(&file)?.take(DetectedFormat::MAGIC_LEN as u64).read_to_end(&mut head)?;
+    let name = file.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+    Ok(DetectedFormat::detect(name, &head))
+}
+
+fn detect_remote_format(path: &str) -> Result<Option<DetectedFormat>, PolarsError> {
+    let store = RemoteStore::open(path)?;
+    if store.find(&store.prefix.child(delta::LOG_DIR), |_| true)?.is_some() {
+        return Ok(Some(DetectedFormat {
+            format: FileFormat::Delta,
+            compression: None,
+        }));
+    }
+
+    eprintln!(">{path}: Detecting format");
+    let file = match store.object(&store.prefix)? {
+        Some(file) => Some(file),
+        None => store.find(&store.prefix, |file| file.size > 0 && !store.is_hidden(&file.location))?,
+    };
+    let file = match file {
+        Some(file) => file,
+        None => return Ok(None),
+    };
+    let head = store.read(&file.location, Some(0..file.size.min(DetectedFormat::MAGIC_LEN as u64)))?;
+    eprintln!(">{}: Probed for format", file.location);
+    Ok(DetectedFormat::detect(file.location.filename().unwrap_or_default(), &head))
+}
+
+//Polars does not expose listing of its cloud storage, so remote paths are inspected via object_store
+//
+//Store is configured from environment variables (e.g. `AWS_REGION` or `GOOGLE_SERVICE_ACCOUNT`)
+struct RemoteStore {
+    runtime: tokio::runtime::Runtime,
+    store: Box<dyn object_store::ObjectStore>,
+    prefix: object_store::path::Path,
+}
+
+fn remote_error(location: &str, error: impl core::fmt::Display) -> PolarsError {
+    PolarsError::ComputeError(format!("{location}: {error}").into())
+}
+
+impl RemoteStore {
+    fn open(path: &str) -> Result<Self, PolarsError> {
+        let url = url::Url::parse(path).map_err(|error| remote_error(path, error))?;
+        let options = std::env::vars().map(|(key, value)| (key.to_ascii_lowercase(), value));
+        let (store, prefix) = object_store::parse_url_opts(&url, options).map_err(|error| remote_error(path, error))?;
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+        Ok(Self {
+            runtime,
+            store,
+            prefix,
+        })
+    }
+
+    //Returns whether `location` has hidden component relative to store's prefix
+    fn is_hidden(&self, location: &object_store::path::Path) -> bool {
+        match location.prefix_match(&self.prefix) {
+            Some(mut parts) => parts.any(|part| DetectedFormat::is_hidden(part.as_ref())),
+            None => false,
+        }
+    }
+
+    //Returns metadata of object at `location`, if it is object rather than prefix
+    fn object(&self, location: &object_store::path::Path) -> Result<Option<object_store::ObjectMeta>, PolarsError> {
+        let options = object_store::GetOptions {
+            head: true,
+            ..object_store::GetOptions::default()
+        };
+        match self.runtime.block_on(self.store.get_opts(location, options)) {
+            Ok(result) => Ok(Some(result.meta)),
+            Err(object_store::Error::NotFound { .. }) => Ok(None),
+            Err(error) => Err(remote_error(location.as_ref(), error)),
+        }
+    }
+
+    //Returns first object within `prefix` matching `predicate` in order of listing
+    fn find(&self, prefix: &object_store::path::Path, predicate: impl Fn(&object_store::ObjectMeta) -> bool) -> Result<Option<object_store::ObjectMeta>, PolarsError> {
+        use futures_util::TryStreamExt;
+
+        self.runtime.block_on(async {
+            let mut objects = self.store.list(Some(prefix));
+            while let Some(object) = objects.try_next().await? {
+                if predicate(&object) {
+                    return Ok(Some(object));
+                }
+            }
+            Ok(None)
+        }).map_err(|error: object_store::Error| remote_error(prefix.as_ref(), error))
+    }
+
+    //Lists every object within `prefix` in order of locations
+    fn list(&self, prefix: &object_store::path::Path) -> Result<Vec<object_store::ObjectMeta>, PolarsError> {
+        use futures_util::TryStreamExt;
+
+        let mut objects = self.runtime.block_on(self.store.list(Some(prefix)).try_collect::<Vec<_>>()).map_err(|error| remote_error(prefix.as_ref(), error))?;
+        objects.sort_by(|left, right| left.location.cmp(&right.location));
+        Ok(objects)
+    }
+
+    //Reads `range` of object at `location`, or whole object if `None`
+    fn read(&self, location: &object_store::path::Path, range: Option<core::ops::Range<u64>>) -> Result<Vec<u8>, PolarsError> {
+        let options = object_store::GetOptions {
+            range: range.map(object_store::GetRange::Bounded),
+            ..object_store::GetOptions::default()
+        };
+        self.runtime.block_on(async {
+            self.store.get_opts(location, options).await?.bytes().await
+        }).map(|bytes| bytes.to_vec()).map_err(|error| remote_error(location.as_ref(), error))
+    }
+}
+
+//Collects files with `extension` within `path` recursively in order of their names
+fn collect_files(path: &std::path::Path, extension: &str, files: &mut Vec<std::path::PathBuf>) -> std::io::Result<()> {
+    if !path.is_dir() {
+        files.push(path.to_owned());
+        return Ok(());
+    }
+
+    let mut entries = std::fs::read_dir(path)?.map(|entry| entry.map(|entry| entry.path())).collect::<Result<Vec<_>, _>>()?;
+    entries.sort();
+    for entry in entries {
+        if entry.is_dir() {
+            collect_files(&entry, extension, files)?;
+        } else if entry.extension().is_some_and(|ext| ext == extension) {
+            files.push(entry);
+        }
+    }
+    Ok(())
+}
+
+//Reads every file of `format` within `path` (file, directory or URI prefix) by `read`, attaching hive partitions of its path
+//
+//Used for formats that polars cannot scan, so files are listed here, including remote ones, and loaded into memory one by one
+fn read_files(path: &str, format: FileFormat, read: impl Fn(std::io::Cursor<Vec<u8>>) -> Result<polars::prelude::DataFrame, PolarsError>) -> Result<LazyFrame, PolarsError> {
+    use polars::prelude::{IntoLazy, UnionArgs};
+
+    if path.contains(['*', '?', '[']) {
+        return Err(PolarsError::InvalidOperation(format!("{path}: Wildcards are not supported for {} format by polars, specify directory instead", format.extension()).into()));
+    }
+
+    let frame = |file: &str, bytes: Vec<u8>| {
+        let mut df = read(std::io::Cursor::new(bytes)).map_err(|error| error.context(file.to_owned().into()))?.lazy();
+        let partitions = std::path::Path::new(file).parent().into_iter().flat_map(|parent| parent.iter()).flat_map(|component| component.to_str());
+        for (key, value) in partitions.flat_map(|component| component.split_once('=')) {
+            df = df.with_column(lit(value).alias(key));
+        }
+        Ok::<_, PolarsError>(df)
+    };
+
+    let mut frames = Vec::new();
+    if path.contains("://") {
+        let store = RemoteStore::open(path)?;
+        //Single object is accepted regardless of its extension
+        let files = match store.object(&store.prefix)? {
+            Some(file) => vec![file],
+            None => store.list(&store.prefix)?.into_iter().filter(|file| !store.is_hidden(&file.location) && file.location.extension() == Some(format.extension())).collect(),
+        };
+        for file in files {
+            frames.push(frame(file.location.as_ref(), store.read(&file.location, None)?)?);
+        }
+    } else {
+        let root = std::path::Path::new(path);
+        let mut files = Vec::new();
+        collect_files(root, format.extension(), &mut files)?;
+        files.retain(|file| !file.strip_prefix(root).unwrap_or(file).iter().any(|part| part.to_str().is_none_or(DetectedFormat::is_hidden)));
+        for file in files {
+            frames.push(frame(&file.display().to_string(), std::fs::read(&file)?)?);
+        }
+    }
+
+    if frames.is_empty() {
+        return Err(PolarsError::NoData(format!("{path}: No {} files available", format.extension()).into()));
+    }
+    polars::prelude::concat_lf_diagonal(frames, UnionArgs::default())
+}
+
+//Number of remote footers fetched at once
+const FOOTER_CONCURRENCY: usize = 32;
+
+///Reads footers of parquet files within `path` (file, directory or URI prefix)
+///
+///Local footers are read in parallel and remote ones are fetched concurrently, returning every file alongside its footer in order of paths
+pub fn read_parquet_footers(path: &str) -> Result<Vec<(String, parquet::FileMetadata)>, PolarsError> {
+    if path.contains(['*', '?', '[']) {
+        return Err(PolarsError::InvalidOperation(format!("{path}: Wildcards are not supported when reading parquet footers").into()));
+    } else if path.contains("://") {
+        return read_remote_parquet_footers(path);
+    }
+
+    let root = std::path::Path::new(path);
+    let mut files = Vec::new();
+    collect_files(root, "parquet", &mut files)?;
+    files.retain(|file| !file.strip_prefix(root).unwrap_or(file).iter().any(|part| part.to_str().is_none_or(DetectedFormat::is_hidden)));
+
+    let threads = std::thread::available_parallelism().map_or(1, usize::from);
+    let chunk_size = files.len().div_ceil(threads).max(1);
+    std::thread::scope(|scope| {
+        let tasks = files.chunks(chunk_size).map(|files| scope.spawn(move || {
+            files.iter().map(|file| read_parquet_footer(file).map(|footer| (file.display().to_string(), footer))).collect::<Result<Vec<_>, _>>()
+        })).collect::<Vec<_>>();
+
+        let mut footers = Vec::with_capacity(files.len());
+        for task in tasks {
+            match task.join() {
+                Ok(result) => footers.extend(result?),
+                Err(_) => return Err(PolarsError::ComputeError("Reading of parquet footers panicked".into())),
+            }
+        }
+        Ok(footers)
+    })
+}
+
+fn read_remote_parquet_footers(path: &str) -> Result<Vec<(String, parquet::FileMetadata)>, PolarsError> {
+    use futures_util::StreamExt;
+
+    let store = RemoteStore::open(path)?;
+    //Single object is accepted regardless of its extension
+    let files = match store.object(&store.prefix)? {
+        Some(file) => vec![file],
+        None => store.list(&store.prefix)?.into_iter().filter(|file| !store.is_hidden(&file.location) && file.location.extension() == Some("parquet")).collect(),
+    };
+
+    let footers = futures_util::stream::iter(files).map(|file| read_remote_parquet_footer(&*store.store, file)).buffered(FOOTER_CONCURRENCY).collect::<Vec<_>>();
+    store.runtime.block_on(footers).into_iter().collect()
+}
+
+async fn read_remote_parquet_footer(store: &dyn object_store::ObjectStore, file: object_store::ObjectMeta) -> Result<(String, parquet::FileMetadata), PolarsError> {
+    let location = file.location.to_string();
+    let error = |error: parquet::ParquetError| PolarsError::ComputeError(format!("{location}: {error}").into());
+    let read = async |range: core::ops::Range<u64>| {
+        let options = object_store::GetOptions {
+            range: Some(object_store::GetRange::Bounded(range)),
+            ..object_store::GetOptions::default()
+        };
+        match store.get_opts(&file.location, options).await {
+            Ok(result) => result.bytes().await.map_err(|error| remote_error(&location, error)),
+            Err(error) => Err(remote_error(&location, error)),
+        }
+    };
+
+    let tail_len = parquet::TAIL_LEN as u64;
+    if file.size < tail_len {
+        return Err(PolarsError::ComputeError(format!("{location}: File is too small to be parquet").into()));
+    }
+    let tail = read(file.size - tail_len..file.size).await?;
+    let len = parquet::footer_len(&tail).map_err(error)? as u64;
+    if len + tail_len > file.size {
+        return Err(PolarsError::ComputeError(format!("{location}: Footer length {len} exceeds file size").into()));
+    }
+
+    let footer = read(file.size - tail_len - len..file.size - tail_len).await?;
+    let footer = parquet::FileMetadata::parse(&footer).map_err(error)?;
+    Ok((location, footer))
+}
+
+fn read_parquet_footer(path: &std::path::Path) -> Result<parquet::FileMetadata, PolarsError> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    let error = |error: parquet::ParquetError| PolarsError::ComputeError(format!("{}: {error}", path.display()).into());
+    let mut file = std::fs::File::open(path)?;
+    let size = file.metadata()?.len();
+    if size < parquet::TAIL_LEN as u64 {
+        return Err(PolarsError::ComputeError(format!("{}: File is too small to be parquet", path.display()).into()));
+    }
+
+    let mut tail = [0; parquet::TAIL_LEN];
+    file.seek(SeekFrom::End(-(parquet::TAIL_LEN as i64)))?;
+    file.read_exact(&mut tail)?;
+    let len = parquet::footer_len(&tail).map_err(error)?;
+    if (len + parquet::TAIL_LEN) as u64 > size {
+        return Err(PolarsError::ComputeError(format!("{}: Footer length {len} exceeds file size", path.display()).into()));
+    }
+
+    let mut footer = vec![0; len];
+    file.seek(SeekFrom::End(-((len + parquet::TAIL_LEN) as i64)))?;
+    file.read_exact(&mut footer)?;
+    parquet::FileMetadata::parse(&footer).map_err(error)
+}
+
+///Computes statistics of every column in `df`
+///
+///Data is aggregated in a single streaming pass. Distinct count is approximate
+pub fn describe(mut df: LazyFrame) -> Result<Vec<format::ColumnStats>, PolarsError> {
+    let schema = df.collect_schema()?;
+    let mut aggregates = vec![polars::prelude::len().alias("rows")];
+    for (idx, (name, dtype)) in schema.iter().enumerate() {
+        let column = col(name.clone());
+        aggregates.push(column.clone().null_count().alias(format!("{idx}:nulls")));
+        //Nested values have no order
+        if !dtype.is_nested() {
+            aggregates.push(column.clone().min().alias(format!("{idx}:min")));
+            aggregates.push(column.clone().max().alias(format!("{idx}:max")));
+            aggregates.push(column.clone().approx_n_unique().alias(format!("{idx}:distinct")));
+        }
+        //Decimals are numeric as well, same as within datafusion
+        if dtype.is_numeric() {
+            aggregates.push(column.cast(DataType::Float64).mean().alias(format!("{idx}:mean")));
+        }
+    }
+
+    let result = df.select(aggregates).with_streaming(true).collect()?;
+    let value = |name: &str| result.column(name).ok().and_then(|column| column.get(0).ok()).and_then(format::polars::cell);
+    let rows = format::count(value("rows"));
+    Ok(schema.iter_names().enumerate().map(|(idx, name)| format::ColumnStats {
+        name: name.to_string(),
+        rows,
+        nulls: format::count(value(&format!("{idx}:nulls"))),
+        min: value(&format!("{idx}:min")),
+        max: value(&format!("{idx}:max")),
+        mean: match value(&format!("{idx}:mean")) {
+            Some(Value::Float(mean)) => Some(mean),
+            _ => None,
+        },
+        distinct: format::count(value(&format!("{idx}:distinct"))),
+    }).collect())
+}
+
+///Counts rows of `df` grouped by `group_by` columns in a single streaming pass
+///
+///Groups are sorted by their values
+pub fn count(df: LazyFrame, group_by: &[String]) -> Result<Vec<(Vec<format::Cell>, u64)>, PolarsError> {
+    use polars::prelude::len;
+
+    let df = match group_by.is_empty() {
+        true => df.select([len()]),
+        false => df.group_by(group_by.iter().map(|column| col(column.as_str())).collect::<Vec<_>>())
+                   .agg([len()])
+                   .sort(group_by.iter().map(String::as_str).collect::<Vec<_>>(), SortMultipleOptions::default()),
+    };
+    let df = df.with_streaming(true).collect()?;
+
+    let columns = df.columns();
+    let mut counts = Vec::with_capacity(df.height());
+    for idx in 0..df.height() {
+        let mut group = columns.iter().map(|column| column.get(idx).ok().and_then(format::polars::cell)).collect::<Vec<_>>();
+        //Count is the last column
+        let count = group.pop().and_then(format::count).unwrap_or(0);
+        counts.push((group, count));
+    }
+    Ok(counts)
+}
+
+///Result of comparing two data frames by key
+pub struct Diff {
+    ///Key columns
+    pub keys: Vec<String>,
+    ///Columns compared in both data frames
+    pub columns: Vec<String>,
+    ///Columns present in only one of data frames, which are not compared
+    pub skipped: Vec<String>,
+    ///Rows present in left data frame only
+    pub only_left: LazyFrame,
+    ///Rows present in right data frame only
+    pub only_right: LazyFrame,
+    //Changed rows: key columns followed by left value, right value and change mark of every compared column
+    changed: LazyFrame,
+    //Number of rows of every kind followed by number of changes of every compared column
+    counts: LazyFrame,
+}
+
+///Compares `left` and `right` by `keys`
+///
+///Rows must be unique by `keys`, otherwise error reports number of duplicate keys. Nulls are compared as equal values, except within keys
+pub fn diff(mut left: LazyFrame, mut right: LazyFrame, keys: &[String]) -> Result<Diff, PolarsError> {
+    use polars::prelude::{JoinArgs, JoinType};
+
+    let left_schema = left.collect_schema()?;
+    let right_schema = right.collect_schema()?;
+    for key in keys {
+        if !left_schema.contains(key) || !right_schema.contains(key) {
+            return Err(PolarsError::ColumnNotFound(format!("Key '{key}' must be present in both data").into()));
+        }
+    }
+
+    let mut columns = Vec::new();
+    let mut skipped = Vec::new();
+    for name in left_schema.iter_names().filter(|name| !keys.iter().any(|key| key == name.as_str())) {
+        match right_schema.contains(name) {
+            true => columns.push(name.to_string()),
+            false => skipped.push(name.to_string()),
+        }
+    }
+    skipped.extend(right_schema.iter_names().filter(|name| !left_schema.contains(name)).map(|name| name.to_string()));
+    if columns.is_empty() {
+        return Err(PolarsError::InvalidOperation("No columns to compare besides keys".into()));
+    }
+
+    //Duplicate key would match every row with the same key on the other side
+    let has_key = keys.iter().map(|key| col(key.as_str()).is_not_null()).reduce(|left, right| left.and(right)).unwrap_or(lit(true));
+    for (side, df) in [("Left", &left), ("Right", &right)] {
+        let duplicates = df.clone().filter(has_key.clone())
+                                   .group_by(keys.iter().map(|key| col(key.as_str())).collect::<Vec<_>>())
+                                   .agg([polars::prelude::len().alias(DUPLICATE_COLUMN)])
+                                   .filter(col(DUPLICATE_COLUMN).gt(lit(1)))
+                                   .select([polars::prelude::len()])
+                                   .with_streaming(true)
+                                   .collect()?;
+        let duplicates = duplicates.columns().first().and_then(|column| column.get(0).ok()).and_then(format::polars::cell);
+        match format::count(duplicates) {
+            Some(0) | None => (),
+            Some(duplicates) => return Err(PolarsError::ComputeError(format!("{side} data has {duplicates} duplicate keys").into())),
+        }
+    }
+
+    //Rename right columns to avoid conflicts within join
+    let right_name = |name: &str| format!("{name}{DIFF_RIGHT_SUFFIX}");
+    let renamed = right.clone().select(right_schema.iter_names().map(|name| col(name.clone()).alias(right_name(name))).collect::<Vec<_>>());
+    let left_on = keys.iter().map(|key| col(key.as_str())).collect::<Vec<_>>();
+    let right_on = keys.iter().map(|key| col(right_name(key))).collect::<Vec<_>>();
+
+    //Anti join outputs columns of its left side only, so there is no conflict
+    let only_left = left.clone().join(right.clone(), left_on.clone(), left_on.clone(), JoinArgs::new(JoinType::Anti));
+    let only_right = right.join(left.clone(), left_on.clone(), left_on.clone(), JoinArgs::new(JoinType::Anti));
+
+    let is_changed = |column: &String| col(column.as_str()).neq_missing(col(right_name(column)));
+    let is_row_changed = columns.iter().map(is_changed).reduce(|left, right| left.or(right)).unwrap_or(lit(false));
+    let mut select = keys.iter().map(|key| col(key.as_str())).collect::<Vec<_>>();
+    for column in columns.iter() {
+        select.push(col(column.as_str()));
+        select.push(col(right_name(column)));
+        select.push(is_changed(column).alias(format!("{column}{DIFF_CHANGED_SUFFIX}")));
+    }
+    let changed = left.clone().join(renamed.clone(), left_on.clone(), right_on.clone(), JoinArgs::new(JoinType::Inner)).filter(is_row_changed.clone()).select(select);
+
+    //Every kind of rows is counted within single full join
+    let left_present = col(DIFF_PRESENT_COLUMN);
+    let right_present = col(right_name(DIFF_PRESENT_COLUMN));
+    let both_present = left_present.clone().is_not_null().and(right_present.clone().is_not_null());
+    let mut counts = vec![
+        right_present.is_null().sum().alias(format::DiffKind::OnlyLeft.as_str()),
+        left_present.is_null().sum().alias(format::DiffKind::OnlyRight.as_str()),
+        both_present.clone().and(is_row_changed).sum().alias(format::DiffKind::Changed.as_str()),
+    ];
+    counts.extend(columns.iter().map(|column| both_present.clone().and(is_changed(column)).sum().alias(format!("{column}{DIFF_CHANGED_SUFFIX}"))));
+    let left = left.with_column(lit(true).alias(DIFF_PRESENT_COLUMN));
+    let renamed = renamed.with_column(lit(true).alias(right_name(DIFF_PRESENT_COLUMN)));
+    let counts = left.join(renamed, left_on, right_on, JoinArgs::new(JoinType::Full)).select(counts);
+
+    Ok(Diff {
+        keys: keys.to_vec(),
+        columns,
+        skipped,
+        only_left,
+        only_right,
+        changed,
+        counts,
+    })
+}
+
+impl Diff {
+    ///Returns changes of rows present in both: key columns followed by changed `column` with its `left` and `right` values
+    ///
+    ///Every value is formatted as text by [format::Cell], so changes are reported identically by both backends.
+    ///Changed rows are unpivoted batch by batch by streaming engine, so changes are never collected into memory
+    pub fn changes(&self) -> LazyFrame {
+        use polars::prelude::{DataFrame, OptFlags};
+
+        let names = format::DiffChanges::new(&self.keys, &self.columns).names;
+        let schema = std::sync::Arc::new(names.iter().map(|name| (PlSmallStr::from(name.as_str()), DataType::String)).collect::<Schema>());
+        let keys = self.keys.clone();
+        let columns = self.columns.clone();
+        let unpivot = move |df: DataFrame| -> Result<DataFrame, PolarsError> {
+            let mut changes = format::DiffChanges::new(&keys, &columns);
+            let columns = df.columns();
+            let mut row = Vec::with_capacity(columns.len());
+            for idx in 0..df.height() {
+                row.clear();
+                row.extend(columns.iter().map(|column| column.get(idx).ok().and_then(format::polars::cell)));
+                changes.push(&row);
+            }
+            let mut frame = DataFrame::empty();
+            for (name, values) in changes.names.iter().zip(changes.take()) {
+                frame.with_column(Series::new(name.as_str().into(), values))?;
+            }
+            Ok(frame)
+        };
+        //Output has its own schema, so neither predicates nor projections are pushed down through unpivot
+        self.changed.clone().map(unpivot, OptFlags::NEW_STREAMING, Some(std::sync::Arc::new(move |_: &Schema| Ok(schema.clone()))), Some("diff changes"))
+    }
+
+    ///Counts rows of every kind, including number of changes per column
+    pub fn summary(self) -> Result<Vec<format::DiffCount>, PolarsError> {
+        let df = self.counts.with_streaming(true).collect()?;
+        let columns = df.columns();
+        Ok(format::diff_summary(&self.columns, |idx| {
+            format::count(columns.get(idx).and_then(|column| column.get(0).ok()).and_then(format::polars::cell)).unwrap_or(0)
+        }))
+    }
+}
+
+///Reads Avro object container files within `path` (file, directory or URI prefix)
+///
+///Avro cannot be scanned lazily, so every file is loaded into memory using its embedded writer schema.
+///Partitions in path (`key=value`) are added as string columns
+pub fn read_avro(path: &str) -> Result<LazyFrame, polars::error::PolarsError> {
+    use polars::prelude::{AvroReader, SerReader};
+
+    read_files(path, FileFormat::Avro, |file| AvroReader::new(file).finish())
+}
+
+fn delta_error(error: delta::DeltaError) -> PolarsError {
+    PolarsError::ComputeError(error.to_string().into())
+}
+
+//Temporary column holding path of Delta data file to attach its partition values
+const DELTA_FILE_COLUMN: &str = "__mishka_delta_file";
+
+//Collects non-null strings of `list` at `idx`
+fn string_list(list: &Series, idx: usize) -> Result<Vec<String>, PolarsError> {
+    match list.list()?.get_as_series(idx) {
+        Some(values) => Ok(values.str()?.iter().flatten().map(str::to_owned).collect()),
+        None => Ok(Vec::new()),
+    }
+}
+
+//Collects entries of parquet map, which is read as list of key-value structs
+fn string_map(entries: &Series) -> Result<Vec<(String, Option<String>)>, PolarsError> {
+    let entries = entries.struct_()?;
+    let keys = entries.field_by_name("key")?;
+    let values = entries.field_by_name("value")?;
+    Ok(keys.str()?.iter().zip(values.str()?.iter()).flat_map(|(key, value)| key.map(|key| (key.to_owned(), value.map(str::to_owned)))).collect())
+}
+
+//Adds data files, protocol and metadata of Delta checkpoint to `snapshot`
+fn read_delta_checkpoint(checkpoint: Vec<u8>, snapshot: &mut delta::Snapshot) -> Result<(), PolarsError> {
+    use polars::prelude::{ParquetReader, SerReader};
+
+    //Every row holds single action, so columns of other actions are null
+    let df = ParquetReader::new(std::io::Cursor::new(checkpoint)).finish()?;
+    if let Ok(protocol) = df.column("protocol") {
+        let protocol = protocol.as_materialized_series().struct_()?.clone();
+        let versions = protocol.field_by_name("minReaderVersion")?.cast(&DataType::Int64)?;
+        let features = protocol.field_by_name("readerFeatures").ok();
+        for (idx, version) in versions.i64()?.iter().enumerate() {
+            if let Some(min_reader_version) = version {
+                snapshot.set_protocol(delta::Protocol {
+                    min_reader_version,
+                    reader_features: match features.as_ref() {
+                        Some(features) => string_list(features, idx)?,
+                        None => Vec::new(),
+                    },
+                });
+            }
+        }
+    }
+    if let Ok(metadata) = df.column("metaData") {
+        let metadata = metadata.as_materialized_series().struct_()?.clone();
+        let schemas = metadata.field_by_name("schemaString")?;
+        let partition_columns = metadata.field_by_name("partitionColumns")?;
+        let configuration = metadata.field_by_name("configuration").ok();
+        for (idx, schema_string) in schemas.str()?.iter().enumerate() {
+            if let Some(schema_string) = schema_string {
+                let configuration = match configuration.as_ref().and_then(|configuration| configuration.list().ok()?.get_as_series(idx)) {
+                    Some(entries) => string_map(&entries)?.into_iter().flat_map(|(key, value)| value.map(|value| (key, value))).collect(),
+                    None => Vec::new(),
+                };
+                snapshot.set_metadata(delta::Metadata {
+                    schema_string: schema_string.to_owned(),
+                    partition_columns: string_list(&partition_columns, idx)?,
+                    configuration,
+                });
+            }
+        }
+    }
+
+    let add = df.column("add")?.as_materialized_series().struct_()?.clone();
+    let paths = add.field_by_name("path")?;
+    let sizes = add.field_by_name("size")?.cast(&DataType::Int64)?;
+    let partition_values = add.field_by_name("partitionValues")?;
+    for ((path, size), values) in paths.str()?.iter().zip(sizes.i64()?.iter()).zip(partition_values.list()?.into_iter()) {
+        //Rows of other actions have no `add`
+        let path = match path {
+            Some(path) => path,
+            None => continue,
+        };
+
+        let partitions = match values {
+            Some(values) => string_map(&values)?,
+            None => Vec::new(),
+        };
+        snapshot.add(delta::DataFile::new(path, size.unwrap_or(0) as u64, partitions).map_err(delta_error)?);
+    }
+    Ok(())
+}
+
+//Replays transaction log of Delta table at `path` up to `version`
+fn read_delta_snapshot(path: &str, version: DeltaVersion) -> Result<delta::Snapshot, PolarsError> {
+    let is_remote = path.contains("://") && !path.starts_with("file://");
+    let root = path.strip_prefix("file://").unwrap_or(path).trim_end_matches('/');
+    let remote = match is_remote {
+        true => Some(RemoteStore::open(path)?),
+        false => None,
+    };
+    let log_dir = std::path::Path::new(root).join(delta::LOG_DIR);
+
+    let mut log = Vec::new();
+    match remote.as_ref() {
+        Some(store) => for file in store.list(&store.prefix.child(delta::LOG_DIR))? {
+            if let Some(name) = file.location.filename() {
+                log.push(delta::LogFile {
+                    name: name.to_owned(),
+                    last_modified: file.last_modified.timestamp_millis(),
+                });
+            }
+        },
+        None => for entry in std::fs::read_dir(&log_dir)? {
+            let entry = entry?;
+            let last_modified = entry.metadata()?.modified()?.duration_since(std::time::UNIX_EPOCH).map_or(0, |modified| modified.as_millis() as i64);
+            if let Some(name) = entry.file_name().to_str() {
+                log.push(delta::LogFile {
+                    name: name.to_owned(),
+                    last_modified,
+                });
+            }
+        },
+    }
+    let read_log = |name: &str| match remote.as_ref() {
+        Some(store) => store.read(&store.prefix.child(delta::LOG_DIR).child(name), None),
+        None => Ok(std::fs::read(log_dir.join(name))?),
+    };
+
+    let segment = delta::LogSegment::new(&log, version).map_err(delta_error)?;
+    eprintln!(">{path}: Reading Delta table version={}", segment.version);
+    let mut snapshot = delta::Snapshot::default();
+    for checkpoint in segment.checkpoint.iter() {
+        read_delta_checkpoint(read_log(checkpoint)?, &mut snapshot)?;
+    }
+    for commit in segment.commits.iter() {
+        let commit = String::from_utf8(read_log(commit)?).map_err(|error| PolarsError::ComputeError(format!("{commit}: {error}").into()))?;
+        snapshot.apply_commit(&commit).map_err(delta_error)?;
+    }
+    snapshot.check_support().map_err(delta_error)?;
+    Ok(snapshot)
+}
+
+///Reads Delta table from `path` (local or remote) at specified `version`
+///
+///Only files listed by transaction log are read within single scan. Columns missing in data files and partition values are typed as per table schema
+pub fn scan_delta(path: &str, version: DeltaVersion) -> Result<LazyFrame, polars::error::PolarsError> {
+    use polars::prelude::IntoLazy;
+
+    let is_remote = path.contains("://") && !path.starts_with("file://");
+    let root = path.strip_prefix("file://").unwrap_or(path).trim_end_matches('/');
+    let snapshot = read_delta_snapshot(path, version)?;
+
+    let table_schema = snapshot.schema().map_err(delta_error)?;
+    let partition_columns = snapshot.partition_columns();
+    let mut paths = Vec::new();
+    let mut partition_values = partition_columns.iter().map(|_| Vec::new()).collect::<Vec<_>>();
+    for file in snapshot.into_files() {
+        for (values, column) in partition_values.iter_mut().zip(partition_columns.iter()) {
+            values.push(file.partition_value(column).map(str::to_owned));
+        }
+        paths.push(match file.is_absolute() {
+            true if !is_remote => file.path.strip_prefix("file://").unwrap_or(&file.path).to_owned(),
+            true => file.path,
+            false => format!("{root}/{}", file.path),
+        });
+    }
+    let first_file = match paths.first() {
+        Some(first_file) => PlRefPath::new(first_file.as_str()),
+        None => return Err(PolarsError::NoData(format!("{path}: Delta table has no data files").into())),
+    };
+
+    let mut args = ScanArgsParquet {
+        use_statistics: true,
+        cache: true,
+        //Partition values are taken from the log
+        hive_options: polars::prelude::HiveOptions {
+            enabled: Some(false),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    //Columns added to the table later are missing in older files, so scan with schema of the table
+    let mut file_schema = LazyFrame::scan_parquet(first_file, args.clone())?.collect_schema()?.as_ref().clone();
+    for column in table_schema.iter().filter(|column| !partition_columns.contains(&column.name)) {
+        if let (None, Some(dtype)) = (file_schema.get(column.name.as_str()), column.dtype.as_ref()) {
+            file_schema.with_column(column.name.as_str().into(), polars_type(dtype)?);
+        }
+    }
+    args.schema = Some(std::sync::Arc::new(file_schema));
+    args.allow_missing_columns = true;
+    if !partition_columns.is_empty() {
+        args.include_file_paths = Some(PlSmallStr::from_static(DELTA_FILE_COLUMN));
+    }
+
+    let sources = polars::prelude::ScanSources::Paths(paths.iter().map(|path| PlRefPath::new(path.as_str())).collect::<Vec<_>>().into());
+    let mut df = LazyFrame::scan_parquet_sources(sources, args)?;
+    if !partition_columns.is_empty() {
+        //Join values to every file by its path, which is recorded as it was passed to scan
+        let mut values = Series::new(PlSmallStr::from_static(DELTA_FILE_COLUMN), paths).into_frame();
+        for (column, column_values) in partition_columns.iter().zip(partition_values) {
+            values.with_column(Series::new(column.as_str().into(), column_values))?;
+        }
+        df = df.left_join(values.lazy(), col(DELTA_FILE_COLUMN), col(DELTA_FILE_COLUMN)).drop(polars::lazy::dsl::Selector::ByName {
+            names: [PlSmallStr::from_static(DELTA_FILE_COLUMN)].into(),
+            strict: true,
+        });
+
+        let partition_schema = table_schema.into_iter().filter(|column| partition_columns.contains(&column.name)).flat_map(|column| column.dtype.map(|dtype| ColumnSchema {
+            name: column.name,
+            dtype,
+            nullable: true,
+        })).collect::<Vec<_>>();
+        df = apply_schema(df, &partition_schema)?;
+    }
+    Ok(df)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use polars::prelude::{DataFrame, IntoLazy};
+
+    //Formats every row of `df` as text, with empty string for null
+    fn rows(df: &DataFrame) -> Vec<Vec<String>> {
+        let columns = df.columns();
+        (0..df.height()).map(|idx| {
+            columns.iter().map(|column| column.get(idx).ok().and_then(format::polars::cell).map_or(String::new(), |value| value.to_string())).collect()
+        }).collect()
+    }
+
+    #[test]
+    fn should_diff_by_key() {
+        let left = polars::df!("id" => [Some(1i64), Some(2), Some(3), None], "name" => [Some("a"), Some("b"), None, Some("x")], "left_only" => [1i32, 2, 3, 4]).expect("left").lazy();
+        let right = polars::df!("id" => [Some(2i64), Some(3), Some(4), None], "name" => [Some("c"), None, Some("d"), Some("x")]).expect("right").lazy();
+        let diff = diff(left, right, &["id".to_owned()]).expect("to compare");
+        assert_eq!(diff.columns, ["name"]);
+        assert_eq!(diff.skipped, ["left_only"]);
+
+        //Null keys never match
+        let only_left = diff.only_left.clone().sort(["id"], SortMultipleOptions::default()).collect().expect("only left");
+        assert_eq!(rows(&only_left), [["", "x", "4"], ["1", "a", "1"]]);
+        let only_right = diff.only_right.clone().sort(["id"], SortMultipleOptions::default()).collect().expect("only right");
+        assert_eq!(rows(&only_right), [["", "x"], ["4", "d"]]);
+        //Nulls are equal within values
+        let changes = diff.changes().with_streaming(true).collect().expect("changes");
+        assert_eq!(rows(&changes), [["2", "name", "b", "c"]]);
+
+        let summary = diff.summary().expect("summary");
+        let summary = summary.iter().map(|count| (count.kind, count.column.as_deref(), count.rows)).collect::<Vec<_>>();
+        assert_eq!(summary, [
+            (format::DiffKind::OnlyLeft, None, 2),
+            (format::DiffKind::OnlyRight, None, 2),
+            (format::DiffKind::Changed, None, 1),
+            (format::DiffKind::Changed, Some("name"), 1),
+        ]);
+    }
+
+    //Formats every group of `counts` as text, with empty string for null
+    fn groups(counts: &[(Vec<format::Cell>, u64)]) -> Vec<(Vec<String>, u64)> {
+        counts.iter().map(|(group, rows)| (group.iter().map(|cell| cell.as_ref().map_or(String::new(), |value| value.to_string())).collect(), *rows)).collect()
+    }
+
+    #[test]
+    fn should_count_footers_as_scan() {
+        let root = std::env::temp_dir().join(format!("mishka-polars-count-{}", std::process::id()));
+        let partitions = [("2024", "New%20York", 3), ("__HIVE_DEFAULT_PARTITION__", "Oslo", 2), ("9", "Oslo", 1), ("10", "Oslo", 4)];
+        for (year, city, rows) in partitions {
+            let dir = root.join(format!("year={year}")).join(format!("city={city}"));
+            std::fs::create_dir_all(&dir).expect("partition dir");
+            let mut df = polars::df!("id" => (0..rows).collect::<Vec<i64>>()).expect("partition data");
+            let file = std::fs::File::create(dir.join("part-0.parquet")).expect("parquet file");
+            polars::prelude::ParquetWriter::new(file).finish(&mut df).expect("to write parquet");
+        }
+        let path = root.to_str().expect("utf-8 path");
+        let group_by = ["year".to_owned(), "city".to_owned()];
+
+        let mut df = scan_parquet(path, &[]).expect("scan");
+        let types = literal_types(&mut df, &group_by).expect("schema").expect("literal types");
+        let footers = read_parquet_footers(path).expect("footers");
+        let footer_counts = parquet::count_rows(footers.iter().map(|(path, footer)| (path.as_str(), footer)), &group_by, &types).expect("footer counts");
+        let scan_counts = count(df, &group_by).expect("scan counts");
+        std::fs::remove_dir_all(&root).expect("to clean up");
+
+        assert_eq!(groups(&footer_counts), groups(&scan_counts));
+        assert_eq!(groups(&footer_counts), [
+            (vec![String::new(), "Oslo".to_owned()], 2),
+            (vec!["9".to_owned(), "Oslo".to_owned()], 1),
+            (vec!["10".to_owned(), "Oslo".to_owned()], 4),
+            (vec!["2024".to_owned(), "New York".to_owned()], 3),
+        ]);
+    }
+
+    #[test]
+    fn should_reject_duplicate_keys() {
+        let left = polars::df!("id" => [Some(1i64), Some(1), Some(2), Some(2), None, None], "name" => ["a", "b", "c", "d", "e", "f"]).expect("left").lazy();
+        let right = polars::df!("id" => [Some(1i64), None, None], "name" => ["a", "b", "c"]).expect("right").lazy();
+        let error = diff(left.clone(), right.clone(), &["id".to_owned()]).err().expect("duplicate keys");
+        assert!(error.to_string().contains("Left data has 2 duplicate keys"), "{error}");
+        //Rows with null keys are never matched, so they are not duplicates
+        assert!(diff(right, left, &["id".to_owned()]).err().expect("duplicate keys").to_string().contains("Right data has 2 duplicate keys"));
+    }
+}